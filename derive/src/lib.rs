@@ -0,0 +1,117 @@
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Fields, LitStr};
+
+/// Derives `database::FromRow`, reading each field from the column of the
+/// same name. Use `#[from_row(rename = "column")]` to read another column.
+/// `Option` fields accept NULL, any other field fails to decode on NULL.
+#[proc_macro_derive(FromRow, attributes(from_row))]
+pub fn derive_from_row(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input)
+        .unwrap_or_else(|e| e.to_compile_error())
+        .into()
+}
+
+fn expand(input: DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    &input.ident,
+                    "FromRow requires a struct with named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(syn::Error::new_spanned(
+                &input.ident,
+                "FromRow can only be derived for structs",
+            ))
+        }
+    };
+
+    let assignments = fields
+        .iter()
+        .map(|field| {
+            let ident = field.ident.as_ref().unwrap();
+            let column = column_name(field)?.unwrap_or_else(|| ident.to_string());
+            Ok(quote! { #ident: row.get(#column)? })
+        })
+        .collect::<syn::Result<Vec<_>>>()?;
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::achiet::database::FromRow for #name #ty_generics #where_clause {
+            fn from_row(
+                row: &::achiet::database::Row,
+            ) -> ::std::result::Result<Self, ::achiet::database::Error> {
+                ::std::result::Result::Ok(Self {
+                    #(#assignments,)*
+                })
+            }
+        }
+    })
+}
+
+fn column_name(field: &syn::Field) -> syn::Result<Option<String>> {
+    let mut rename = None;
+    for attr in field.attrs.iter().filter(|a| a.path().is_ident("from_row")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("rename") {
+                rename = Some(meta.value()?.parse::<LitStr>()?.value());
+                Ok(())
+            } else {
+                Err(meta.error("unsupported from_row attribute"))
+            }
+        })?;
+    }
+    Ok(rename)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use syn::parse_quote;
+
+    #[test]
+    fn expand_columns() {
+        let tokens = expand(parse_quote! {
+            struct Item {
+                id: String,
+                #[from_row(rename = "item_count")]
+                count: i32,
+                label: Option<String>,
+            }
+        })
+        .unwrap()
+        .to_string();
+        assert!(tokens.contains(r#"id : row . get ("id") ?"#));
+        assert!(tokens.contains(r#"count : row . get ("item_count") ?"#));
+        assert!(tokens.contains(r#"label : row . get ("label") ?"#));
+    }
+
+    #[test]
+    fn expand_errors() {
+        let error = |input: DeriveInput| expand(input).unwrap_err().to_string();
+        assert_eq!(
+            error(parse_quote! { enum Item { A } }),
+            "FromRow can only be derived for structs"
+        );
+        assert_eq!(
+            error(parse_quote! { struct Item(String); }),
+            "FromRow requires a struct with named fields"
+        );
+        assert_eq!(
+            error(parse_quote! {
+                struct Item {
+                    #[from_row(skip)]
+                    id: String,
+                }
+            }),
+            "unsupported from_row attribute"
+        );
+    }
+}
@@ -51,10 +51,10 @@ atlasfmt() {
 
 sqlite() {
   dbfile="sqlite://${1:-$SQLITE_FILE}"
-  atlas schema apply \
-    --url "$dbfile" \
-    --to "file://schema.hcl" \
-    --var tenant=main
+  run_service \
+    -e "HOME=/tmp" \
+    -e "DATABASE_URL=$dbfile" \
+    rust cargo run -- migrate
 }
 
 [ $# -gt 0 ] && cmd="$1" && shift
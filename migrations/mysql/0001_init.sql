@@ -0,0 +1,73 @@
+-- InnoDB limits index keys to 3072 bytes, so the long utf8mb4 columns are
+-- indexed by prefix. Foreign key names are schema-wide, hence the table prefix.
+CREATE TABLE `user` (
+  `id` varchar(40) NOT NULL,
+  `username` varchar(200) NOT NULL,
+  `password` varchar(200) NOT NULL,
+  `email` varchar(200) NULL,
+  `role` varchar(50) NOT NULL,
+  `created_at` datetime NULL,
+  `updated_at` datetime NULL,
+  PRIMARY KEY (`id`)
+);
+CREATE UNIQUE INDEX `idx_user_username` ON `user` (`username`);
+
+CREATE TABLE `resource` (
+  `id` varchar(40) NOT NULL,
+  `url` varchar(1000) NOT NULL,
+  `protocol` varchar(50) NOT NULL,
+  `host` varchar(200) NOT NULL,
+  `path` varchar(1000) NULL,
+  `query` varchar(1000) NULL,
+  `created_at` datetime NULL,
+  `updated_at` datetime NULL,
+  PRIMARY KEY (`id`)
+);
+CREATE UNIQUE INDEX `idx_resource_url` ON `resource` (`url`(768));
+
+CREATE TABLE `bookmark` (
+  `id` varchar(40) NOT NULL,
+  `user_id` varchar(40) NOT NULL,
+  `title` varchar(250) NULL,
+  `url` varchar(1000) NULL,
+  `description` text NULL,
+  `resource_id` varchar(40) NULL,
+  `created_at` datetime NULL,
+  `updated_at` datetime NULL,
+  PRIMARY KEY (`id`),
+  CONSTRAINT `bookmark_user_id` FOREIGN KEY (`user_id`) REFERENCES `user` (`id`) ON UPDATE NO ACTION ON DELETE CASCADE,
+  CONSTRAINT `bookmark_resource_id` FOREIGN KEY (`resource_id`) REFERENCES `resource` (`id`) ON UPDATE NO ACTION ON DELETE CASCADE
+);
+CREATE INDEX `idx_bookmark_user_id` ON `bookmark` (`user_id`);
+CREATE INDEX `idx_bookmark_resource_id` ON `bookmark` (`resource_id`);
+
+CREATE TABLE `tag` (
+  `id` varchar(40) NOT NULL,
+  `path` varchar(1000) NOT NULL,
+  `prefix` varchar(1000) NOT NULL,
+  `name` varchar(200) NOT NULL,
+  `label` varchar(200) NULL,
+  `parent_id` varchar(40) NULL,
+  `depth` int unsigned NOT NULL,
+  `value_type` varchar(20) NULL,
+  `user_id` varchar(40) NOT NULL,
+  `created_at` datetime NULL,
+  `updated_at` datetime NULL,
+  PRIMARY KEY (`id`),
+  CONSTRAINT `tag_user_id` FOREIGN KEY (`user_id`) REFERENCES `user` (`id`) ON UPDATE NO ACTION ON DELETE CASCADE,
+  CONSTRAINT `tag_parent_id` FOREIGN KEY (`parent_id`) REFERENCES `tag` (`id`) ON UPDATE NO ACTION ON DELETE CASCADE
+);
+CREATE INDEX `idx_tag_user_id` ON `tag` (`user_id`);
+CREATE UNIQUE INDEX `idx_tag_user_unique` ON `tag` (`path`(700), `user_id`);
+
+CREATE TABLE `tagged_bookmark` (
+  `id` varchar(40) NOT NULL,
+  `ref_id` varchar(40) NOT NULL,
+  `tag_id` varchar(40) NOT NULL,
+  `value` text NULL,
+  PRIMARY KEY (`id`),
+  CONSTRAINT `tagged_bookmark_ref_id` FOREIGN KEY (`ref_id`) REFERENCES `bookmark` (`id`) ON UPDATE NO ACTION ON DELETE CASCADE,
+  CONSTRAINT `tagged_bookmark_tag_id` FOREIGN KEY (`tag_id`) REFERENCES `tag` (`id`) ON UPDATE NO ACTION ON DELETE CASCADE
+);
+CREATE INDEX `idx_tagged_bookmark_ref_id` ON `tagged_bookmark` (`ref_id`);
+CREATE INDEX `idx_tagged_bookmark_tag_id` ON `tagged_bookmark` (`tag_id`);
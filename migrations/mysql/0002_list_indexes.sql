@@ -0,0 +1,6 @@
+-- Listings page through a user's rows in id order, the new indexes are
+-- created first so the user_id foreign keys keep an index
+CREATE INDEX `idx_bookmark_user_id_id` ON `bookmark` (`user_id`, `id`);
+DROP INDEX `idx_bookmark_user_id` ON `bookmark`;
+CREATE INDEX `idx_tag_user_id_id` ON `tag` (`user_id`, `id`);
+DROP INDEX `idx_tag_user_id` ON `tag`;
@@ -0,0 +1,6 @@
+-- Outcome of the last link check of each resource
+ALTER TABLE `resource` ADD COLUMN `status_code` int NULL;
+ALTER TABLE `resource` ADD COLUMN `final_url` text NULL;
+ALTER TABLE `resource` ADD COLUMN `last_checked_at` datetime NULL;
+ALTER TABLE `resource` ADD COLUMN `last_ok_at` datetime NULL;
+CREATE INDEX `idx_resource_last_checked_at` ON `resource` (`last_checked_at`);
@@ -0,0 +1,12 @@
+-- Offline copy of each resource
+CREATE TABLE `archive` (
+  `id` varchar(40) NOT NULL,
+  `resource_id` varchar(40) NOT NULL,
+  `storage_key` varchar(200) NOT NULL,
+  `content_type` varchar(100) NOT NULL,
+  `size` int NOT NULL,
+  `created_at` datetime NULL,
+  PRIMARY KEY (`id`),
+  CONSTRAINT `archive_resource_id` FOREIGN KEY (`resource_id`) REFERENCES `resource` (`id`) ON UPDATE NO ACTION ON DELETE CASCADE
+);
+CREATE UNIQUE INDEX `idx_archive_resource_id` ON `archive` (`resource_id`);
@@ -0,0 +1,12 @@
+-- Readable content of each resource
+CREATE TABLE `article` (
+  `id` varchar(40) NOT NULL,
+  `resource_id` varchar(40) NOT NULL,
+  `text` mediumtext NOT NULL,
+  `html` mediumtext NOT NULL,
+  `word_count` int NOT NULL,
+  `created_at` datetime NULL,
+  PRIMARY KEY (`id`),
+  CONSTRAINT `article_resource_id` FOREIGN KEY (`resource_id`) REFERENCES `resource` (`id`) ON UPDATE NO ACTION ON DELETE CASCADE
+);
+CREATE UNIQUE INDEX `idx_article_resource_id` ON `article` (`resource_id`);
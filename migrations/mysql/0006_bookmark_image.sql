@@ -0,0 +1,2 @@
+-- Preview image of each bookmark
+ALTER TABLE `bookmark` ADD COLUMN `image_url` varchar(1000) NULL;
@@ -0,0 +1,4 @@
+-- Title, description and preview image of each resource, from its page
+ALTER TABLE `resource` ADD COLUMN `title` varchar(250) NULL;
+ALTER TABLE `resource` ADD COLUMN `description` text NULL;
+ALTER TABLE `resource` ADD COLUMN `image_url` varchar(1000) NULL;
@@ -0,0 +1,7 @@
+-- Resources are matched by the canonical form of their url, and keep the url
+-- they were first bookmarked with to request. Existing resources were stored
+-- by their canonical url, which becomes their key as is.
+ALTER TABLE `resource` ADD COLUMN `canonical_url` varchar(1000) NULL;
+UPDATE `resource` SET `canonical_url` = `url`;
+DROP INDEX `idx_resource_url` ON `resource`;
+CREATE UNIQUE INDEX `idx_resource_canonical_url` ON `resource` (`canonical_url`(768));
@@ -0,0 +1,6 @@
+-- Outcome of the last link check of each resource
+ALTER TABLE "resource" ADD COLUMN "status_code" integer NULL;
+ALTER TABLE "resource" ADD COLUMN "final_url" text NULL;
+ALTER TABLE "resource" ADD COLUMN "last_checked_at" timestamptz NULL;
+ALTER TABLE "resource" ADD COLUMN "last_ok_at" timestamptz NULL;
+CREATE INDEX "idx_resource_last_checked_at" ON "resource" ("last_checked_at");
@@ -0,0 +1,3 @@
+-- When a check of each resource was last dispatched, so that it is not
+-- dispatched again while it waits in the queue
+ALTER TABLE "resource" ADD COLUMN "check_queued_at" timestamptz NULL;
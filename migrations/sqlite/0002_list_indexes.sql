@@ -0,0 +1,5 @@
+-- Listings page through a user's rows in id order
+CREATE INDEX "idx_bookmark_user_id_id" ON "bookmark" ("user_id", "id");
+DROP INDEX "idx_bookmark_user_id";
+CREATE INDEX "idx_tag_user_id_id" ON "tag" ("user_id", "id");
+DROP INDEX "idx_tag_user_id";
@@ -0,0 +1,12 @@
+-- Metadata last fetched for each resource
+CREATE TABLE "metatag" (
+  "id" varchar(40) NOT NULL,
+  "resource_id" varchar(40) NOT NULL,
+  "data" text NULL,
+  "etag" varchar(200) NULL,
+  "last_modified" varchar(100) NULL,
+  "fetched_at" datetime NULL,
+  PRIMARY KEY ("id"),
+  CONSTRAINT "resource_id" FOREIGN KEY ("resource_id") REFERENCES "resource" ("id") ON UPDATE NO ACTION ON DELETE CASCADE
+);
+CREATE UNIQUE INDEX "idx_metatag_resource_id" ON "metatag" ("resource_id");
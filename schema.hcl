@@ -17,7 +17,7 @@ table "user" {
     null = false
   }
   column "password" {
-    type = varchar(60)
+    type = varchar(200)
     null = false
   }
   column "email" {
@@ -72,6 +72,10 @@ table "bookmark" {
     type = varchar(40)
     null = true
   }
+  column "image_url" {
+    type = varchar(1000)
+    null = true
+  }
   column "created_at" {
     type = datetime
     null = true
@@ -84,8 +88,8 @@ table "bookmark" {
   primary_key {
     columns = [column.id]
   }
-  index "idx_bookmark_user_id" {
-    columns = [column.user_id]
+  index "idx_bookmark_user_id_id" {
+    columns = [column.user_id, column.id]
   }
   index "idx_bookmark_resource_id" {
     columns = [column.resource_id]
@@ -156,8 +160,8 @@ table "tag" {
   primary_key {
     columns = [column.id]
   }
-  index "idx_tag_user_id" {
-    columns = [column.user_id]
+  index "idx_tag_user_id_id" {
+    columns = [column.user_id, column.id]
   }
   index "idx_tag_user_unique" {
     columns = [column.path, column.user_id]
@@ -255,12 +259,180 @@ table "resource" {
     type = datetime
     null = true
   }
+  column "status_code" {
+    type = int
+    null = true
+  }
+  column "final_url" {
+    type = text
+    null = true
+  }
+  column "last_checked_at" {
+    type = datetime
+    null = true
+  }
+  column "last_ok_at" {
+    type = datetime
+    null = true
+  }
+  column "check_queued_at" {
+    type = datetime
+    null = true
+  }
+  column "title" {
+    type = varchar(250)
+    null = true
+  }
+  column "description" {
+    type = text
+    null = true
+  }
+  column "image_url" {
+    type = varchar(1000)
+    null = true
+  }
+  column "canonical_url" {
+    type = varchar(1000)
+    null = true
+  }
+
+  primary_key {
+    columns = [column.id]
+  }
+  index "idx_resource_canonical_url" {
+    columns = [column.canonical_url]
+    unique  = true
+  }
+  index "idx_resource_last_checked_at" {
+    columns = [column.last_checked_at]
+  }
+}
+
+table "archive" {
+  schema = schema.achiet
+  column "id" {
+    type = varchar(40)
+    null = false
+  }
+  column "resource_id" {
+    type = varchar(40)
+    null = false
+  }
+  column "storage_key" {
+    type = varchar(200)
+    null = false
+  }
+  column "content_type" {
+    type = varchar(100)
+    null = false
+  }
+  column "size" {
+    type = int
+    null = false
+  }
+  column "created_at" {
+    type = datetime
+    null = true
+  }
 
   primary_key {
     columns = [column.id]
   }
-  index "idx_resource_url" {
-    columns = [column.url]
+  index "idx_archive_resource_id" {
+    columns = [column.resource_id]
     unique  = true
   }
+
+  foreign_key "resource_id" {
+    columns     = [column.resource_id]
+    ref_columns = [table.resource.column.id]
+    on_delete   = CASCADE
+    on_update   = NO_ACTION
+  }
+}
+
+table "article" {
+  schema = schema.achiet
+  column "id" {
+    type = varchar(40)
+    null = false
+  }
+  column "resource_id" {
+    type = varchar(40)
+    null = false
+  }
+  column "text" {
+    type = mediumtext
+    null = false
+  }
+  column "html" {
+    type = mediumtext
+    null = false
+  }
+  column "word_count" {
+    type = int
+    null = false
+  }
+  column "created_at" {
+    type = datetime
+    null = true
+  }
+
+  primary_key {
+    columns = [column.id]
+  }
+  index "idx_article_resource_id" {
+    columns = [column.resource_id]
+    unique  = true
+  }
+
+  foreign_key "resource_id" {
+    columns     = [column.resource_id]
+    ref_columns = [table.resource.column.id]
+    on_delete   = CASCADE
+    on_update   = NO_ACTION
+  }
+}
+
+table "metatag" {
+  schema = schema.achiet
+  column "id" {
+    type = varchar(40)
+    null = false
+  }
+  column "resource_id" {
+    type = varchar(40)
+    null = false
+  }
+  column "data" {
+    type = mediumtext
+    null = true
+  }
+  column "etag" {
+    type = varchar(200)
+    null = true
+  }
+  column "last_modified" {
+    type = varchar(100)
+    null = true
+  }
+  column "fetched_at" {
+    type = datetime
+    null = true
+  }
+
+  primary_key {
+    columns = [column.id]
+  }
+  index "idx_metatag_resource_id" {
+    columns = [column.resource_id]
+    unique  = true
+  }
+
+  foreign_key "resource_id" {
+    columns     = [column.resource_id]
+    ref_columns = [table.resource.column.id]
+    on_delete   = CASCADE
+    on_update   = NO_ACTION
+  }
 }
@@ -0,0 +1,135 @@
+use super::{
+    bookmark::{find_bookmark_one, SearchBookmark},
+    state::AuthenticationState,
+};
+use crate::{
+    app::AppState,
+    archive::Storage,
+    model::{Article, Bookmark, Resource},
+    repo,
+    taskqueue::Task,
+};
+use axum::{
+    extract::{Extension, Json, Path},
+    http::{header, StatusCode},
+    response::IntoResponse,
+};
+use chrono::{offset::Utc, DateTime};
+use serde::Serialize;
+
+/// Archived pages are served from the api origin, so they are sandboxed and
+/// may only load what was inlined into them.
+const CONTENT_SECURITY_POLICY: &str =
+    "sandbox; default-src 'none'; img-src data:; style-src 'unsafe-inline' data:; font-src data:";
+
+async fn find_resource(
+    app_state: &AppState,
+    auth: &AuthenticationState,
+    bookmark_id: String,
+) -> Result<Resource, (StatusCode, String)> {
+    let bookmark: Bookmark = find_bookmark_one(
+        app_state,
+        &app_state.database().connection(),
+        SearchBookmark {
+            id: Some(bookmark_id),
+            user_id: auth.user_id(),
+            ..Default::default()
+        },
+    )
+    .await?;
+    let resource_id = bookmark
+        .resource_id
+        .ok_or((StatusCode::BAD_REQUEST, "bookmark has no url".to_string()))?;
+    Ok(
+        repo::resource::find_resource(app_state, &app_state.database().connection(), resource_id)
+            .await?,
+    )
+}
+
+/// Serves the archived copy of the page of a bookmark.
+pub async fn find(
+    Extension(app_state): Extension<AppState>,
+    Extension(auth): Extension<AuthenticationState>,
+    Path(bookmark_id): Path<String>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let resource = find_resource(&app_state, &auth, bookmark_id).await?;
+    let archive =
+        repo::archive::find_archive(&app_state, &app_state.database().connection(), resource.id)
+            .await?;
+    let html = app_state
+        .archive_storage()
+        .get(&archive.storage_key)
+        .await
+        .map_err(|e| {
+            log::error!("{}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, "".to_string())
+        })?
+        .ok_or((StatusCode::NOT_FOUND, "".to_string()))?;
+
+    Ok((
+        [
+            (
+                header::CONTENT_TYPE,
+                format!("{}; charset=utf-8", archive.content_type),
+            ),
+            (
+                header::CONTENT_SECURITY_POLICY,
+                CONTENT_SECURITY_POLICY.to_string(),
+            ),
+        ],
+        html,
+    ))
+}
+
+#[derive(Serialize)]
+pub struct ArticleResponse {
+    pub text: String,
+    pub html: String,
+    pub word_count: i32,
+    pub reading_minutes: i32,
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+impl From<Article> for ArticleResponse {
+    fn from(article: Article) -> Self {
+        Self {
+            reading_minutes: article.reading_minutes(),
+            text: article.text,
+            html: article.html,
+            word_count: article.word_count,
+            created_at: article.created_at,
+        }
+    }
+}
+
+/// The readable content of the page of a bookmark, found when it was archived.
+pub async fn find_article(
+    Extension(app_state): Extension<AppState>,
+    Extension(auth): Extension<AuthenticationState>,
+    Path(bookmark_id): Path<String>,
+) -> Result<Json<ArticleResponse>, (StatusCode, String)> {
+    let resource = find_resource(&app_state, &auth, bookmark_id).await?;
+    let article =
+        repo::article::find_article(&app_state, &app_state.database().connection(), resource.id)
+            .await?;
+
+    Ok(Json(ArticleResponse::from(article)))
+}
+
+/// Queues archiving the page of a bookmark again.
+pub async fn create(
+    Extension(app_state): Extension<AppState>,
+    Extension(auth): Extension<AuthenticationState>,
+    Path(bookmark_id): Path<String>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let resource = find_resource(&app_state, &auth, bookmark_id).await?;
+    app_state
+        .dispatcher()
+        .dispatch(Task::ArchivePage {
+            resource: Box::new(resource),
+            refresh: true,
+        })
+        .map_err(|_| (StatusCode::SERVICE_UNAVAILABLE, "".to_string()))?;
+
+    Ok(StatusCode::ACCEPTED)
+}
@@ -1,9 +1,16 @@
-use super::{router::Config, state::AuthenticationState};
+use super::state::AuthenticationState;
 use crate::{
     app::{util, AppState},
-    database::Connection,
-    model::{Bookmark, Tag, TaggedItem, TaggedType, BOOKMARK_TABLE},
-    repo,
+    database::{Connection, Error, FromRow},
+    model::{
+        Bookmark, Resource, Tag, TaggedItem, TaggedType, ARTICLE_TABLE, BOOKMARK_TABLE,
+        RESOURCE_TABLE,
+    },
+    repo::{
+        self,
+        page::{self, Cursor, Page},
+    },
+    taskqueue::Task,
 };
 use axum::{
     extract::{Extension, Json, Path, Query},
@@ -16,47 +23,88 @@ use serde::{Deserialize, Serialize};
 pub struct SearchBookmark {
     pub id: Option<String>,
     pub user_id: Option<String>,
+    pub resource_id: Option<String>,
+    pub host: Option<String>,
+    /// Matches the title, description and url, and the article text
+    pub q: Option<String>,
+    pub limit: Option<u32>,
+    pub cursor: Option<Cursor>,
 }
 
 pub async fn find_bookmarks(
     app_state: &AppState,
+    conn: &impl Connection,
     search_params: SearchBookmark,
-) -> Result<Vec<Bookmark>, ()> {
+) -> Result<Vec<Bookmark>, Error> {
     let mut filters = app_state.new_query();
     filters.set_separator(" AND ");
     if let Some(id) = search_params.id.clone() {
-        filters.push_str("id = ?").bind(id.into());
+        filters.push_eq("id", id.into());
     }
     if let Some(user_id) = search_params.user_id.clone() {
-        filters.push_str("user_id = ?").bind(user_id.into());
+        filters.push_eq("user_id", user_id.into());
     }
+    if let Some(resource_id) = search_params.resource_id.clone() {
+        filters.push_eq("resource_id", resource_id.into());
+    }
+    if let Some(host) = search_params.host.clone() {
+        let mut resources = app_state.new_query();
+        repo::resource::push_host_filter(&mut resources, &host);
+        let mut filter = app_state.new_query();
+        filter
+            .push_str("resource_id IN (SELECT id FROM ")
+            .push_identifier(RESOURCE_TABLE)
+            .push_str(" WHERE ")
+            .append(resources)
+            .push_str(")");
+        filters.append(filter);
+    }
+    if let Some(q) = search_params
+        .q
+        .as_deref()
+        .map(str::trim)
+        .filter(|q| !q.is_empty())
+    {
+        let pattern = format!("%{}%", q.to_lowercase());
+        let mut filter = app_state.new_query();
+        filter
+            .push_str("(LOWER(title) LIKE ? OR LOWER(description) LIKE ? OR LOWER(url) LIKE ?")
+            .bind(pattern.clone().into())
+            .bind(pattern.clone().into())
+            .bind(pattern.clone().into())
+            .push_str(" OR resource_id IN (SELECT resource_id FROM ")
+            .push_identifier(ARTICLE_TABLE)
+            .push_str(" WHERE LOWER(text) LIKE ?))")
+            .bind(pattern.into());
+        filters.append(filter);
+    }
+    page::push_cursor(&mut filters, search_params.cursor);
 
     let mut query = app_state.new_query();
-    query.push_str("SELECT * FROM ").push_str(BOOKMARK_TABLE);
+    query
+        .push_str("SELECT * FROM ")
+        .push_identifier(BOOKMARK_TABLE);
     if !filters.is_empty() {
         query.push_str(" WHERE ").append(filters);
     }
+    page::push_limit(&mut query, search_params.limit);
 
-    app_state
-        .database()
-        .connection()
-        .fetch(query)
-        .await
-        .map_err(|_| ())?
-        .into_iter()
-        .map(|row| Bookmark::try_from(row))
-        .collect::<Result<Vec<_>, _>>()
-        .map_err(|_| ())
+    conn.fetch(query)
+        .await?
+        .iter()
+        .map(Bookmark::from_row)
+        .collect()
 }
 
 pub async fn find_bookmark_one(
     app_state: &AppState,
+    conn: &impl Connection,
     search_params: SearchBookmark,
-) -> Result<Bookmark, ()> {
-    find_bookmarks(app_state, search_params)
+) -> Result<Bookmark, Error> {
+    find_bookmarks(app_state, conn, search_params)
         .await?
         .pop()
-        .ok_or(())
+        .ok_or(Error::NotFound)
 }
 
 #[derive(Deserialize)]
@@ -64,9 +112,34 @@ pub struct BookmarkRequest {
     pub title: Option<String>,
     pub url: Option<String>,
     pub description: Option<String>,
+    pub image_url: Option<String>,
     pub tags: Option<Vec<String>>,
 }
 
+/// What creating a bookmark of an already bookmarked page does.
+#[derive(Deserialize, Default, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum OnDuplicate {
+    /// Creates another bookmark and reports the existing one in `duplicate_of`
+    #[default]
+    Allow,
+    /// Responds with 409
+    Reject,
+    /// Responds with the existing bookmark
+    Existing,
+}
+
+#[derive(Deserialize)]
+pub struct CreateBookmarkParams {
+    #[serde(default)]
+    pub on_duplicate: OnDuplicate,
+}
+
+#[derive(Deserialize)]
+pub struct MergeBookmarkRequest {
+    pub source_id: String,
+}
+
 #[derive(Serialize)]
 pub struct BookmarkResponse {
     pub id: String,
@@ -74,8 +147,12 @@ pub struct BookmarkResponse {
     pub url: Option<String>,
     pub description: Option<String>,
     pub tags: Vec<String>,
+    pub resource_id: Option<String>,
+    pub image_url: Option<String>,
     pub created_at: Option<DateTime<Utc>>,
     pub updated_at: Option<DateTime<Utc>>,
+    /// Bookmark of the same page the user already had when this one was created
+    pub duplicate_of: Option<String>,
 }
 
 impl From<Bookmark> for BookmarkResponse {
@@ -86,8 +163,11 @@ impl From<Bookmark> for BookmarkResponse {
             url: bookmark.url,
             description: bookmark.description,
             tags: Vec::new(),
+            resource_id: bookmark.resource_id,
+            image_url: bookmark.image_url,
             created_at: bookmark.created_at,
             updated_at: bookmark.updated_at,
+            duplicate_of: None,
         }
     }
 }
@@ -100,44 +180,97 @@ impl BookmarkResponse {
             url: bookmark.url,
             description: bookmark.description,
             tags: tag_data.iter().map(|data| data.0.path.clone()).collect(),
+            resource_id: bookmark.resource_id,
+            image_url: bookmark.image_url,
             created_at: bookmark.created_at,
             updated_at: bookmark.updated_at,
+            duplicate_of: None,
         }
     }
+
+    async fn load_tags(app_state: &AppState, bookmark: Bookmark) -> Result<Self, Error> {
+        let owned_tags = repo::tag::find_tagged_data_from_refs(
+            app_state,
+            &app_state.database().connection(),
+            TaggedType::Bookmark,
+            vec![bookmark.id.clone()],
+        )
+        .await
+        .map(|result| result.find_tags(bookmark.id.clone()))?;
+        Ok(Self::with_tags(bookmark, owned_tags))
+    }
+}
+
+/// Archives the page of a newly bookmarked resource once it is committed.
+fn archive_after_commit(app_state: &AppState, conn: &impl Connection, resource: Resource) {
+    let dispatcher = app_state.dispatcher().clone();
+    conn.after_commit(Box::new(move || {
+        let _ = dispatcher.dispatch(Task::ArchivePage {
+            resource: Box::new(resource),
+            refresh: false,
+        });
+    }));
+}
+
+/// Fills in what the user left out of a bookmark, and what its resource
+/// lacks, from its page once it is committed.
+fn enrich_after_commit(
+    app_state: &AppState,
+    conn: &impl Connection,
+    bookmark: &Bookmark,
+    resource: &Resource,
+) {
+    let is_missing = |value: &Option<String>| value.as_deref().is_none_or(|v| v.trim().is_empty());
+    if ![
+        &bookmark.title,
+        &bookmark.description,
+        &bookmark.image_url,
+        &resource.title,
+        &resource.description,
+        &resource.image_url,
+    ]
+    .into_iter()
+    .any(is_missing)
+    {
+        return;
+    }
+    let dispatcher = app_state.dispatcher().clone();
+    let bookmark = bookmark.clone();
+    conn.after_commit(Box::new(move || {
+        let _ = dispatcher.dispatch(Task::EnrichBookmark(Box::new(bookmark)));
+    }));
 }
 
 pub async fn list(
     Extension(app_state): Extension<AppState>,
     Extension(auth): Extension<AuthenticationState>,
     Query(search_params): Query<SearchBookmark>,
-) -> Result<Json<Vec<BookmarkResponse>>, (StatusCode, String)> {
+) -> Result<Json<Page<BookmarkResponse>>, (StatusCode, String)> {
+    let limit = page::limit(search_params.limit);
     let bookmarks = find_bookmarks(
         &app_state,
+        &app_state.database().connection(),
         SearchBookmark {
             user_id: auth.user_id(),
+            limit: Some(limit),
             ..search_params
         },
     )
-    .await
-    .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "".to_string()))?;
+    .await?;
+    let bookmarks = Page::new(bookmarks, limit, |bookmark| &bookmark.id);
 
     let tagged_result = repo::tag::find_tagged_data_from_refs(
         &app_state,
+        &app_state.database().connection(),
         TaggedType::Bookmark,
-        bookmarks.clone().into_iter().map(|b| b.id).collect(),
+        bookmarks.items.iter().map(|b| b.id.clone()).collect(),
     )
-    .await
-    .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "".to_string()))?;
+    .await?;
 
-    Ok(Json(
-        bookmarks
-            .into_iter()
-            .map(|bookmark| {
-                let owned_tags = tagged_result.find_tags(bookmark.id.clone());
-                BookmarkResponse::with_tags(bookmark, owned_tags)
-            })
-            .collect(),
-    ))
+    Ok(Json(bookmarks.map(|bookmark| {
+        let owned_tags = tagged_result.find_tags(bookmark.id.clone());
+        BookmarkResponse::with_tags(bookmark, owned_tags)
+    })))
 }
 
 pub async fn find(
@@ -147,38 +280,73 @@ pub async fn find(
 ) -> Result<Json<BookmarkResponse>, (StatusCode, String)> {
     let bookmark = find_bookmark_one(
         &app_state,
+        &app_state.database().connection(),
         SearchBookmark {
             id: Some(bookmark_id),
             user_id: auth.user_id(),
+            ..Default::default()
         },
     )
-    .await
-    .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "".to_string()))?;
-
-    let owned_tags = repo::tag::find_tagged_data_from_refs(
-        &app_state,
-        TaggedType::Bookmark,
-        vec![bookmark.id.clone()],
-    )
-    .await
-    .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "".to_string()))
-    .map(|result| result.find_tags(bookmark.id.clone()))?;
+    .await?;
 
-    Ok(Json(BookmarkResponse::with_tags(bookmark, owned_tags)))
+    Ok(Json(
+        BookmarkResponse::load_tags(&app_state, bookmark).await?,
+    ))
 }
 
 pub async fn create(
     Extension(app_state): Extension<AppState>,
     Extension(auth): Extension<AuthenticationState>,
+    Query(params): Query<CreateBookmarkParams>,
     Json(payload): Json<BookmarkRequest>,
 ) -> Result<Json<BookmarkResponse>, (StatusCode, String)> {
+    let tx = app_state.database().begin().await?;
+    let resource = match &payload.url {
+        Some(url) => repo::resource::upsert_resource(&app_state, &tx, url).await?,
+        None => None,
+    };
+    let duplicate = match resource.as_ref() {
+        Some(resource) => find_bookmarks(
+            &app_state,
+            &tx,
+            SearchBookmark {
+                user_id: auth.user_id(),
+                resource_id: Some(resource.id.clone()),
+                limit: Some(1),
+                ..Default::default()
+            },
+        )
+        .await?
+        .into_iter()
+        .next(),
+        None => None,
+    };
+    if let Some(duplicate) = duplicate.as_ref() {
+        match params.on_duplicate {
+            OnDuplicate::Allow => {}
+            OnDuplicate::Reject => {
+                tx.rollback().await?;
+                return Err((
+                    StatusCode::CONFLICT,
+                    format!("duplicate of bookmark {}", duplicate.id),
+                ));
+            }
+            OnDuplicate::Existing => {
+                tx.rollback().await?;
+                return Ok(Json(
+                    BookmarkResponse::load_tags(&app_state, duplicate.clone()).await?,
+                ));
+            }
+        }
+    }
     let bookmark = Bookmark {
         id: util::new_uid(),
         user_id: auth.user_id().unwrap(),
         title: payload.title.clone(),
         url: payload.url.clone(),
         description: payload.description.clone(),
-        resource_id: None,
+        resource_id: resource.as_ref().map(|r| r.id.clone()),
+        image_url: payload.image_url.clone(),
         created_at: Some(util::now()),
         updated_at: Some(util::now()),
     };
@@ -196,26 +364,38 @@ pub async fn create(
         .push_str("?")
         .bind(bookmark.description.clone().into())
         .push_str("?")
-        .bind(bookmark.created_at.clone().into())
+        .bind(bookmark.resource_id.clone().into())
+        .push_str("?")
+        .bind(bookmark.image_url.clone().into())
+        .push_str("?")
+        .bind(bookmark.created_at.into())
         .push_str("?")
-        .bind(bookmark.updated_at.clone().into());
+        .bind(bookmark.updated_at.into());
     let mut query = app_state.new_query();
     query
         .push_str("INSERT INTO ")
-        .push_str(BOOKMARK_TABLE)
-        .push_str(" (id, user_id, title, url, description, created_at, updated_at) VALUES (")
+        .push_identifier(BOOKMARK_TABLE)
+        .push_str(" ")
+        .push_columns(&[
+            "id",
+            "user_id",
+            "title",
+            "url",
+            "description",
+            "resource_id",
+            "image_url",
+            "created_at",
+            "updated_at",
+        ])
+        .push_str(" VALUES (")
         .append(values)
         .push_str(")");
-    app_state
-        .database()
-        .connection()
-        .execute(query)
-        .await
-        .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "".to_string()))?;
+    tx.execute(query).await?;
 
     let owned_tags = if let Some(tag_inputs) = payload.tags {
         repo::tag::sync_tagged_data_from_ref(
             &app_state,
+            &tx,
             auth.user_id().unwrap(),
             TaggedType::Bookmark,
             bookmark.id.clone(),
@@ -234,13 +414,20 @@ pub async fn create(
                 .collect(),
         )
         .await
-        .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "".to_string()))
         .map(|result| result.find_tags(bookmark.id.clone()))?
     } else {
         Vec::new()
     };
+    if let Some(resource) = resource {
+        enrich_after_commit(&app_state, &tx, &bookmark, &resource);
+        archive_after_commit(&app_state, &tx, resource);
+    }
+    tx.commit().await?;
 
-    Ok(Json(BookmarkResponse::with_tags(bookmark, owned_tags)))
+    Ok(Json(BookmarkResponse {
+        duplicate_of: duplicate.map(|d| d.id),
+        ..BookmarkResponse::with_tags(bookmark, owned_tags)
+    }))
 }
 
 pub async fn update(
@@ -251,50 +438,54 @@ pub async fn update(
 ) -> Result<Json<BookmarkResponse>, (StatusCode, String)> {
     let mut bookmark = find_bookmark_one(
         &app_state,
+        &app_state.database().connection(),
         SearchBookmark {
             id: Some(bookmark_id.clone()),
             user_id: auth.user_id(),
+            ..Default::default()
         },
     )
-    .await
-    .map_err(|_| (StatusCode::NOT_FOUND, "".to_string()))?;
+    .await?;
 
+    let tx = app_state.database().begin().await?;
+    let mut resource = None;
     bookmark.updated_at = Some(util::now());
     let mut values = app_state.new_query();
     values.set_separator(", ");
-    values
-        .push_str("updated_at = ?")
-        .bind(bookmark.updated_at.clone().into());
+    values.push_eq("updated_at", bookmark.updated_at.into());
     if let Some(url) = payload.url {
+        resource = repo::resource::upsert_resource(&app_state, &tx, &url).await?;
         bookmark.url = Some(url.clone());
-        values.push_str("url = ?").bind(url.clone().into());
+        bookmark.resource_id = resource.as_ref().map(|r| r.id.clone());
+        values.push_eq("url", url.clone().into());
+        values.push_eq("resource_id", bookmark.resource_id.clone().into());
     }
     if let Some(title) = payload.title {
         bookmark.title = Some(title.clone());
-        values.push_str("title = ?").bind(title.into());
+        values.push_eq("title", title.into());
     }
     if let Some(description) = payload.description {
         bookmark.description = Some(description.clone());
-        values.push_str("description = ?").bind(description.into());
+        values.push_eq("description", description.into());
+    }
+    if let Some(image_url) = payload.image_url {
+        bookmark.image_url = Some(image_url.clone());
+        values.push_eq("image_url", image_url.into());
     }
     let mut query = app_state.new_query();
     query
         .push_str("UPDATE ")
-        .push_str(BOOKMARK_TABLE)
+        .push_identifier(BOOKMARK_TABLE)
         .push_str(" SET ")
         .append(values)
-        .push_str(" WHERE id = ?")
-        .bind(bookmark_id.into());
-    app_state
-        .database()
-        .connection()
-        .execute(query)
-        .await
-        .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "".to_string()))?;
+        .push_str(" WHERE ")
+        .push_eq("id", bookmark_id.into());
+    tx.execute(query).await?;
 
     let owned_tags = if let Some(tag_inputs) = payload.tags {
         repo::tag::sync_tagged_data_from_ref(
             &app_state,
+            &tx,
             auth.user_id().unwrap(),
             TaggedType::Bookmark,
             bookmark.id.clone(),
@@ -313,11 +504,26 @@ pub async fn update(
                 .collect(),
         )
         .await
-        .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "".to_string()))
         .map(|result| result.find_tags(bookmark.id.clone()))?
     } else {
         Vec::new()
     };
+    // Metadata still missing is looked up again on every update, not only when
+    // the url changed
+    let resource = match (resource, bookmark.resource_id.clone()) {
+        (Some(resource), _) => {
+            archive_after_commit(&app_state, &tx, resource.clone());
+            Some(resource)
+        }
+        (None, Some(resource_id)) => {
+            Some(repo::resource::find_resource(&app_state, &tx, resource_id).await?)
+        }
+        (None, None) => None,
+    };
+    if let Some(resource) = resource {
+        enrich_after_commit(&app_state, &tx, &bookmark, &resource);
+    }
+    tx.commit().await?;
 
     Ok(Json(BookmarkResponse::with_tags(bookmark, owned_tags)))
 }
@@ -329,26 +535,453 @@ pub async fn delete(
 ) -> Result<Json<BookmarkResponse>, (StatusCode, String)> {
     let bookmark = find_bookmark_one(
         &app_state,
+        &app_state.database().connection(),
         SearchBookmark {
             id: Some(bookmark_id.clone()),
             user_id: auth.user_id(),
+            ..Default::default()
         },
     )
-    .await
-    .map_err(|_| (StatusCode::NOT_FOUND, "".to_string()))?;
+    .await?;
 
     let mut query = app_state.new_query();
     query
         .push_str("DELETE FROM ")
-        .push_str(BOOKMARK_TABLE)
-        .push_str(" WHERE id = ?")
-        .bind(bookmark.id.clone().into());
-    app_state
-        .database()
-        .connection()
-        .execute(query)
-        .await
-        .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "".to_string()))?;
+        .push_identifier(BOOKMARK_TABLE)
+        .push_str(" WHERE ")
+        .push_eq("id", bookmark.id.clone().into());
+    app_state.database().connection().execute(query).await?;
 
     Ok(Json(BookmarkResponse::from(bookmark)))
 }
+
+/// The longer of two texts, `current` on a tie.
+fn richer(current: Option<String>, other: Option<String>) -> Option<String> {
+    let len = |s: &Option<String>| s.as_deref().map_or(0, |s| s.trim().chars().count());
+    match len(&other) > len(&current) {
+        true => other,
+        false => current,
+    }
+}
+
+/// Folds the bookmark `source_id` into `bookmark_id` and deletes it. Tags are
+/// unioned, keeping the values of `bookmark_id`, and the longer title and
+/// description win.
+pub async fn merge(
+    Extension(app_state): Extension<AppState>,
+    Extension(auth): Extension<AuthenticationState>,
+    Path(bookmark_id): Path<String>,
+    Json(payload): Json<MergeBookmarkRequest>,
+) -> Result<Json<BookmarkResponse>, (StatusCode, String)> {
+    if payload.source_id == bookmark_id {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "cannot merge a bookmark into itself".to_string(),
+        ));
+    }
+    let conn = app_state.database().connection();
+    let find = |id: String| {
+        find_bookmark_one(
+            &app_state,
+            &conn,
+            SearchBookmark {
+                id: Some(id),
+                user_id: auth.user_id(),
+                ..Default::default()
+            },
+        )
+    };
+    let mut bookmark = find(bookmark_id).await?;
+    let source = find(payload.source_id).await?;
+
+    bookmark.title = richer(bookmark.title, source.title);
+    bookmark.description = richer(bookmark.description, source.description);
+    if bookmark.url.is_none() {
+        bookmark.url = source.url;
+        bookmark.resource_id = source.resource_id;
+    }
+    bookmark.image_url = bookmark.image_url.or(source.image_url);
+    bookmark.created_at = bookmark
+        .created_at
+        .into_iter()
+        .chain(source.created_at)
+        .min();
+    bookmark.updated_at = Some(util::now());
+
+    let tx = app_state.database().begin().await?;
+    let (mut tagged_items, source_items): (Vec<_>, Vec<_>) = repo::tag::find_tagged_items(
+        &app_state,
+        &tx,
+        TaggedType::Bookmark,
+        repo::tag::SearchTaggedItem {
+            ref_id_vec: Some(vec![bookmark.id.clone(), source.id.clone()]),
+            ..Default::default()
+        },
+    )
+    .await?
+    .into_iter()
+    .partition(|item| item.ref_id == bookmark.id);
+    for item in source_items {
+        if !tagged_items.iter().any(|t| t.tag_id == item.tag_id) {
+            tagged_items.push(TaggedItem {
+                id: String::new(),
+                ref_id: bookmark.id.clone(),
+                ..item
+            });
+        }
+    }
+    if !tagged_items.is_empty() {
+        repo::tag::sync_tagged_items(&app_state, &tx, TaggedType::Bookmark, tagged_items).await?;
+    }
+
+    let mut values = app_state.new_query();
+    values.set_separator(", ");
+    values
+        .push_eq("title", bookmark.title.clone().into())
+        .push_eq("description", bookmark.description.clone().into())
+        .push_eq("url", bookmark.url.clone().into())
+        .push_eq("resource_id", bookmark.resource_id.clone().into())
+        .push_eq("image_url", bookmark.image_url.clone().into())
+        .push_eq("created_at", bookmark.created_at.into())
+        .push_eq("updated_at", bookmark.updated_at.into());
+    let mut query = app_state.new_query();
+    query
+        .push_str("UPDATE ")
+        .push_identifier(BOOKMARK_TABLE)
+        .push_str(" SET ")
+        .append(values)
+        .push_str(" WHERE ")
+        .push_eq("id", bookmark.id.clone().into());
+    tx.execute(query).await?;
+
+    let mut query = app_state.new_query();
+    query
+        .push_str("DELETE FROM ")
+        .push_identifier(BOOKMARK_TABLE)
+        .push_str(" WHERE ")
+        .push_eq("id", source.id.into());
+    tx.execute(query).await?;
+    tx.commit().await?;
+
+    Ok(Json(
+        BookmarkResponse::load_tags(&app_state, bookmark).await?,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{model::User, taskqueue, testutil};
+
+    async fn setup() -> (AppState, AuthenticationState, taskqueue::Worker) {
+        let (app_state, worker) = testutil::app_state().await;
+        testutil::create_user(&app_state, "u").await;
+        let auth = AuthenticationState::User(User {
+            id: "u".to_string(),
+            ..Default::default()
+        });
+        (app_state, auth, worker)
+    }
+
+    async fn create_with(
+        app_state: &AppState,
+        auth: &AuthenticationState,
+        on_duplicate: OnDuplicate,
+        request: BookmarkRequest,
+    ) -> Result<BookmarkResponse, (StatusCode, String)> {
+        create(
+            Extension(app_state.clone()),
+            Extension(auth.clone()),
+            Query(CreateBookmarkParams { on_duplicate }),
+            Json(request),
+        )
+        .await
+        .map(|Json(response)| response)
+    }
+
+    fn request(url: &str, title: &str, tags: &[&str]) -> BookmarkRequest {
+        BookmarkRequest {
+            title: Some(title.to_string()),
+            url: Some(url.to_string()),
+            description: None,
+            image_url: None,
+            tags: Some(tags.iter().map(|t| t.to_string()).collect()),
+        }
+    }
+
+    async fn count(app_state: &AppState) -> usize {
+        Connection::fetch(
+            &app_state.database().connection(),
+            "SELECT id FROM bookmark".into(),
+        )
+        .await
+        .unwrap()
+        .len()
+    }
+
+    #[tokio::test]
+    async fn create_on_duplicate() {
+        let (app_state, auth, _worker) = setup().await;
+        let url = "https://example.com/a";
+        let original = create_with(
+            &app_state,
+            &auth,
+            OnDuplicate::Allow,
+            request(url, "A", &[]),
+        )
+        .await
+        .unwrap();
+        assert_eq!(original.duplicate_of, None);
+
+        let (status, _) = create_with(
+            &app_state,
+            &auth,
+            OnDuplicate::Reject,
+            request(url, "B", &[]),
+        )
+        .await
+        .err()
+        .unwrap();
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert_eq!(count(&app_state).await, 1);
+
+        let existing = create_with(
+            &app_state,
+            &auth,
+            OnDuplicate::Existing,
+            request(url, "C", &[]),
+        )
+        .await
+        .unwrap();
+        assert_eq!(existing.id, original.id);
+        assert_eq!(existing.title.as_deref(), Some("A"));
+        assert_eq!(count(&app_state).await, 1);
+
+        let allowed = create_with(
+            &app_state,
+            &auth,
+            OnDuplicate::Allow,
+            request(url, "D", &[]),
+        )
+        .await
+        .unwrap();
+        assert_ne!(allowed.id, original.id);
+        assert_eq!(allowed.duplicate_of, Some(original.id));
+        assert_eq!(count(&app_state).await, 2);
+    }
+
+    #[tokio::test]
+    async fn create_on_duplicate_of_many() {
+        let (app_state, auth, _worker) = setup().await;
+        let url = "https://example.com/a";
+        let mut created = Vec::new();
+        for title in ["A", "B", "C"] {
+            // Ids only order by creation across milliseconds.
+            tokio::time::sleep(std::time::Duration::from_millis(2)).await;
+            let bookmark = create_with(
+                &app_state,
+                &auth,
+                OnDuplicate::Allow,
+                request(url, title, &[]),
+            )
+            .await
+            .unwrap();
+            created.push(bookmark);
+        }
+        assert_eq!(created[1].duplicate_of.as_ref(), Some(&created[0].id));
+        assert_eq!(created[2].duplicate_of.as_ref(), Some(&created[0].id));
+
+        let existing = create_with(
+            &app_state,
+            &auth,
+            OnDuplicate::Existing,
+            request(url, "D", &[]),
+        )
+        .await
+        .unwrap();
+        assert_eq!(existing.id, created[0].id);
+        assert_eq!(count(&app_state).await, 3);
+    }
+
+    #[tokio::test]
+    async fn enrich_bookmark_and_resource() {
+        use crate::link::FetchPolicy;
+        use axum::{http::header, routing::get, Router};
+
+        let router = Router::new().route(
+            "/page",
+            get(|| async {
+                (
+                    [(header::CONTENT_TYPE, "text/html")],
+                    r#"<title>Page title</title>
+<meta name="description" content="Page description">
+<meta property="og:image" content="/a.png">"#,
+                )
+            }),
+        );
+        let url = format!("{}/page", testutil::serve(router).await);
+        let (app_state, auth, worker) = setup().await;
+        let app_state = app_state.with_fetch_policy(FetchPolicy {
+            allow_private: true,
+            ..Default::default()
+        });
+        let enrich = || async {
+            let tasks = worker
+                .drain()
+                .into_iter()
+                .filter(|task| matches!(task, Task::EnrichBookmark(_)))
+                .collect::<Vec<_>>();
+            let count = tasks.len();
+            for task in tasks {
+                task.run(&app_state).await.unwrap();
+            }
+            count
+        };
+        let find = |id: String| {
+            let app_state = app_state.clone();
+            let auth = auth.clone();
+            async move {
+                let conn = app_state.database().connection();
+                let bookmark = find_bookmark_one(
+                    &app_state,
+                    &conn,
+                    SearchBookmark {
+                        id: Some(id),
+                        user_id: auth.user_id(),
+                        ..Default::default()
+                    },
+                )
+                .await
+                .unwrap();
+                let resource = repo::resource::find_resource(
+                    &app_state,
+                    &conn,
+                    bookmark.resource_id.clone().unwrap(),
+                )
+                .await
+                .unwrap();
+                (bookmark, resource)
+            }
+        };
+
+        let created = create_with(
+            &app_state,
+            &auth,
+            OnDuplicate::Allow,
+            request(&url, "Mine", &[]),
+        )
+        .await
+        .unwrap();
+        // The first lookup is lost, e.g. while the page was down
+        worker.drain();
+
+        // Updates look up what is still missing even with the url unchanged
+        let Json(updated) = update(
+            Extension(app_state.clone()),
+            Extension(auth.clone()),
+            Path(created.id.clone()),
+            Json(BookmarkRequest {
+                title: None,
+                url: None,
+                description: None,
+                image_url: None,
+                tags: Some(vec!["a".to_string()]),
+            }),
+        )
+        .await
+        .unwrap();
+        assert_eq!(updated.tags, vec!["/a"]);
+        assert_eq!(enrich().await, 1);
+
+        let (bookmark, resource) = find(created.id.clone()).await;
+        let image_url = Some(url.replace("/page", "/a.png"));
+        assert_eq!(bookmark.title.as_deref(), Some("Mine"));
+        assert_eq!(bookmark.description.as_deref(), Some("Page description"));
+        assert_eq!(bookmark.image_url, image_url);
+        assert_eq!(resource.title.as_deref(), Some("Page title"));
+        assert_eq!(resource.description.as_deref(), Some("Page description"));
+        assert_eq!(resource.image_url, image_url);
+
+        // Nothing is looked up once nothing is missing
+        let second = create_with(
+            &app_state,
+            &auth,
+            OnDuplicate::Allow,
+            BookmarkRequest {
+                description: Some("Mine too".to_string()),
+                image_url: Some("https://example.com/b.png".to_string()),
+                ..request(&url, "Mine", &[])
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(enrich().await, 0);
+        assert_eq!(
+            find(second.id).await.0.description.as_deref(),
+            Some("Mine too")
+        );
+    }
+
+    #[tokio::test]
+    async fn merge_bookmarks() {
+        let (app_state, auth, _worker) = setup().await;
+        let target = create_with(
+            &app_state,
+            &auth,
+            OnDuplicate::Allow,
+            request("https://example.com/a", "Short", &["a", "b"]),
+        )
+        .await
+        .unwrap();
+        let source = create_with(
+            &app_state,
+            &auth,
+            OnDuplicate::Allow,
+            BookmarkRequest {
+                description: Some("Found later".to_string()),
+                ..request("https://example.com/b", "A longer title", &["b", "c"])
+            },
+        )
+        .await
+        .unwrap();
+
+        let Json(merged) = merge(
+            Extension(app_state.clone()),
+            Extension(auth.clone()),
+            Path(target.id.clone()),
+            Json(MergeBookmarkRequest {
+                source_id: source.id.clone(),
+            }),
+        )
+        .await
+        .unwrap();
+        assert_eq!(merged.id, target.id);
+        assert_eq!(merged.title.as_deref(), Some("A longer title"));
+        assert_eq!(merged.description.as_deref(), Some("Found later"));
+        assert_eq!(merged.url.as_deref(), Some("https://example.com/a"));
+        let mut tags = merged.tags;
+        tags.sort();
+        assert_eq!(tags, vec!["/a", "/b", "/c"]);
+
+        let conn = app_state.database().connection();
+        let remaining = find_bookmarks(&app_state, &conn, SearchBookmark::default())
+            .await
+            .unwrap();
+        assert_eq!(
+            remaining.iter().map(|b| b.id.clone()).collect::<Vec<_>>(),
+            vec![target.id]
+        );
+        let items = repo::tag::find_tagged_items(
+            &app_state,
+            &conn,
+            TaggedType::Bookmark,
+            repo::tag::SearchTaggedItem {
+                ref_id_vec: Some(vec![source.id]),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+        assert!(items.is_empty());
+    }
+}
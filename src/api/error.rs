@@ -0,0 +1,57 @@
+use crate::{archive, database, extract::MetatagError, link::FetchError};
+use axum::http::StatusCode;
+
+impl From<database::Error> for (StatusCode, String) {
+    fn from(e: database::Error) -> Self {
+        match e {
+            database::Error::NotFound => (StatusCode::NOT_FOUND, "".to_string()),
+            database::Error::UniqueViolation(_) => {
+                (StatusCode::CONFLICT, "already exists".to_string())
+            }
+            database::Error::ForeignKeyViolation(_) => {
+                (StatusCode::BAD_REQUEST, "invalid reference".to_string())
+            }
+            e => {
+                log::error!("{}", e);
+                (StatusCode::INTERNAL_SERVER_ERROR, "".to_string())
+            }
+        }
+    }
+}
+
+impl From<FetchError> for (StatusCode, String) {
+    fn from(e: FetchError) -> Self {
+        let status = match e {
+            FetchError::Url(_) | FetchError::Scheme(_) => StatusCode::BAD_REQUEST,
+            FetchError::Address(_) => StatusCode::FORBIDDEN,
+            FetchError::Timeout => StatusCode::GATEWAY_TIMEOUT,
+            FetchError::TooManyRedirects
+            | FetchError::TooLarge
+            | FetchError::Status(_)
+            | FetchError::Request(_) => StatusCode::BAD_GATEWAY,
+        };
+        (status, e.to_string())
+    }
+}
+
+impl From<archive::Error> for (StatusCode, String) {
+    fn from(e: archive::Error) -> Self {
+        match e {
+            archive::Error::Fetch(e) => e.into(),
+            archive::Error::Unsupported(_) => (StatusCode::UNSUPPORTED_MEDIA_TYPE, e.to_string()),
+            e => {
+                log::error!("{}", e);
+                (StatusCode::INTERNAL_SERVER_ERROR, "".to_string())
+            }
+        }
+    }
+}
+
+impl From<MetatagError> for (StatusCode, String) {
+    fn from(e: MetatagError) -> Self {
+        match e {
+            MetatagError::Fetch(e) => e.into(),
+            MetatagError::Database(e) => e.into(),
+        }
+    }
+}
@@ -0,0 +1,46 @@
+use super::state::AuthenticationState;
+use crate::{app::AppState, repo};
+use axum::{
+    extract::{Extension, Json, Query},
+    http::StatusCode,
+};
+use chrono::{offset::Utc, DateTime};
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize)]
+pub struct SearchHost {
+    pub host: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct HostResponse {
+    pub host: String,
+    pub bookmark_count: i32,
+    pub last_saved_at: Option<DateTime<Utc>>,
+}
+
+impl From<repo::resource::HostSummary> for HostResponse {
+    fn from(summary: repo::resource::HostSummary) -> Self {
+        Self {
+            host: summary.host,
+            bookmark_count: summary.bookmark_count,
+            last_saved_at: summary.last_saved_at,
+        }
+    }
+}
+
+pub async fn list(
+    Extension(app_state): Extension<AppState>,
+    Extension(auth): Extension<AuthenticationState>,
+    Query(search_params): Query<SearchHost>,
+) -> Result<Json<Vec<HostResponse>>, (StatusCode, String)> {
+    let hosts = repo::resource::find_hosts(
+        &app_state,
+        &app_state.database().connection(),
+        auth.user_id().unwrap(),
+        search_params.host,
+    )
+    .await?;
+
+    Ok(Json(hosts.into_iter().map(HostResponse::from).collect()))
+}
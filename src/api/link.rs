@@ -0,0 +1,71 @@
+use super::state::AuthenticationState;
+use crate::{
+    app::AppState,
+    repo::{
+        self,
+        link::{BookmarkLink, LinkState},
+        page::{self, Cursor, Page},
+    },
+};
+use axum::{
+    extract::{Extension, Json, Query},
+    http::StatusCode,
+};
+use chrono::{offset::Utc, DateTime};
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize)]
+pub struct SearchLinkRequest {
+    pub state: Option<LinkState>,
+    pub limit: Option<u32>,
+    pub cursor: Option<Cursor>,
+}
+
+#[derive(Serialize)]
+pub struct LinkResponse {
+    pub bookmark_id: String,
+    pub title: Option<String>,
+    pub url: Option<String>,
+    pub status_code: Option<i32>,
+    pub final_url: Option<String>,
+    pub last_checked_at: Option<DateTime<Utc>>,
+    pub last_ok_at: Option<DateTime<Utc>>,
+}
+
+impl From<BookmarkLink> for LinkResponse {
+    fn from(link: BookmarkLink) -> Self {
+        Self {
+            bookmark_id: link.id,
+            title: link.title,
+            url: link.url,
+            status_code: link.status_code,
+            final_url: link.final_url,
+            last_checked_at: link.last_checked_at,
+            last_ok_at: link.last_ok_at,
+        }
+    }
+}
+
+/// Bookmarks whose link was found broken or redirected when last checked.
+pub async fn list(
+    Extension(app_state): Extension<AppState>,
+    Extension(auth): Extension<AuthenticationState>,
+    Query(search_params): Query<SearchLinkRequest>,
+) -> Result<Json<Page<LinkResponse>>, (StatusCode, String)> {
+    let limit = page::limit(search_params.limit);
+    let links = repo::link::find_bookmark_links(
+        &app_state,
+        &app_state.database().connection(),
+        repo::link::SearchLink {
+            user_id: auth.user_id(),
+            state: search_params.state,
+            limit: Some(limit),
+            cursor: search_params.cursor,
+        },
+    )
+    .await?;
+
+    Ok(Json(
+        Page::new(links, limit, |link| &link.id).map(LinkResponse::from),
+    ))
+}
@@ -1,5 +1,9 @@
+use crate::{
+    app::AppState,
+    extract::{self, Metatag},
+};
 use axum::{
-    extract::{Json, Query},
+    extract::{Extension, Json, Query},
     http::StatusCode,
 };
 use serde::{Deserialize, Serialize};
@@ -7,106 +11,63 @@ use serde::{Deserialize, Serialize};
 #[derive(Deserialize)]
 pub struct MetatagsRequest {
     pub url: String,
+    /// Fetches the page again rather than serving what was fetched last
+    #[serde(default)]
+    pub refresh: bool,
 }
 
-#[derive(Serialize, Default)]
-pub struct Metatag {
-    pub title: Option<String>,
-    pub description: Option<String>,
-    pub image_url: Option<String>,
+#[derive(Serialize)]
+pub struct TagValueResponse {
+    pub path: String,
+    pub value_type: Option<String>,
+    pub value: Option<String>,
 }
 
-pub async fn fetch(
-    Query(payload): Query<MetatagsRequest>,
-) -> Result<Json<Metatag>, (StatusCode, String)> {
-    let html = reqwest::get(payload.url.as_str())
-        .await
-        .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "".to_string()))?
-        .text()
-        .await
-        .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "".to_string()))?;
-    let document = scraper::Html::parse_document(html.as_str());
-
-    match extract_from_plain(&document)
-        .or_else(|| extract_from_opengraph(&document))
-        .or_else(|| extract_from_twitter(&document))
-    {
-        Some(m) => Ok(Json(m)),
-        None => Err((StatusCode::NOT_FOUND, "".to_string())),
-    }
-}
-
-fn value_from_select<'a>(select: Option<scraper::ElementRef<'a>>, name: &'a str) -> &'a str {
-    select
-        .and_then(|e| e.value().attr(name))
-        .unwrap_or_default()
+#[derive(Serialize)]
+pub struct MetatagResponse {
+    #[serde(flatten)]
+    pub metatag: Metatag,
+    /// Typed tag values from the structured data and tags suggested for the
+    /// site, to prefill the bookmark
+    pub tag_values: Vec<TagValueResponse>,
 }
 
-fn extract_from_plain(document: &scraper::Html) -> Option<Metatag> {
-    let title = document
-        .select(&scraper::Selector::parse(r#"title"#).unwrap())
-        .next();
-    let description = document
-        .select(&scraper::Selector::parse(r#"meta[name="description"]"#).unwrap())
-        .next();
-
-    if let None = title.or(description) {
-        None
-    } else {
-        Some(Metatag {
-            title: Some(
-                title
-                    .and_then(|e| e.text().next())
-                    .map(|t| t.trim())
-                    .unwrap_or_default()
-                    .to_string(),
-            ),
-            description: Some(value_from_select(description, "content").to_string()),
-            image_url: None,
-        })
+impl From<Metatag> for MetatagResponse {
+    fn from(metatag: Metatag) -> Self {
+        let tag_values = metatag
+            .structured_data
+            .iter()
+            .flat_map(|structured| structured.tag_values())
+            .map(|(tag, item)| TagValueResponse {
+                path: tag.path,
+                value_type: tag.value_type,
+                value: item.value,
+            })
+            .chain(
+                metatag
+                    .site
+                    .iter()
+                    .flat_map(|site| site.tag_paths.iter())
+                    .map(|path| TagValueResponse {
+                        path: path.clone(),
+                        value_type: None,
+                        value: None,
+                    }),
+            )
+            .collect();
+        Self {
+            metatag,
+            tag_values,
+        }
     }
 }
 
-fn extract_from_opengraph(document: &scraper::Html) -> Option<Metatag> {
-    let title = document
-        .select(&scraper::Selector::parse(r#"meta[property="og:title"]"#).unwrap())
-        .next();
-    let description = document
-        .select(&scraper::Selector::parse(r#"meta[property="og:description"]"#).unwrap())
-        .next();
-    let image_url = document
-        .select(&scraper::Selector::parse(r#"meta[property="og:image"]"#).unwrap())
-        .next();
-
-    if let None = title.or(description).or(image_url) {
-        None
-    } else {
-        Some(Metatag {
-            title: Some(value_from_select(title, "content").to_string()),
-            description: Some(value_from_select(description, "content").to_string()),
-            image_url: Some(value_from_select(image_url, "content").to_string()),
-        })
-    }
-}
-
-fn extract_from_twitter(document: &scraper::Html) -> Option<Metatag> {
-    let title = document
-        .select(&scraper::Selector::parse(r#"meta[name="twitter:title"]"#).unwrap())
-        .next();
-    let description = document
-        .select(&scraper::Selector::parse(r#"meta[name="twitter:description"]"#).unwrap())
-        .next();
-    let image_url = document
-        .select(&scraper::Selector::parse(r#"meta[name="twitter:image"]"#).unwrap())
-        .next();
-
-    if let None = title.or(description).or(image_url) {
-        None
-    } else {
-        Some(Metatag {
-            title: Some(value_from_select(title, "content").to_string()),
-            description: Some(value_from_select(description, "content").to_string()),
-            image_url: Some(value_from_select(image_url, "content").to_string()),
-        })
+pub async fn fetch(
+    Extension(app_state): Extension<AppState>,
+    Query(payload): Query<MetatagsRequest>,
+) -> Result<Json<MetatagResponse>, (StatusCode, String)> {
+    match extract::fetch_metatag(&app_state, &payload.url, payload.refresh).await? {
+        Some(m) => Ok(Json(m.into())),
+        None => Err((StatusCode::NOT_FOUND, "".to_string())),
     }
 }
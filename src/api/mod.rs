@@ -1,4 +1,8 @@
+mod archive;
 mod bookmark;
+mod error;
+mod host;
+mod link;
 mod metatag;
 mod router;
 mod state;
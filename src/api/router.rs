@@ -19,7 +19,6 @@ pub fn new_handler(app_state: AppState, config: Config) -> axum::Router {
         ]);
 
     let mut router = axum::Router::new()
-        .route("/metatag", get(super::metatag::fetch))
         .route("/register", post(super::user::create))
         .route("/auth", post(super::user::create_token))
         .nest(
@@ -48,8 +47,17 @@ pub fn new_handler(app_state: AppState, config: Config) -> axum::Router {
                             get(super::bookmark::find)
                                 .put(super::bookmark::update)
                                 .delete(super::bookmark::delete),
-                        ),
+                        )
+                        .route("/:bookmark_id/merge", post(super::bookmark::merge))
+                        .route(
+                            "/:bookmark_id/archive",
+                            get(super::archive::find).post(super::archive::create),
+                        )
+                        .route("/:bookmark_id/article", get(super::archive::find_article)),
                 )
+                .route("/host", get(super::host::list))
+                .route("/metatag", get(super::metatag::fetch))
+                .route("/link", get(super::link::list))
                 .nest(
                     "/tag",
                     axum::Router::new()
@@ -1,10 +1,11 @@
 use super::state::AuthenticationState;
 use crate::{
     app::AppState,
-    database::Connection,
-    model::{Tag, TAG_TABLE},
-    repo,
-    taskqueue::Task,
+    model::Tag,
+    repo::{
+        self,
+        page::{self, Page},
+    },
 };
 use axum::{
     extract::{Extension, Json, Path, Query},
@@ -55,7 +56,6 @@ pub struct CreateTagRequest {
 
 #[derive(Deserialize)]
 pub struct UpdatedTagRequest {
-    pub path: Option<String>,
     pub label: Option<String>,
     pub value_type: Option<String>,
 }
@@ -64,19 +64,21 @@ pub async fn list(
     Extension(app_state): Extension<AppState>,
     Extension(auth): Extension<AuthenticationState>,
     Query(search_params): Query<repo::tag::SearchTag>,
-) -> Result<Json<Vec<TagResponse>>, (StatusCode, String)> {
+) -> Result<Json<Page<TagResponse>>, (StatusCode, String)> {
+    let limit = page::limit(search_params.limit);
     let tags = repo::tag::find_tags(
         &app_state,
+        &app_state.database().connection(),
         repo::tag::SearchTag {
             user_id: auth.user_id(),
+            limit: Some(limit),
             ..search_params
         },
     )
-    .await
-    .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "".to_string()))?;
+    .await?;
 
     Ok(Json(
-        tags.into_iter().map(|t| TagResponse::from(t)).collect(),
+        Page::new(tags, limit, |tag| &tag.id).map(TagResponse::from),
     ))
 }
 
@@ -87,14 +89,14 @@ pub async fn find(
 ) -> Result<Json<TagResponse>, (StatusCode, String)> {
     let tag = repo::tag::find_tags(
         &app_state,
+        &app_state.database().connection(),
         repo::tag::SearchTag {
             id_vec: Some(vec![tag_id]),
             user_id: auth.user_id(),
             ..Default::default()
         },
     )
-    .await
-    .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "".to_string()))?
+    .await?
     .pop()
     .ok_or((StatusCode::NOT_FOUND, "".to_string()))?;
 
@@ -113,9 +115,8 @@ pub async fn create(
         ..Tag::from_path(payload.path)
     };
 
-    let tag = repo::tag::create_tags(&app_state, vec![value])
-        .await
-        .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "".to_string()))?
+    let tag = repo::tag::create_tags(&app_state, &app_state.database().connection(), vec![value])
+        .await?
         .pop()
         .ok_or((StatusCode::INTERNAL_SERVER_ERROR, "".to_string()))?;
 
@@ -130,14 +131,14 @@ pub async fn update(
 ) -> Result<Json<TagResponse>, (StatusCode, String)> {
     let mut tag = repo::tag::find_tags(
         &app_state,
+        &app_state.database().connection(),
         repo::tag::SearchTag {
             id_vec: Some(vec![tag_id.clone()]),
             user_id: auth.user_id(),
             ..Default::default()
         },
     )
-    .await
-    .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "".to_string()))?
+    .await?
     .pop()
     .ok_or((StatusCode::NOT_FOUND, "".to_string()))?;
 
@@ -148,9 +149,8 @@ pub async fn update(
         tag.value_type = Some(value_type.clone());
     }
 
-    let tag = repo::tag::update_tags(&app_state, vec![tag])
-        .await
-        .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "".to_string()))?
+    let tag = repo::tag::update_tags(&app_state, &app_state.database().connection(), vec![tag])
+        .await?
         .pop()
         .ok_or((StatusCode::INTERNAL_SERVER_ERROR, "".to_string()))?;
 
@@ -164,20 +164,19 @@ pub async fn delete(
 ) -> Result<Json<TagResponse>, (StatusCode, String)> {
     let tag = repo::tag::find_tags(
         &app_state,
+        &app_state.database().connection(),
         repo::tag::SearchTag {
             id_vec: Some(vec![tag_id.clone()]),
             user_id: auth.user_id(),
             ..Default::default()
         },
     )
-    .await
-    .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "".to_string()))?
+    .await?
     .pop()
     .ok_or((StatusCode::NOT_FOUND, "".to_string()))?;
 
-    let tag = repo::tag::delete_tags(&app_state, vec![tag])
-        .await
-        .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "".to_string()))?
+    let tag = repo::tag::delete_tags(&app_state, &app_state.database().connection(), vec![tag])
+        .await?
         .pop()
         .ok_or((StatusCode::INTERNAL_SERVER_ERROR, "".to_string()))?;
 
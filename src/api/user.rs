@@ -1,9 +1,10 @@
 use super::{router::Config, state::AuthenticationState};
 use crate::{
     app::{util, AppState},
-    database::Connection,
+    database::{Connection, Error, FromRow},
     hash::{hash_password, verify_password},
     model::{User, USER_TABLE},
+    repo::page::{self, Cursor, Page},
 };
 use axum::{
     extract::{Extension, Json, Path, Query, Request},
@@ -59,58 +60,66 @@ pub struct SearchUser {
     pub username: Option<String>,
     pub email: Option<String>,
     pub role: Option<String>,
+    pub limit: Option<u32>,
+    pub cursor: Option<Cursor>,
 }
 
-pub async fn find_users(app_state: &AppState, search_params: SearchUser) -> Result<Vec<User>, ()> {
+pub async fn find_users(
+    app_state: &AppState,
+    search_params: SearchUser,
+) -> Result<Vec<User>, Error> {
     let mut filters = app_state.new_query();
     filters.set_separator(" AND ");
     if let Some(id) = search_params.id.clone() {
-        filters.push_str("id IN (?)").bind(id.into());
+        filters.push_eq("id", id.into());
     }
     if let Some(username) = search_params.username.clone() {
-        filters.push_str("username IN (?)").bind(username.into());
+        filters.push_eq("username", username.into());
     }
     if let Some(email) = search_params.email.clone() {
-        filters.push_str("email IN (?)").bind(email.into());
+        filters.push_eq("email", email.into());
     }
     if let Some(role) = search_params.role.clone() {
-        filters.push_str("role IN (?)").bind(role.into());
+        filters.push_eq("role", role.into());
     }
+    page::push_cursor(&mut filters, search_params.cursor);
 
     let mut query = app_state.new_query();
-    query.push_str("SELECT * FROM ").push_str(USER_TABLE);
+    query.push_str("SELECT * FROM ").push_identifier(USER_TABLE);
     if !filters.is_empty() {
         query.push_str(" WHERE ").append(filters);
     }
+    page::push_limit(&mut query, search_params.limit);
 
     app_state
         .database()
         .connection()
         .fetch(query)
-        .await
-        .map_err(|_| ())?
-        .into_iter()
-        .map(|row| User::try_from(row))
-        .collect::<Result<Vec<_>, _>>()
-        .map_err(|_| ())
+        .await?
+        .iter()
+        .map(User::from_row)
+        .collect()
 }
 
 pub async fn list(
     Extension(app_state): Extension<AppState>,
     Query(search_params): Query<SearchUser>,
-) -> Result<Json<Vec<UserResponse>>, (StatusCode, String)> {
-    let users = find_users(&app_state, search_params)
-        .await
-        .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "".to_string()))?;
+) -> Result<Json<Page<UserResponse>>, (StatusCode, String)> {
+    let limit = page::limit(search_params.limit);
+    let users = find_users(
+        &app_state,
+        SearchUser {
+            limit: Some(limit),
+            ..search_params
+        },
+    )
+    .await?;
     Ok(Json(
-        users
-            .into_iter()
-            .map(|user| UserResponse::from(user))
-            .collect(),
+        Page::new(users, limit, |user| &user.id).map(UserResponse::from),
     ))
 }
 
-pub async fn find_user_by_id(app_state: &AppState, user_id: String) -> Result<User, ()> {
+pub async fn find_user_by_id(app_state: &AppState, user_id: String) -> Result<User, Error> {
     find_users(
         app_state,
         SearchUser {
@@ -120,16 +129,14 @@ pub async fn find_user_by_id(app_state: &AppState, user_id: String) -> Result<Us
     )
     .await?
     .pop()
-    .ok_or(())
+    .ok_or(Error::NotFound)
 }
 
 pub async fn find(
     Extension(app_state): Extension<AppState>,
     Path(user_id): Path<String>,
 ) -> Result<Json<UserResponse>, (StatusCode, String)> {
-    let user = find_user_by_id(&app_state, user_id)
-        .await
-        .map_err(|_| (StatusCode::NOT_FOUND, "".to_string()))?;
+    let user = find_user_by_id(&app_state, user_id).await?;
     Ok(Json(UserResponse::from(user)))
 }
 
@@ -152,31 +159,36 @@ pub async fn create(
     values
         .push_str("?")
         .bind(user.id.clone().into())
-        .push_str(", ?")
+        .push_str("?")
         .bind(user.username.clone().into())
-        .push_str(", ?")
+        .push_str("?")
         .bind(user.password.clone().into())
-        .push_str(", ?")
+        .push_str("?")
         .bind(user.email.clone().into())
-        .push_str(", ?")
+        .push_str("?")
         .bind(user.role.clone().into())
-        .push_str(", ?")
-        .bind(user.created_at.clone().into())
-        .push_str(", ?")
-        .bind(user.updated_at.clone().into());
+        .push_str("?")
+        .bind(user.created_at.into())
+        .push_str("?")
+        .bind(user.updated_at.into());
     let mut query = app_state.new_query();
     query
         .push_str("INSERT INTO ")
-        .push_str(USER_TABLE)
-        .push_str(" (id, username, password, email, role, created_at, updated_at) VALUES (")
+        .push_identifier(USER_TABLE)
+        .push_str(" ")
+        .push_columns(&[
+            "id",
+            "username",
+            "password",
+            "email",
+            "role",
+            "created_at",
+            "updated_at",
+        ])
+        .push_str(" VALUES (")
         .append(values)
         .push_str(")");
-    app_state
-        .database()
-        .connection()
-        .execute(query)
-        .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+    app_state.database().connection().execute(query).await?;
 
     Ok(Json(UserResponse::from(user)))
 }
@@ -186,49 +198,38 @@ pub async fn update(
     Path(user_id): Path<String>,
     Json(payload): Json<UpdateUserRequest>,
 ) -> Result<Json<UserResponse>, (StatusCode, String)> {
-    let mut user = find_user_by_id(&app_state, user_id.clone())
-        .await
-        .map_err(|_| (StatusCode::NOT_FOUND, "".to_string()))?;
+    let mut user = find_user_by_id(&app_state, user_id.clone()).await?;
 
     user.updated_at = Some(util::now());
     let mut values = app_state.new_query();
     values.set_separator(", ");
-    values
-        .push_str("updated_at = ?")
-        .bind(user.updated_at.clone().into());
+    values.push_eq("updated_at", user.updated_at.into());
     if let Some(username) = payload.username.clone() {
         user.username = username.clone();
-        values.push_str("username = ?").bind(username.into());
+        values.push_eq("username", username.into());
     }
     if let Some(password) = payload.password.clone() {
         user.password = hash_password(password)
             .map_err(|_| (StatusCode::BAD_REQUEST, "malformed password".to_string()))?;
-        values
-            .push_str("password = ?")
-            .bind(user.password.clone().into());
+        values.push_eq("password", user.password.clone().into());
     }
     if let Some(email) = payload.email.clone() {
         user.email = Some(email.clone());
-        values.push_str("email = ?").bind(email.into());
+        values.push_eq("email", email.into());
     }
     if let Some(role) = payload.role.clone() {
         user.role = role.clone();
-        values.push_str("role = ?").bind(role.into());
+        values.push_eq("role", role.into());
     }
     let mut query = app_state.new_query();
     query
         .push_str("UPDATE ")
-        .push_str(USER_TABLE)
+        .push_identifier(USER_TABLE)
         .push_str(" SET ")
         .append(values)
-        .push_str(" WHERE id = ?")
-        .bind(user_id.clone().into());
-    app_state
-        .database()
-        .connection()
-        .execute(query)
-        .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+        .push_str(" WHERE ")
+        .push_eq("id", user_id.clone().into());
+    app_state.database().connection().execute(query).await?;
 
     Ok(Json(UserResponse::from(user)))
 }
@@ -237,22 +238,15 @@ pub async fn delete(
     Extension(app_state): Extension<AppState>,
     Path(user_id): Path<String>,
 ) -> Result<Json<UserResponse>, (StatusCode, String)> {
-    let user = find_user_by_id(&app_state, user_id.clone())
-        .await
-        .map_err(|_| (StatusCode::NOT_FOUND, "".to_string()))?;
+    let user = find_user_by_id(&app_state, user_id.clone()).await?;
 
     let mut query = app_state.new_query();
     query
         .push_str("DELETE FROM ")
-        .push_str(USER_TABLE)
-        .push_str(" WHERE id = ?")
-        .bind(user_id.into());
-    app_state
-        .database()
-        .connection()
-        .execute(query)
-        .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+        .push_identifier(USER_TABLE)
+        .push_str(" WHERE ")
+        .push_eq("id", user_id.into());
+    app_state.database().connection().execute(query).await?;
 
     Ok(Json(UserResponse::from(user)))
 }
@@ -334,16 +328,16 @@ pub async fn authenticate(
         .ok_or(StatusCode::UNAUTHORIZED)?
         .to_str()
         .map_err(|_| StatusCode::UNAUTHORIZED)
-        .and_then(|s| match s.split_once(" ") {
+        .and_then(|s| match s.split_once(' ') {
             Some((scheme, token)) => match scheme {
-                ref s if s.to_lowercase() == "bearer" => Ok(token),
+                s if s.to_lowercase() == "bearer" => Ok(token),
                 _ => Err(StatusCode::UNAUTHORIZED),
             },
             _ => Err(StatusCode::UNAUTHORIZED),
         })?;
 
     let claims = decode::<Claims>(
-        &access_token,
+        access_token,
         &DecodingKey::from_secret(config.jwt_secret.clone().as_bytes()),
         &Validation::default(),
     )
@@ -1,17 +1,88 @@
 use crate::{
+    archive::ArchiveStorage,
     database::{Database, Query},
-    taskqueue::{Dispatcher, Task},
+    extract::{Precedence, Providers, SiteExtractors},
+    link::{Canonicalizer, FetchPolicy, Fetcher},
+    taskqueue::Dispatcher,
 };
+use chrono::Duration;
+use std::sync::Arc;
 
 #[derive(Clone)]
 pub struct AppState {
     db: Database,
     dispatcher: Dispatcher,
+    canonicalizer: Arc<Canonicalizer>,
+    fetcher: Fetcher,
+    metatag_precedence: Arc<Precedence>,
+    metatag_ttl: Duration,
+    oembed_providers: Arc<Providers>,
+    site_extractors: Arc<SiteExtractors>,
+    archive_storage: ArchiveStorage,
 }
 
 impl AppState {
     pub fn new(db: Database, dispatcher: Dispatcher) -> Self {
-        Self { db, dispatcher }
+        Self {
+            db,
+            dispatcher,
+            canonicalizer: Default::default(),
+            fetcher: Default::default(),
+            metatag_precedence: Default::default(),
+            metatag_ttl: Duration::days(1),
+            oembed_providers: Default::default(),
+            site_extractors: Default::default(),
+            archive_storage: Default::default(),
+        }
+    }
+
+    pub fn with_canonicalizer(self, canonicalizer: Canonicalizer) -> Self {
+        Self {
+            canonicalizer: Arc::new(canonicalizer),
+            ..self
+        }
+    }
+
+    pub fn with_fetch_policy(self, policy: FetchPolicy) -> Self {
+        Self {
+            fetcher: Fetcher::new(policy),
+            ..self
+        }
+    }
+
+    pub fn with_metatag_precedence(self, precedence: Precedence) -> Self {
+        Self {
+            metatag_precedence: Arc::new(precedence),
+            ..self
+        }
+    }
+
+    pub fn with_metatag_ttl(self, ttl: Duration) -> Self {
+        Self {
+            metatag_ttl: ttl,
+            ..self
+        }
+    }
+
+    pub fn with_oembed_providers(self, providers: Providers) -> Self {
+        Self {
+            oembed_providers: Arc::new(providers),
+            ..self
+        }
+    }
+
+    pub fn with_site_extractors(self, extractors: SiteExtractors) -> Self {
+        Self {
+            site_extractors: Arc::new(extractors),
+            ..self
+        }
+    }
+
+    pub fn with_archive_storage(self, archive_storage: ArchiveStorage) -> Self {
+        Self {
+            archive_storage,
+            ..self
+        }
     }
 
     pub fn database(&self) -> &Database {
@@ -19,10 +90,40 @@ impl AppState {
     }
 
     pub fn new_query(&self) -> Query {
-        Query::new()
+        Query::with_dialect(self.db.dialect())
     }
 
     pub fn dispatcher(&self) -> &Dispatcher {
         &self.dispatcher
     }
+
+    pub fn canonicalizer(&self) -> &Canonicalizer {
+        &self.canonicalizer
+    }
+
+    /// Client for requests made on behalf of users, e.g. link checks.
+    pub fn fetcher(&self) -> &Fetcher {
+        &self.fetcher
+    }
+
+    pub fn metatag_precedence(&self) -> &Precedence {
+        &self.metatag_precedence
+    }
+
+    /// How long fetched metadata is served before being revalidated.
+    pub fn metatag_ttl(&self) -> Duration {
+        self.metatag_ttl
+    }
+
+    pub fn oembed_providers(&self) -> &Providers {
+        &self.oembed_providers
+    }
+
+    pub fn site_extractors(&self) -> &SiteExtractors {
+        &self.site_extractors
+    }
+
+    pub fn archive_storage(&self) -> &ArchiveStorage {
+        &self.archive_storage
+    }
 }
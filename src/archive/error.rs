@@ -0,0 +1,34 @@
+use crate::link::FetchError;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    Fetch(FetchError),
+    Unsupported(String),
+    Storage(String),
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Fetch(e) => write!(f, "fetch failed: {}", e),
+            Self::Unsupported(content_type) => {
+                write!(f, "unsupported content type {}", content_type)
+            }
+            Self::Storage(msg) => write!(f, "storage error: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<FetchError> for Error {
+    fn from(e: FetchError) -> Self {
+        Self::Fetch(e)
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Self {
+        Self::Storage(e.to_string())
+    }
+}
@@ -0,0 +1,7 @@
+mod error;
+mod page;
+mod storage;
+
+pub use error::*;
+pub use page::*;
+pub use storage::*;
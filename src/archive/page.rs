@@ -0,0 +1,438 @@
+use super::error::Error;
+use crate::link::{resolve, Fetched, Fetcher};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use encoding_rs::{Encoding, UTF_8, WINDOWS_1252};
+use regex::{Captures, Regex};
+use scraper::{node::Text, Html, Node, Selector};
+use std::{collections::HashMap, sync::OnceLock};
+use url::Url;
+
+pub const MAX_PAGE_SIZE: usize = 10 << 20;
+pub const MAX_ASSET_SIZE: usize = 5 << 20;
+/// Bytes of stylesheets and images inlined into one page at most, the rest
+/// are left linked.
+pub const MAX_INLINED_SIZE: usize = 40 << 20;
+
+/// Bytes of a page searched for a `<meta>` charset, as browsers do.
+const CHARSET_PRESCAN_SIZE: usize = 1024;
+
+fn meta_charset_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| {
+        Regex::new(r#"(?i)<meta[^>]+charset\s*=\s*["']?\s*([a-z0-9_:.\-]+)"#).unwrap()
+    })
+}
+
+/// The encoding of an HTML `body` by its BOM, the `charset` of the response
+/// and then its `<meta>` tags, or UTF-8 when it is valid as such.
+fn html_encoding(body: &[u8], charset: Option<&str>) -> &'static Encoding {
+    if let Some((encoding, _)) = Encoding::for_bom(body) {
+        return encoding;
+    }
+    if let Some(encoding) = charset.and_then(|charset| Encoding::for_label(charset.as_bytes())) {
+        return encoding;
+    }
+    let prescan = String::from_utf8_lossy(&body[..body.len().min(CHARSET_PRESCAN_SIZE)]);
+    let meta = meta_charset_pattern()
+        .captures(&prescan)
+        .and_then(|captures| Encoding::for_label(captures[1].as_bytes()));
+    match meta {
+        // A page read as ASCII cannot be UTF-16, the label is wrong
+        Some(encoding) => encoding.output_encoding(),
+        None if std::str::from_utf8(body).is_ok() => UTF_8,
+        None => WINDOWS_1252,
+    }
+}
+
+/// `body` as text, by its BOM or `charset` and UTF-8 otherwise.
+fn decode_text(body: &[u8], charset: Option<&str>) -> String {
+    let encoding = charset
+        .and_then(|charset| Encoding::for_label(charset.as_bytes()))
+        .unwrap_or(UTF_8);
+    encoding.decode(body).0.into_owned()
+}
+
+fn css_url_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN
+        .get_or_init(|| Regex::new(r#"url\(\s*(?:"([^"]*)"|'([^']*)'|([^)"'\s]*))\s*\)"#).unwrap())
+}
+
+fn css_urls<'a>(css: &'a str, base: &'a Url) -> impl Iterator<Item = Url> + 'a {
+    css_url_pattern()
+        .captures_iter(css)
+        .filter_map(|captures| resolve(base, captured_url(&captures)))
+}
+
+fn captured_url<'a>(captures: &Captures<'a>) -> &'a str {
+    (1..=3)
+        .find_map(|i| captures.get(i))
+        .map_or("", |m| m.as_str())
+}
+
+/// Stylesheets and data urls of the assets of a page, by absolute url.
+#[derive(Default)]
+struct Assets {
+    stylesheets: HashMap<Url, String>,
+    data_urls: HashMap<Url, String>,
+    inlined_size: usize,
+}
+
+impl Assets {
+    async fn inline(&mut self, fetcher: &Fetcher, url: Url) {
+        if self.data_urls.contains_key(&url) || self.inlined_size >= MAX_INLINED_SIZE {
+            return;
+        }
+        match fetcher.fetch(url.as_str(), MAX_ASSET_SIZE).await {
+            Ok(asset) => {
+                let mime = match asset.content_type.is_empty() {
+                    true => "application/octet-stream",
+                    false => asset.content_type.as_str(),
+                };
+                let data_url = format!("data:{};base64,{}", mime, STANDARD.encode(&asset.body));
+                self.inlined_size += data_url.len();
+                self.data_urls.insert(url, data_url);
+            }
+            Err(e) => log::debug!("archiving {} failed - {}", url, e),
+        }
+    }
+
+    async fn inline_stylesheet(&mut self, fetcher: &Fetcher, url: Url) {
+        if self.stylesheets.contains_key(&url) || self.inlined_size >= MAX_INLINED_SIZE {
+            return;
+        }
+        match fetcher.fetch(url.as_str(), MAX_ASSET_SIZE).await {
+            Ok(stylesheet) => {
+                let css = decode_text(&stylesheet.body, stylesheet.charset.as_deref());
+                for asset_url in css_urls(&css, &stylesheet.url).collect::<Vec<_>>() {
+                    self.inline(fetcher, asset_url).await;
+                }
+                let css = self.rewrite_css(&css, &stylesheet.url);
+                self.inlined_size += css.len();
+                self.stylesheets.insert(url, css);
+            }
+            Err(e) => log::debug!("archiving {} failed - {}", url, e),
+        }
+    }
+
+    /// Points the urls of `css` at their data urls, or at least makes them
+    /// absolute.
+    fn rewrite_css(&self, css: &str, base: &Url) -> String {
+        css_url_pattern()
+            .replace_all(css, |captures: &Captures| {
+                match resolve(base, captured_url(captures)) {
+                    Some(url) => format!(
+                        "url(\"{}\")",
+                        self.data_urls
+                            .get(&url)
+                            .map_or(url.as_str(), |d| d.as_str())
+                    ),
+                    None => captures[0].to_string(),
+                }
+            })
+            // Keeps stylesheets from closing the `<style>` they end up in
+            .replace("</style", "<\\/style")
+    }
+
+    fn src(&self, base: &Url, href: &str) -> Option<String> {
+        let url = resolve(base, href)?;
+        Some(self.data_urls.get(&url).cloned().unwrap_or(url.into()))
+    }
+}
+
+/// The assets of `document`: stylesheets, and images and whatever its inline
+/// styles refer to.
+fn collect_assets(document: &Html, base: &Url) -> (Vec<Url>, Vec<Url>) {
+    let select = |selector: &str, attr: &str| {
+        document
+            .select(&Selector::parse(selector).unwrap())
+            .filter_map(|e| e.value().attr(attr))
+            .filter_map(|href| resolve(base, href))
+            .collect::<Vec<_>>()
+    };
+    let stylesheets = select(r#"link[rel~="stylesheet" i][href]"#, "href");
+    let mut assets = select("img[src]", "src");
+    assets.extend(select(r#"link[rel~="icon" i][href]"#, "href"));
+    for style in document.select(&Selector::parse("style").unwrap()) {
+        assets.extend(css_urls(&style.text().collect::<String>(), base).collect::<Vec<_>>());
+    }
+    for element in document.select(&Selector::parse("[style]").unwrap()) {
+        let css = element.value().attr("style").unwrap_or_default();
+        assets.extend(css_urls(css, base).collect::<Vec<_>>());
+    }
+    (stylesheets, assets)
+}
+
+/// Inlines the assets of `document` and strips whatever could run or load
+/// anything: scripts, frames, event handlers and `srcset`s.
+fn rewrite(document: &mut Html, base: &Url, assets: &Assets) {
+    let ids = document
+        .tree
+        .nodes()
+        .map(|node| node.id())
+        .collect::<Vec<_>>();
+    for id in ids {
+        let in_style = document
+            .tree
+            .get(id)
+            .and_then(|node| node.parent())
+            .and_then(|parent| parent.value().as_element().map(|e| e.name() == "style"))
+            .unwrap_or(false);
+        let Some(mut node) = document.tree.get_mut(id) else {
+            continue;
+        };
+        let element = match node.value() {
+            Node::Text(text) if in_style => {
+                text.text = assets.rewrite_css(&text.text, base).into();
+                continue;
+            }
+            Node::Element(element) => element,
+            _ => continue,
+        };
+
+        if matches!(
+            element.name(),
+            "script" | "iframe" | "frame" | "object" | "embed" | "base"
+        ) {
+            node.detach();
+            continue;
+        }
+
+        element.attrs.retain(|name, value| {
+            let name = name.local.to_lowercase();
+            let is_script = match name.as_str() {
+                "href" | "src" | "action" => {
+                    value.trim_start().to_lowercase().starts_with("javascript:")
+                }
+                name => name.starts_with("on"),
+            };
+            !is_script && !matches!(name.as_str(), "srcset" | "integrity" | "nonce")
+        });
+        let stylesheet = match element.name() {
+            "link" if element.attr("rel").is_some_and(is_stylesheet) => element
+                .attr("href")
+                .and_then(|href| resolve(base, href))
+                .and_then(|url| assets.stylesheets.get(&url)),
+            _ => None,
+        };
+        let is_inlined = |name: &str| matches!(name, "img" | "link");
+        let element_name = element.name().to_string();
+        for (name, value) in element.attrs.iter_mut() {
+            match &*name.local {
+                "href" | "src" => {
+                    let src = match is_inlined(&element_name) {
+                        true => assets.src(base, value),
+                        false => resolve(base, value).map(String::from),
+                    };
+                    if let Some(src) = src {
+                        *value = src.into();
+                    }
+                }
+                "style" => *value = assets.rewrite_css(value, base).into(),
+                _ => {}
+            }
+        }
+
+        if let Some(css) = stylesheet {
+            element.name.local = "style".into();
+            element.attrs.clear();
+            node.append(Node::Text(Text {
+                text: css.clone().into(),
+            }));
+        }
+    }
+}
+
+fn is_stylesheet(rel: &str) -> bool {
+    rel.split_ascii_whitespace()
+        .any(|rel| rel.eq_ignore_ascii_case("stylesheet"))
+}
+
+/// An HTML page as fetched, `url` being where it ended up after redirects.
+#[derive(Debug, Clone)]
+pub struct Page {
+    pub url: Url,
+    pub html: String,
+}
+
+impl Page {
+    /// Decodes the fetched HTML by its charset.
+    pub fn decode(fetched: Fetched) -> Self {
+        let encoding = html_encoding(&fetched.body, fetched.charset.as_deref());
+        Self {
+            url: fetched.url,
+            html: encoding
+                .decode_with_bom_removal(&fetched.body)
+                .0
+                .into_owned(),
+        }
+    }
+
+    /// The url relative links of the page resolve against.
+    pub fn base_url(&self, document: &Html) -> Url {
+        document
+            .select(&Selector::parse("base[href]").unwrap())
+            .next()
+            .and_then(|base| base.value().attr("href"))
+            .and_then(|href| resolve(&self.url, href))
+            .unwrap_or_else(|| self.url.clone())
+    }
+}
+
+pub fn is_html(content_type: &str) -> bool {
+    matches!(content_type, "" | "text/html" | "application/xhtml+xml")
+}
+
+pub async fn fetch_page(fetcher: &Fetcher, url: &str) -> Result<Page, Error> {
+    let page = fetcher.fetch(url, MAX_PAGE_SIZE).await?;
+    if !is_html(&page.content_type) {
+        return Err(Error::Unsupported(page.content_type));
+    }
+    Ok(Page::decode(page))
+}
+
+/// Returns `page` as a single HTML document, with its stylesheets and images
+/// inlined as data urls and its scripts removed.
+pub async fn inline_page(fetcher: &Fetcher, page: &Page) -> String {
+    // The document is parsed again for the rewrite, `Html` cannot be held
+    // across the requests for the assets
+    let (base, stylesheets, images) = {
+        let document = Html::parse_document(&page.html);
+        let base = page.base_url(&document);
+        let (stylesheets, images) = collect_assets(&document, &base);
+        (base, stylesheets, images)
+    };
+    let mut assets = Assets::default();
+    for stylesheet in stylesheets {
+        assets.inline_stylesheet(fetcher, stylesheet).await;
+    }
+    for image in images {
+        assets.inline(fetcher, image).await;
+    }
+
+    let mut document = Html::parse_document(&page.html);
+    rewrite(&mut document, &base, &assets);
+    document.html()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        link::{FetchError, FetchPolicy},
+        testutil,
+    };
+    use axum::{http::header, routing::get, Router};
+
+    const PAGE: &str = r#"<!DOCTYPE html>
+<html><head>
+<title>Archived</title>
+<link rel="stylesheet" href="style.css">
+<style>body { background: url(bg.png) }</style>
+<script>alert(1)</script>
+</head><body>
+<a href="/other" onclick="steal()">Other</a>
+<a href="javascript:steal()">Run</a>
+<img src="/pixel.png" srcset="/pixel-2x.png 2x">
+<img src="/missing.png">
+<iframe src="https://example.com/"></iframe>
+</body></html>"#;
+
+    async fn serve_stub() -> String {
+        let png = || async { ([(header::CONTENT_TYPE, "image/png")], b"png".to_vec()) };
+        let router = Router::new()
+            .route(
+                "/page",
+                get(|| async { ([(header::CONTENT_TYPE, "text/html")], PAGE) }),
+            )
+            .route(
+                "/style.css",
+                get(|| async {
+                    (
+                        [(header::CONTENT_TYPE, "text/css")],
+                        r#"h1 { background: url("pixel.png") }"#,
+                    )
+                }),
+            )
+            .route("/pixel.png", get(png))
+            .route("/bg.png", get(png))
+            .route(
+                "/data.json",
+                get(|| async { ([(header::CONTENT_TYPE, "application/json")], "{}") }),
+            );
+        testutil::serve(router).await
+    }
+
+    #[tokio::test]
+    async fn archive() {
+        let base = serve_stub().await;
+        let fetcher = Fetcher::new(FetchPolicy {
+            allow_private: true,
+            ..Default::default()
+        });
+        let png = format!("data:image/png;base64,{}", STANDARD.encode("png"));
+
+        let page = fetch_page(&fetcher, &format!("{}/page", base))
+            .await
+            .unwrap();
+        let html = inline_page(&fetcher, &page).await;
+        assert!(html.contains(&format!(
+            r#"<style>h1 {{ background: url("{}") }}</style>"#,
+            png
+        )));
+        assert!(html.contains(&format!(r#"body {{ background: url("{}") }}"#, png)));
+        assert!(html.contains(&format!(r#"<img src="{}">"#, png)));
+        assert!(html.contains(&format!(r#"<img src="{}/missing.png">"#, base)));
+        assert!(html.contains(&format!(r#"<a href="{}/other">Other</a>"#, base)));
+        assert!(html.contains("<a>Run</a>"));
+        for stripped in ["<script", "alert", "steal", "srcset", "<iframe", "<link"] {
+            assert!(!html.contains(stripped), "{} was kept", stripped);
+        }
+
+        assert_eq!(
+            fetch_page(&fetcher, &format!("{}/data.json", base))
+                .await
+                .unwrap_err(),
+            Error::Unsupported("application/json".to_string())
+        );
+        assert!(matches!(
+            fetch_page(&fetcher, &format!("{}/gone", base)).await,
+            Err(Error::Fetch(FetchError::Status(404)))
+        ));
+    }
+
+    #[test]
+    fn decode_charsets() {
+        let decode = |body: &[u8], charset: Option<&str>| {
+            Page::decode(Fetched {
+                url: Url::parse("https://example.com/").unwrap(),
+                content_type: "text/html".to_string(),
+                charset: charset.map(String::from),
+                etag: None,
+                last_modified: None,
+                body: body.to_vec(),
+            })
+            .html
+        };
+        let encode = |encoding: &'static Encoding, html: &str| encoding.encode(html).0.into_owned();
+
+        let html = r#"<meta charset="shift_jis"><title>日本語</title>"#;
+        let shift_jis = encode(encoding_rs::SHIFT_JIS, html);
+        assert_eq!(decode(&shift_jis, None), html);
+        let html =
+            r#"<meta http-equiv="Content-Type" content="text/html; charset=GBK"><p>中文</p>"#;
+        assert_eq!(decode(&encode(encoding_rs::GBK, html), None), html);
+
+        // The response charset wins over the page
+        let html = "<meta charset=utf-8><p>Привет</p>";
+        let windows_1251 = encode(encoding_rs::WINDOWS_1251, html);
+        assert_eq!(decode(&windows_1251, Some("windows-1251")), html);
+        assert_eq!(
+            decode(b"\xef\xbb\xbf<p>\xc3\xa9</p>", Some("iso-8859-1")),
+            "<p>é</p>"
+        );
+
+        assert_eq!(decode("<p>é</p>".as_bytes(), None), "<p>é</p>");
+        assert_eq!(decode(b"<p>\xe9</p>", None), "<p>é</p>");
+    }
+}
@@ -0,0 +1,152 @@
+use super::error::Error;
+use std::{
+    collections::HashMap,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
+
+#[trait_variant::make(Storage: Send)]
+pub trait LocalStorage {
+    async fn put(&self, key: &str, data: Vec<u8>) -> Result<(), Error>;
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>, Error>;
+}
+
+#[derive(Clone)]
+pub enum ArchiveStorage {
+    File(FileStorage),
+    Memory(MemoryStorage),
+}
+
+impl Default for ArchiveStorage {
+    fn default() -> Self {
+        Self::Memory(MemoryStorage::default())
+    }
+}
+
+/// Opens the storage at `url`, `file://<directory>` or `memory:`.
+pub fn open(url: &str) -> Result<ArchiveStorage, Error> {
+    match url.split_once(':') {
+        Some(("file", path)) => Ok(ArchiveStorage::File(FileStorage::new(
+            path.trim_start_matches("//"),
+        ))),
+        Some(("memory", _)) => Ok(ArchiveStorage::Memory(MemoryStorage::default())),
+        _ => Err(Error::Storage(format!("unsupported storage {}", url))),
+    }
+}
+
+impl Storage for ArchiveStorage {
+    async fn put(&self, key: &str, data: Vec<u8>) -> Result<(), Error> {
+        match self {
+            Self::File(storage) => Storage::put(storage, key, data).await,
+            Self::Memory(storage) => Storage::put(storage, key, data).await,
+        }
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>, Error> {
+        match self {
+            Self::File(storage) => Storage::get(storage, key).await,
+            Self::Memory(storage) => Storage::get(storage, key).await,
+        }
+    }
+}
+
+/// Keeps every item in a file named after its key under `root`.
+#[derive(Clone)]
+pub struct FileStorage {
+    root: PathBuf,
+}
+
+impl FileStorage {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    fn path(&self, key: &str) -> Result<PathBuf, Error> {
+        if key
+            .split('/')
+            .any(|part| part.is_empty() || part == "." || part == "..")
+        {
+            return Err(Error::Storage(format!("invalid key {}", key)));
+        }
+        Ok(self.root.join(key))
+    }
+}
+
+impl Storage for FileStorage {
+    async fn put(&self, key: &str, data: Vec<u8>) -> Result<(), Error> {
+        let path = self.path(key)?;
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        // Written aside first so that readers never see a partial file, named
+        // after the whole file name so that `a.html` and `a.json` do not share it
+        let mut partial = path.clone().into_os_string();
+        partial.push(".partial");
+        tokio::fs::write(&partial, data).await?;
+        tokio::fs::rename(&partial, &path).await?;
+        Ok(())
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>, Error> {
+        match tokio::fs::read(self.path(key)?).await {
+            Ok(data) => Ok(Some(data)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+/// Keeps items in memory only, they are gone once the process exits.
+#[derive(Clone, Default)]
+pub struct MemoryStorage {
+    items: Arc<Mutex<HashMap<String, Vec<u8>>>>,
+}
+
+impl Storage for MemoryStorage {
+    async fn put(&self, key: &str, data: Vec<u8>) -> Result<(), Error> {
+        self.items.lock().unwrap().insert(key.to_string(), data);
+        Ok(())
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>, Error> {
+        Ok(self.items.lock().unwrap().get(key).cloned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn file_storage() {
+        let root = std::env::temp_dir().join(format!("achiet-{}", ulid::Ulid::new()));
+        let storage = open(&format!("file://{}", root.display())).unwrap();
+
+        Storage::put(&storage, "a/b.html", b"archived".to_vec())
+            .await
+            .unwrap();
+        assert_eq!(
+            Storage::get(&storage, "a/b.html").await.unwrap(),
+            Some(b"archived".to_vec())
+        );
+        assert_eq!(Storage::get(&storage, "a/c.html").await.unwrap(), None);
+
+        let (html, json) = tokio::join!(
+            Storage::put(&storage, "a/d.html", b"html".to_vec()),
+            Storage::put(&storage, "a/d.json", b"json".to_vec()),
+        );
+        html.unwrap();
+        json.unwrap();
+        assert_eq!(
+            Storage::get(&storage, "a/d.json").await.unwrap(),
+            Some(b"json".to_vec())
+        );
+        assert_eq!(
+            Storage::get(&storage, "a/d.html").await.unwrap(),
+            Some(b"html".to_vec())
+        );
+        assert!(Storage::get(&storage, "../b.html").await.is_err());
+
+        tokio::fs::remove_dir_all(root).await.unwrap();
+    }
+}
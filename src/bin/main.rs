@@ -1,26 +1,92 @@
-use achiet::{api, app::AppState, database, taskqueue};
+use achiet::{
+    api,
+    app::AppState,
+    archive, database,
+    extract::{Precedence, Providers},
+    link::{Canonicalizer, FetchPolicy},
+    taskqueue,
+};
 
 #[tokio::main]
 async fn main() {
-    let dsn = "sqlite://app.db";
+    let dsn = std::env::var("DATABASE_URL").unwrap_or("sqlite://app.db".to_string());
+    let archive_url = std::env::var("ARCHIVE_URL").unwrap_or("file://archive".to_string());
+    let allow_private_urls = std::env::var("ALLOW_PRIVATE_URLS").is_ok_and(|v| v == "true");
+    let metatag_precedence = std::env::var("METATAG_PRECEDENCE")
+        .map(|s| {
+            s.parse::<Precedence>()
+                .unwrap_or_else(|e| panic!("METATAG_PRECEDENCE: invalid precedence {}: {}", s, e))
+        })
+        .unwrap_or_default();
+    let oembed_providers = std::env::var("OEMBED_PROVIDERS")
+        .map(|path| {
+            let json = std::fs::read_to_string(&path)
+                .unwrap_or_else(|e| panic!("OEMBED_PROVIDERS: cannot read {}: {}", path, e));
+            Providers::from_json(&json)
+                .unwrap_or_else(|e| panic!("OEMBED_PROVIDERS: invalid registry in {}: {}", path, e))
+        })
+        .unwrap_or_default();
+    let canonical_rules = std::env::var("CANONICAL_RULES")
+        .map(|path| {
+            let json = std::fs::read_to_string(&path)
+                .unwrap_or_else(|e| panic!("CANONICAL_RULES: cannot read {}: {}", path, e));
+            Canonicalizer::from_json(&json)
+                .unwrap_or_else(|e| panic!("CANONICAL_RULES: invalid rules in {}: {}", path, e))
+        })
+        .unwrap_or_default();
+    let metatag_ttl = std::env::var("METATAG_TTL")
+        .map(|secs| {
+            chrono::Duration::seconds(
+                secs.parse()
+                    .unwrap_or_else(|e| panic!("METATAG_TTL: invalid seconds {}: {}", secs, e)),
+            )
+        })
+        .ok();
     let worker_count = 4u32;
 
     env_logger::init();
 
-    let db = database::connect(dsn.to_string()).await.unwrap();
+    let db = database::connect(dsn).await.unwrap();
+    match database::migrate(&db).await {
+        Ok(versions) => versions
+            .iter()
+            .for_each(|version| log::info!("applied migration {}", version)),
+        Err(e) => panic!("{}", e),
+    }
+    if std::env::args().nth(1).as_deref() == Some("migrate") {
+        return;
+    }
+
     let (dispatcher, worker) = taskqueue::channel();
 
-    let app_state = AppState::new(db, dispatcher.clone());
-    let rt = tokio::runtime::Runtime::new().unwrap();
+    let app_state = AppState::new(db, dispatcher.clone())
+        .with_archive_storage(archive::open(&archive_url).unwrap())
+        .with_fetch_policy(FetchPolicy {
+            allow_private: allow_private_urls,
+            ..Default::default()
+        })
+        .with_canonicalizer(canonical_rules)
+        .with_metatag_precedence(metatag_precedence)
+        .with_oembed_providers(oembed_providers);
+    let app_state = match metatag_ttl {
+        Some(ttl) => app_state.with_metatag_ttl(ttl),
+        None => app_state,
+    };
 
-    for i in 0..worker_count {
+    // Workers block on the task channel, so they get threads of their own
+    // rather than starving the runtime
+    let handle = tokio::runtime::Handle::current();
+    for _ in 0..worker_count {
         let app_state_clone = app_state.clone();
         let worker_clone = worker.clone();
-        tokio::spawn(async move {
-            let _ = worker_clone.work(app_state_clone).await;
+        let handle = handle.clone();
+        std::thread::spawn(move || {
+            let _ = handle.block_on(worker_clone.work(app_state_clone));
         });
     }
 
+    tokio::spawn(taskqueue::Scheduler::default().run(app_state.clone()));
+
     let api_handler = api::new_handler(
         app_state.clone(),
         api::Config {
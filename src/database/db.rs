@@ -1,36 +1,355 @@
 use super::{
+    dialect::Dialect,
+    error::Error,
+    mysql::{MySqlConnection, MySqlTransaction},
+    postgres::{PostgresConnection, PostgresTransaction},
     query::{Query, Row},
-    sqlite::SqliteConnection,
+    sqlite::{SqliteConnection, SqliteTransaction},
 };
+use futures_util::{stream::BoxStream, StreamExt};
+use std::{
+    future::Future,
+    sync::{Arc, Mutex},
+};
+use tokio::sync::mpsc;
 
 #[derive(Clone)]
 pub enum Database {
     Sqlite(SqliteConnection),
-    MySql,
-    Postgres,
+    MySql(MySqlConnection),
+    Postgres(PostgresConnection),
 }
 
 impl Database {
     pub fn connection(&self) -> impl Connection {
+        self.clone()
+    }
+
+    pub fn dialect(&self) -> Dialect {
         match self {
-            Self::Sqlite(conn) => (*conn).clone(),
-            _ => todo!(),
+            Self::Sqlite(_) => Dialect::Sqlite,
+            Self::MySql(_) => Dialect::MySql,
+            Self::Postgres(_) => Dialect::Postgres,
+        }
+    }
+}
+
+pub type CommitHook = Box<dyn FnOnce() + Send>;
+
+pub type RowStream = BoxStream<'static, Result<Row, Error>>;
+
+/// Number of rows buffered ahead of the consumer of a `RowStream`.
+const STREAM_BUFFER: usize = 64;
+
+/// Runs `produce` in its own task and streams what it sends. The channel is
+/// bounded, so the producer only reads ahead of the consumer by a few rows,
+/// and it is closed once the stream is dropped.
+pub(crate) fn row_stream<F, Fut>(produce: F) -> RowStream
+where
+    F: FnOnce(mpsc::Sender<Result<Row, Error>>) -> Fut,
+    Fut: Future<Output = ()> + Send + 'static,
+{
+    let (sender, receiver) = mpsc::channel(STREAM_BUFFER);
+    tokio::spawn(produce(sender));
+    futures_util::stream::unfold(receiver, |mut receiver| async move {
+        receiver.recv().await.map(|row| (row, receiver))
+    })
+    .boxed()
+}
+
+/// Sends the rows of an sqlx cursor until it is exhausted or the receiving
+/// stream is dropped.
+pub(crate) async fn forward_rows<R>(
+    mut rows: BoxStream<'_, Result<R, sqlx::Error>>,
+    sender: &mpsc::Sender<Result<Row, Error>>,
+) where
+    Row: TryFrom<R, Error = Error>,
+{
+    while let Some(row) = rows.next().await {
+        let row = row.map_err(Error::from).and_then(Row::try_from);
+        let failed = row.is_err();
+        if sender.send(row).await.is_err() || failed {
+            break;
         }
     }
 }
 
 #[trait_variant::make(Connection: Send)]
 pub trait LocalConnection {
-    async fn fetch(&self, query: Query) -> Result<Vec<Row>, ()>;
-    async fn fetch_one(&self, query: Query) -> Result<Row, ()>;
-    async fn execute(&self, query: Query) -> Result<(), String>;
+    async fn fetch(&self, query: Query) -> Result<Vec<Row>, Error>;
+    async fn fetch_one(&self, query: Query) -> Result<Row, Error>;
+    async fn execute(&self, query: Query) -> Result<(), Error>;
+    async fn begin(&self) -> Result<Transaction, Error>;
+    /// Streams the rows of `query` as they are read from the database. Within
+    /// a transaction, other queries wait until the stream is done or dropped.
+    fn fetch_stream(&self, query: Query) -> RowStream;
+    /// Runs `hook` once the changes made so far are committed, immediately
+    /// when there is no transaction in progress.
+    fn after_commit(&self, hook: CommitHook);
+}
+
+impl Connection for Database {
+    async fn fetch(&self, query: Query) -> Result<Vec<Row>, Error> {
+        match self {
+            Self::Sqlite(conn) => Connection::fetch(conn, query).await,
+            Self::Postgres(conn) => Connection::fetch(conn, query).await,
+            Self::MySql(conn) => Connection::fetch(conn, query).await,
+        }
+    }
+
+    async fn fetch_one(&self, query: Query) -> Result<Row, Error> {
+        match self {
+            Self::Sqlite(conn) => Connection::fetch_one(conn, query).await,
+            Self::Postgres(conn) => Connection::fetch_one(conn, query).await,
+            Self::MySql(conn) => Connection::fetch_one(conn, query).await,
+        }
+    }
+
+    async fn execute(&self, query: Query) -> Result<(), Error> {
+        match self {
+            Self::Sqlite(conn) => Connection::execute(conn, query).await,
+            Self::Postgres(conn) => Connection::execute(conn, query).await,
+            Self::MySql(conn) => Connection::execute(conn, query).await,
+        }
+    }
+
+    async fn begin(&self) -> Result<Transaction, Error> {
+        match self {
+            Self::Sqlite(conn) => Connection::begin(conn).await,
+            Self::Postgres(conn) => Connection::begin(conn).await,
+            Self::MySql(conn) => Connection::begin(conn).await,
+        }
+    }
+
+    fn fetch_stream(&self, query: Query) -> RowStream {
+        match self {
+            Self::Sqlite(conn) => Connection::fetch_stream(conn, query),
+            Self::Postgres(conn) => Connection::fetch_stream(conn, query),
+            Self::MySql(conn) => Connection::fetch_stream(conn, query),
+        }
+    }
+
+    fn after_commit(&self, hook: CommitHook) {
+        hook()
+    }
 }
 
-pub async fn connect(dsn: String) -> Result<Database, ()> {
+#[derive(Clone)]
+pub(crate) enum TransactionHandle {
+    Sqlite(SqliteTransaction),
+    MySql(MySqlTransaction),
+    Postgres(PostgresTransaction),
+}
+
+/// A transaction shared by all of its clones. Beginning a transaction from
+/// another one opens a savepoint, committed or rolled back on its own.
+#[derive(Clone)]
+pub struct Transaction {
+    handle: TransactionHandle,
+    depth: usize,
+    hooks: Arc<Mutex<Vec<(usize, CommitHook)>>>,
+}
+
+impl Transaction {
+    pub(crate) fn new(handle: TransactionHandle) -> Self {
+        Self {
+            handle,
+            depth: 0,
+            hooks: Default::default(),
+        }
+    }
+
+    fn savepoint(&self) -> String {
+        format!("_savepoint_{}", self.depth)
+    }
+
+    pub async fn commit(self) -> Result<(), Error> {
+        if self.depth > 0 {
+            let query = format!("RELEASE SAVEPOINT {}", self.savepoint());
+            return Connection::execute(&self, query.into()).await;
+        }
+
+        match &self.handle {
+            TransactionHandle::Sqlite(tx) => tx.commit().await,
+            TransactionHandle::Postgres(tx) => tx.commit().await,
+            TransactionHandle::MySql(tx) => tx.commit().await,
+        }?;
+
+        let hooks = std::mem::take(&mut *self.hooks.lock().unwrap());
+        hooks.into_iter().for_each(|(_, hook)| hook());
+        Ok(())
+    }
+
+    pub async fn rollback(self) -> Result<(), Error> {
+        self.hooks
+            .lock()
+            .unwrap()
+            .retain(|(depth, _)| *depth < self.depth);
+
+        if self.depth > 0 {
+            let query = format!("ROLLBACK TO SAVEPOINT {}", self.savepoint());
+            return Connection::execute(&self, query.into()).await;
+        }
+
+        match &self.handle {
+            TransactionHandle::Sqlite(tx) => tx.rollback().await,
+            TransactionHandle::Postgres(tx) => tx.rollback().await,
+            TransactionHandle::MySql(tx) => tx.rollback().await,
+        }
+    }
+}
+
+impl Connection for Transaction {
+    async fn fetch(&self, query: Query) -> Result<Vec<Row>, Error> {
+        match &self.handle {
+            TransactionHandle::Sqlite(tx) => tx.fetch(query).await,
+            TransactionHandle::Postgres(tx) => tx.fetch(query).await,
+            TransactionHandle::MySql(tx) => tx.fetch(query).await,
+        }
+    }
+
+    async fn fetch_one(&self, query: Query) -> Result<Row, Error> {
+        match &self.handle {
+            TransactionHandle::Sqlite(tx) => tx.fetch_one(query).await,
+            TransactionHandle::Postgres(tx) => tx.fetch_one(query).await,
+            TransactionHandle::MySql(tx) => tx.fetch_one(query).await,
+        }
+    }
+
+    async fn execute(&self, query: Query) -> Result<(), Error> {
+        match &self.handle {
+            TransactionHandle::Sqlite(tx) => tx.execute(query).await,
+            TransactionHandle::Postgres(tx) => tx.execute(query).await,
+            TransactionHandle::MySql(tx) => tx.execute(query).await,
+        }
+    }
+
+    async fn begin(&self) -> Result<Transaction, Error> {
+        let tx = Self {
+            handle: self.handle.clone(),
+            depth: self.depth + 1,
+            hooks: self.hooks.clone(),
+        };
+        let query = format!("SAVEPOINT {}", tx.savepoint());
+        Connection::execute(self, query.into()).await?;
+        Ok(tx)
+    }
+
+    fn fetch_stream(&self, query: Query) -> RowStream {
+        match &self.handle {
+            TransactionHandle::Sqlite(tx) => tx.fetch_stream(query),
+            TransactionHandle::Postgres(tx) => tx.fetch_stream(query),
+            TransactionHandle::MySql(tx) => tx.fetch_stream(query),
+        }
+    }
+
+    fn after_commit(&self, hook: CommitHook) {
+        self.hooks.lock().unwrap().push((self.depth, hook));
+    }
+}
+
+pub async fn connect(dsn: String) -> Result<Database, Error> {
     match dsn {
-        ref s if s.starts_with("sqlite:") => Ok(Database::Sqlite(
-            SqliteConnection::connect(dsn).await.unwrap(),
-        )),
-        _ => todo!(),
+        ref s if s.starts_with("sqlite:") => {
+            Ok(Database::Sqlite(SqliteConnection::connect(dsn).await?))
+        }
+        ref s if s.starts_with("postgres:") || s.starts_with("postgresql:") => {
+            Ok(Database::Postgres(PostgresConnection::connect(dsn).await?))
+        }
+        ref s if s.starts_with("mysql:") => {
+            Ok(Database::MySql(MySqlConnection::connect(dsn).await?))
+        }
+        _ => Err(Error::Connection(format!(
+            "unsupported database url {}",
+            dsn
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    async fn count(conn: &impl Connection) -> usize {
+        Connection::fetch(conn, "SELECT id FROM item".into())
+            .await
+            .unwrap()
+            .len()
+    }
+
+    #[tokio::test]
+    async fn transaction() {
+        let db = testutil::connect().await;
+        Connection::execute(&db, "CREATE TABLE item (id VARCHAR(32))".into())
+            .await
+            .unwrap();
+
+        let tx = Connection::begin(&db).await.unwrap();
+        Connection::execute(&tx, "INSERT INTO item VALUES ('a')".into())
+            .await
+            .unwrap();
+        assert_eq!(count(&tx).await, 1);
+        tx.rollback().await.unwrap();
+        assert_eq!(count(&db).await, 0);
+
+        let committed = Arc::new(AtomicUsize::new(0));
+        let tx = Connection::begin(&db).await.unwrap();
+        Connection::execute(&tx, "INSERT INTO item VALUES ('a')".into())
+            .await
+            .unwrap();
+        let savepoint = Connection::begin(&tx).await.unwrap();
+        Connection::execute(&savepoint, "INSERT INTO item VALUES ('b')".into())
+            .await
+            .unwrap();
+        let hook_counter = committed.clone();
+        Connection::after_commit(
+            &savepoint,
+            Box::new(move || {
+                hook_counter.fetch_add(1, Ordering::SeqCst);
+            }),
+        );
+        savepoint.rollback().await.unwrap();
+        let hook_counter = committed.clone();
+        Connection::after_commit(
+            &tx,
+            Box::new(move || {
+                hook_counter.fetch_add(1, Ordering::SeqCst);
+            }),
+        );
+        assert_eq!(committed.load(Ordering::SeqCst), 0);
+        tx.commit().await.unwrap();
+
+        assert_eq!(count(&db).await, 1);
+        assert_eq!(committed.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn fetch_stream() {
+        let db = testutil::connect().await;
+        Connection::execute(&db, "CREATE TABLE item (id INTEGER)".into())
+            .await
+            .unwrap();
+        let values = (0..200).map(|i| format!("({})", i)).collect::<Vec<_>>();
+        let query = format!("INSERT INTO item VALUES {}", values.join(", "));
+        Connection::execute(&db, query.into()).await.unwrap();
+
+        let query = || Query::from("SELECT id FROM item ORDER BY id");
+        let ids = Connection::fetch_stream(&db, query())
+            .map(|row| row.unwrap().get::<i32>("id").unwrap())
+            .collect::<Vec<_>>()
+            .await;
+        assert_eq!(ids, (0..200).collect::<Vec<_>>());
+
+        let tx = Connection::begin(&db).await.unwrap();
+        let first = Connection::fetch_stream(&tx, query())
+            .take(3)
+            .collect::<Vec<_>>()
+            .await;
+        assert_eq!(first.len(), 3);
+        // the dropped stream hands the transaction back
+        assert_eq!(count(&tx).await, 200);
+        tx.commit().await.unwrap();
     }
 }
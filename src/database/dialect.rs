@@ -0,0 +1,57 @@
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Dialect {
+    #[default]
+    Sqlite,
+    MySql,
+    Postgres,
+}
+
+impl Dialect {
+    pub fn quote_identifier(&self, ident: &str) -> String {
+        match self {
+            Self::MySql => format!("`{}`", ident.replace('`', "``")),
+            Self::Sqlite | Self::Postgres => format!("\"{}\"", ident.replace('"', "\"\"")),
+        }
+    }
+
+    /// Quotes a column name, each part of a qualified one such as `b.id` on
+    /// its own.
+    pub fn quote_column(&self, column: &str) -> String {
+        column
+            .split('.')
+            .map(|part| self.quote_identifier(part))
+            .collect::<Vec<_>>()
+            .join(".")
+    }
+
+    /// Renders the `?` placeholders of `sql` into the form expected by the
+    /// backend, leaving quoted literals and identifiers untouched.
+    pub fn render_placeholders(&self, sql: String) -> String {
+        if *self != Self::Postgres {
+            return sql;
+        }
+
+        let mut result = String::with_capacity(sql.len());
+        let mut quote: Option<char> = None;
+        let mut index = 0;
+        for ch in sql.chars() {
+            match (quote, ch) {
+                (None, '\'' | '"' | '`') => {
+                    quote = Some(ch);
+                    result.push(ch);
+                }
+                (Some(q), _) if q == ch => {
+                    quote = None;
+                    result.push(ch);
+                }
+                (None, '?') => {
+                    index += 1;
+                    result.push('$');
+                    result.push_str(&index.to_string());
+                }
+                _ => result.push(ch),
+            }
+        }
+        result
+    }
+}
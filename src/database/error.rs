@@ -0,0 +1,100 @@
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    NotFound,
+    UniqueViolation(String),
+    ForeignKeyViolation(String),
+    Decode { column: String },
+    Connection(String),
+    Other(String),
+}
+
+impl Error {
+    pub(crate) fn decode(column: impl Into<String>) -> Self {
+        Self::Decode {
+            column: column.into(),
+        }
+    }
+
+    pub(crate) fn transaction_finished() -> Self {
+        Self::Other("transaction finished".to_string())
+    }
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NotFound => write!(f, "row not found"),
+            Self::UniqueViolation(msg) => write!(f, "unique violation: {}", msg),
+            Self::ForeignKeyViolation(msg) => write!(f, "foreign key violation: {}", msg),
+            Self::Decode { column } => write!(f, "failed to decode column {}", column),
+            Self::Connection(msg) => write!(f, "connection error: {}", msg),
+            Self::Other(msg) => write!(f, "{}", msg),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<sqlx::Error> for Error {
+    fn from(e: sqlx::Error) -> Self {
+        use sqlx::error::ErrorKind;
+
+        match e {
+            sqlx::Error::RowNotFound => Self::NotFound,
+            sqlx::Error::Database(e) => match e.kind() {
+                ErrorKind::UniqueViolation => Self::UniqueViolation(e.message().to_string()),
+                ErrorKind::ForeignKeyViolation => {
+                    Self::ForeignKeyViolation(e.message().to_string())
+                }
+                _ => Self::Other(e.to_string()),
+            },
+            sqlx::Error::ColumnNotFound(column) => Self::decode(column),
+            sqlx::Error::ColumnDecode { index, .. } => Self::decode(index),
+            sqlx::Error::Configuration(_)
+            | sqlx::Error::Io(_)
+            | sqlx::Error::Tls(_)
+            | sqlx::Error::PoolTimedOut
+            | sqlx::Error::PoolClosed
+            | sqlx::Error::WorkerCrashed => Self::Connection(e.to_string()),
+            _ => Self::Other(e.to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{database::Connection, testutil};
+
+    #[tokio::test]
+    async fn from_sqlx() {
+        let db = testutil::connect().await;
+        Connection::execute(&db, "CREATE TABLE item (id VARCHAR(32) PRIMARY KEY)".into())
+            .await
+            .unwrap();
+        Connection::execute(&db, "INSERT INTO item VALUES ('a')".into())
+            .await
+            .unwrap();
+
+        assert!(matches!(
+            Connection::execute(&db, "INSERT INTO item VALUES ('a')".into()).await,
+            Err(Error::UniqueViolation(_))
+        ));
+        assert_eq!(
+            Connection::fetch_one(&db, "SELECT id FROM item WHERE id = 'b'".into())
+                .await
+                .unwrap_err(),
+            Error::NotFound
+        );
+
+        let row = Connection::fetch_one(&db, "SELECT id FROM item".into())
+            .await
+            .unwrap();
+        assert_eq!(row.get::<String>("id").unwrap(), "a");
+        assert_eq!(row.get::<bool>("id").unwrap_err(), Error::decode("id"));
+        assert_eq!(
+            row.get::<String>("name").unwrap_err(),
+            Error::decode("name")
+        );
+    }
+}
@@ -0,0 +1,172 @@
+use super::{
+    db::{Connection, Database},
+    dialect::Dialect,
+    error::Error,
+    query::Query,
+};
+
+const MIGRATION_TABLE: &str = "schema_migrations";
+
+macro_rules! migration {
+    ($version:literal, $name:literal) => {
+        Migration {
+            version: $version,
+            name: $name,
+            sqlite: include_str!(concat!("../../migrations/sqlite/", $name, ".sql")),
+            mysql: include_str!(concat!("../../migrations/mysql/", $name, ".sql")),
+            postgres: include_str!(concat!("../../migrations/postgres/", $name, ".sql")),
+        }
+    };
+}
+
+pub struct Migration {
+    pub version: i32,
+    pub name: &'static str,
+    sqlite: &'static str,
+    mysql: &'static str,
+    postgres: &'static str,
+}
+
+pub const MIGRATIONS: &[Migration] = &[
+    migration!(1, "0001_init"),
+    migration!(2, "0002_list_indexes"),
+    migration!(3, "0003_link_health"),
+    migration!(4, "0004_archive"),
+    migration!(5, "0005_article"),
+    migration!(6, "0006_bookmark_image"),
+    migration!(7, "0007_metatag"),
+    migration!(8, "0008_link_check_queue"),
+    migration!(9, "0009_resource_metadata"),
+    migration!(10, "0010_resource_canonical_url"),
+];
+
+impl Migration {
+    pub fn sql(&self, dialect: Dialect) -> &'static str {
+        match dialect {
+            Dialect::Sqlite => self.sqlite,
+            Dialect::MySql => self.mysql,
+            Dialect::Postgres => self.postgres,
+        }
+    }
+
+    /// Splits the script into single statements, as prepared statements on
+    /// Postgres and MySQL only take one at a time.
+    pub fn statements(&self, dialect: Dialect) -> Vec<String> {
+        self.sql(dialect)
+            .lines()
+            .filter(|line| !line.trim_start().starts_with("--"))
+            .collect::<Vec<_>>()
+            .join("\n")
+            .split(';')
+            .map(|stmt| stmt.trim().to_string())
+            .filter(|stmt| !stmt.is_empty())
+            .collect()
+    }
+}
+
+async fn create_migration_table(db: &Database) -> Result<(), Error> {
+    let datetime = match db.dialect() {
+        Dialect::Postgres => "timestamptz",
+        Dialect::Sqlite | Dialect::MySql => "datetime",
+    };
+    let mut query = Query::with_dialect(db.dialect());
+    query
+        .push_str("CREATE TABLE IF NOT EXISTS ")
+        .push_identifier(MIGRATION_TABLE)
+        .push_str(&format!(
+            " (version int NOT NULL, name varchar(200) NOT NULL, applied_at {} NULL, PRIMARY KEY (version))",
+            datetime
+        ));
+    Connection::execute(db, query).await
+}
+
+async fn applied_versions(db: &Database) -> Result<Vec<i32>, Error> {
+    let mut query = Query::with_dialect(db.dialect());
+    query
+        .push_str("SELECT version FROM ")
+        .push_identifier(MIGRATION_TABLE);
+    Connection::fetch(db, query)
+        .await?
+        .into_iter()
+        .map(|row| row.get("version"))
+        .collect()
+}
+
+/// Applies the pending migrations in order and returns their versions. Each
+/// migration runs in its own transaction, though MySQL commits DDL implicitly.
+pub async fn migrate(db: &Database) -> Result<Vec<i32>, Error> {
+    create_migration_table(db).await?;
+    let applied = applied_versions(db).await?;
+
+    let mut versions = vec![];
+    for migration in MIGRATIONS
+        .iter()
+        .filter(|migration| !applied.contains(&migration.version))
+    {
+        let tx = Connection::begin(db).await?;
+        for stmt in migration.statements(db.dialect()) {
+            if let Err(e) = tx.execute(stmt.into()).await {
+                let _ = tx.rollback().await;
+                return Err(Error::Other(format!(
+                    "migration {} failed: {}",
+                    migration.name, e
+                )));
+            }
+        }
+
+        let mut query = Query::with_dialect(db.dialect());
+        query
+            .push_str("INSERT INTO ")
+            .push_identifier(MIGRATION_TABLE)
+            .push_str(" ")
+            .push_columns(&["version", "name", "applied_at"])
+            .push_str(" VALUES (?, ?, ?)")
+            .bind(migration.version.into())
+            .bind(migration.name.into())
+            .bind(chrono::Utc::now().into());
+        tx.execute(query).await?;
+        tx.commit().await?;
+        versions.push(migration.version);
+    }
+    Ok(versions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil;
+
+    #[tokio::test]
+    async fn migrate_all() {
+        let db = testutil::connect().await;
+        let versions: Vec<i32> = MIGRATIONS.iter().map(|m| m.version).collect();
+        assert_eq!(migrate(&db).await.unwrap(), versions);
+        assert_eq!(migrate(&db).await.unwrap(), Vec::<i32>::new());
+
+        let tables = match db.dialect() {
+            Dialect::Sqlite => "SELECT name FROM sqlite_master WHERE type = 'table' ORDER BY name",
+            Dialect::Postgres => "SELECT table_name AS name FROM information_schema.tables WHERE table_schema = current_schema() ORDER BY name",
+            Dialect::MySql => "SELECT table_name AS name FROM information_schema.tables WHERE table_schema = DATABASE() ORDER BY name",
+        };
+        let tables = Connection::fetch(&db, tables.into())
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|row| row.get("name").unwrap())
+            .collect::<Vec<String>>();
+        assert_eq!(
+            tables,
+            [
+                "archive",
+                "article",
+                "bookmark",
+                "metatag",
+                "resource",
+                "schema_migrations",
+                "tag",
+                "tagged_bookmark",
+                "user"
+            ]
+        );
+    }
+}
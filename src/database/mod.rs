@@ -1,8 +1,18 @@
 mod db;
+mod dialect;
+mod error;
+mod migrate;
+mod mysql;
+mod postgres;
 mod query;
 mod sqlite;
 mod value;
 
 pub use db::*;
+pub use dialect::*;
+pub use error::*;
+pub use migrate::*;
 pub use query::*;
 pub use value::*;
+
+pub use achiet_derive::FromRow;
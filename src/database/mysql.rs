@@ -0,0 +1,230 @@
+use super::{
+    db::{
+        forward_rows, row_stream, CommitHook, Connection, RowStream, Transaction, TransactionHandle,
+    },
+    error::Error,
+    query::{Column, ColumnIndex, Query, Row},
+    value::{Value, Values},
+};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+impl TryFrom<sqlx::mysql::MySqlRow> for Row {
+    type Error = Error;
+
+    fn try_from(row: sqlx::mysql::MySqlRow) -> Result<Self, Self::Error> {
+        use sqlx::{Column as SqlxColumn, Row, TypeInfo};
+
+        let columns = row
+            .columns()
+            .iter()
+            .map(|c| {
+                let decode = || Error::decode(c.name());
+                let value = match c.type_info().name() {
+                    "VARCHAR" | "CHAR" | "TEXT" | "TINYTEXT" | "MEDIUMTEXT" | "LONGTEXT"
+                    | "ENUM" => Value::String(row.try_get(c.ordinal()).map_err(|_| decode())?),
+                    "BOOLEAN" => Value::Bool(row.try_get(c.ordinal()).map_err(|_| decode())?),
+                    "TINYINT" | "SMALLINT" | "MEDIUMINT" | "INT" | "BIGINT" => {
+                        Value::Int(row.try_get(c.ordinal()).map_err(|_| decode())?)
+                    }
+                    "TINYINT UNSIGNED" | "SMALLINT UNSIGNED" | "MEDIUMINT UNSIGNED"
+                    | "INT UNSIGNED" | "BIGINT UNSIGNED" => {
+                        Value::Unsigned(row.try_get(c.ordinal()).map_err(|_| decode())?)
+                    }
+                    "FLOAT" | "DOUBLE" => Value::Float(
+                        row.try_get::<Option<f64>, _>(c.ordinal())
+                            .map_err(|_| decode())?,
+                    ),
+                    "JSON" => Value::Json(row.try_get(c.ordinal()).map_err(|_| decode())?),
+                    "NULL" => Value::Null,
+                    "DATETIME" | "TIMESTAMP" => {
+                        Value::DateTime(row.try_get(c.ordinal()).map_err(|_| decode())?)
+                    }
+                    "BINARY" | "VARBINARY" | "TINYBLOB" | "BLOB" | "MEDIUMBLOB" | "LONGBLOB" => {
+                        Value::Bytes(row.try_get(c.ordinal()).map_err(|_| decode())?)
+                    }
+                    _ => return Err(decode()),
+                };
+                Ok(Column(c.name().to_string(), value))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self { columns })
+    }
+}
+
+impl<'q> sqlx::IntoArguments<'q, sqlx::mysql::MySql> for Values {
+    fn into_arguments(self) -> sqlx::mysql::MySqlArguments {
+        use sqlx::Arguments;
+        let mut args = sqlx::mysql::MySqlArguments::default();
+        for value in self.0.into_iter() {
+            match value {
+                Value::Null => args.add(None::<String>),
+                Value::Bool(b) => args.add(b),
+                Value::String(s) => args.add(s),
+                Value::DateTime(dt) => args.add(dt),
+                Value::Int(i) => args.add(i),
+                Value::Unsigned(u) => args.add(u),
+                Value::Float(f) => args.add(f),
+                Value::Bytes(b) => args.add(b),
+                Value::Json(j) => args.add(j.map(sqlx::types::Json)),
+            }
+        }
+        args
+    }
+}
+
+impl sqlx::ColumnIndex<sqlx::mysql::MySqlRow> for ColumnIndex {
+    fn index(&self, row: &sqlx::mysql::MySqlRow) -> Result<usize, sqlx::Error> {
+        match self {
+            ColumnIndex::Str(name) => name.as_str().index(row),
+            ColumnIndex::Int(index) => index.index(row),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct MySqlConnection {
+    pool: sqlx::mysql::MySqlPool,
+}
+
+impl MySqlConnection {
+    pub async fn connect(dsn: String) -> Result<Self, Error> {
+        let pool = sqlx::mysql::MySqlPoolOptions::new()
+            .after_connect(|conn, _meta| {
+                Box::pin(async move {
+                    // DATETIME columns carry no zone, keep them in UTC like the other backends
+                    use sqlx::Executor;
+                    conn.execute("SET time_zone = '+00:00'").await?;
+                    Ok(())
+                })
+            })
+            .connect(dsn.as_str())
+            .await?;
+        Ok(Self { pool })
+    }
+}
+
+impl Connection for MySqlConnection {
+    async fn fetch(&self, query: Query) -> Result<Vec<Row>, Error> {
+        let (sql, args) = query.build();
+        sqlx::query_with(sql.as_str(), args)
+            .fetch_all(&self.pool)
+            .await?
+            .into_iter()
+            .map(Row::try_from)
+            .collect()
+    }
+
+    async fn fetch_one(&self, query: Query) -> Result<Row, Error> {
+        let (sql, args) = query.build();
+        Row::try_from(
+            sqlx::query_with(sql.as_str(), args)
+                .fetch_one(&self.pool)
+                .await?,
+        )
+    }
+
+    async fn execute(&self, query: Query) -> Result<(), Error> {
+        let (sql, args) = query.build();
+        sqlx::query_with(sql.as_str(), args)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn begin(&self) -> Result<Transaction, Error> {
+        MySqlTransaction::begin(&self.pool)
+            .await
+            .map(|tx| Transaction::new(TransactionHandle::MySql(tx)))
+    }
+
+    fn fetch_stream(&self, query: Query) -> RowStream {
+        let pool = self.pool.clone();
+        row_stream(move |sender| async move {
+            let (sql, args) = query.build();
+            forward_rows(sqlx::query_with(sql.as_str(), args).fetch(&pool), &sender).await;
+        })
+    }
+
+    fn after_commit(&self, hook: CommitHook) {
+        hook()
+    }
+}
+
+#[derive(Clone)]
+pub struct MySqlTransaction {
+    tx: Arc<Mutex<Option<sqlx::Transaction<'static, sqlx::mysql::MySql>>>>,
+}
+
+impl MySqlTransaction {
+    async fn begin(pool: &sqlx::mysql::MySqlPool) -> Result<Self, Error> {
+        let tx = pool.begin().await?;
+        Ok(Self {
+            tx: Arc::new(Mutex::new(Some(tx))),
+        })
+    }
+
+    pub async fn fetch(&self, query: Query) -> Result<Vec<Row>, Error> {
+        let (sql, args) = query.build();
+        let mut guard = self.tx.lock().await;
+        let tx = guard.as_mut().ok_or_else(Error::transaction_finished)?;
+        sqlx::query_with(sql.as_str(), args)
+            .fetch_all(&mut **tx)
+            .await?
+            .into_iter()
+            .map(Row::try_from)
+            .collect()
+    }
+
+    pub async fn fetch_one(&self, query: Query) -> Result<Row, Error> {
+        let (sql, args) = query.build();
+        let mut guard = self.tx.lock().await;
+        let tx = guard.as_mut().ok_or_else(Error::transaction_finished)?;
+        Row::try_from(
+            sqlx::query_with(sql.as_str(), args)
+                .fetch_one(&mut **tx)
+                .await?,
+        )
+    }
+
+    pub async fn execute(&self, query: Query) -> Result<(), Error> {
+        let (sql, args) = query.build();
+        let mut guard = self.tx.lock().await;
+        let tx = guard.as_mut().ok_or_else(Error::transaction_finished)?;
+        sqlx::query_with(sql.as_str(), args)
+            .execute(&mut **tx)
+            .await?;
+        Ok(())
+    }
+
+    pub fn fetch_stream(&self, query: Query) -> RowStream {
+        let tx = self.tx.clone();
+        row_stream(move |sender| async move {
+            let (sql, args) = query.build();
+            let mut guard = tx.lock().await;
+            match guard.as_mut() {
+                Some(tx) => {
+                    let rows = sqlx::query_with(sql.as_str(), args).fetch(&mut **tx);
+                    forward_rows(rows, &sender).await;
+                }
+                None => {
+                    let _ = sender.send(Err(Error::transaction_finished())).await;
+                }
+            }
+        })
+    }
+
+    pub async fn commit(&self) -> Result<(), Error> {
+        match self.tx.lock().await.take() {
+            Some(tx) => tx.commit().await.map_err(Error::from),
+            None => Err(Error::transaction_finished()),
+        }
+    }
+
+    pub async fn rollback(&self) -> Result<(), Error> {
+        match self.tx.lock().await.take() {
+            Some(tx) => tx.rollback().await.map_err(Error::from),
+            None => Err(Error::transaction_finished()),
+        }
+    }
+}
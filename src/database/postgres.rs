@@ -0,0 +1,252 @@
+use super::{
+    db::{
+        forward_rows, row_stream, CommitHook, Connection, RowStream, Transaction, TransactionHandle,
+    },
+    error::Error,
+    query::{Column, ColumnIndex, Query, Row},
+    value::{Value, Values},
+};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+impl TryFrom<sqlx::postgres::PgRow> for Row {
+    type Error = Error;
+
+    fn try_from(row: sqlx::postgres::PgRow) -> Result<Self, Self::Error> {
+        use sqlx::{Column as SqlxColumn, Row, TypeInfo};
+
+        let columns = row
+            .columns()
+            .iter()
+            .map(|c| {
+                let decode = || Error::decode(c.name());
+                let value = match c.type_info().name() {
+                    "TEXT" | "VARCHAR" | "BPCHAR" | "NAME" => {
+                        Value::String(row.try_get(c.ordinal()).map_err(|_| decode())?)
+                    }
+                    "BOOL" => Value::Bool(row.try_get(c.ordinal()).map_err(|_| decode())?),
+                    "INT2" => Value::Int(
+                        row.try_get::<Option<i16>, _>(c.ordinal())
+                            .map_err(|_| decode())?
+                            .map(i32::from),
+                    ),
+                    "INT4" => Value::Int(row.try_get(c.ordinal()).map_err(|_| decode())?),
+                    // COUNT(*) is INT8, values past i32::MAX are a decode error
+                    "INT8" => Value::Int(
+                        row.try_get::<Option<i64>, _>(c.ordinal())
+                            .map_err(|_| decode())?
+                            .map(i32::try_from)
+                            .transpose()
+                            .map_err(|_| decode())?,
+                    ),
+                    "FLOAT4" => Value::Float(
+                        row.try_get::<Option<f32>, _>(c.ordinal())
+                            .map_err(|_| decode())?
+                            .map(f64::from),
+                    ),
+                    "FLOAT8" => Value::Float(row.try_get(c.ordinal()).map_err(|_| decode())?),
+                    "JSON" | "JSONB" => {
+                        Value::Json(row.try_get(c.ordinal()).map_err(|_| decode())?)
+                    }
+                    "TIMESTAMPTZ" => {
+                        Value::DateTime(row.try_get(c.ordinal()).map_err(|_| decode())?)
+                    }
+                    "TIMESTAMP" => Value::DateTime(
+                        row.try_get::<Option<chrono::NaiveDateTime>, _>(c.ordinal())
+                            .map_err(|_| decode())?
+                            .map(|dt| dt.and_utc()),
+                    ),
+                    "BYTEA" => Value::Bytes(row.try_get(c.ordinal()).map_err(|_| decode())?),
+                    _ => return Err(decode()),
+                };
+                Ok(Column(c.name().to_string(), value))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self { columns })
+    }
+}
+
+/// NULL bound without a type, Postgres infers it from the statement instead of
+/// rejecting e.g. a TEXT null for an integer column.
+struct Untyped;
+
+impl sqlx::Type<sqlx::postgres::Postgres> for Untyped {
+    fn type_info() -> sqlx::postgres::PgTypeInfo {
+        sqlx::postgres::PgTypeInfo::with_oid(sqlx::postgres::types::Oid(0))
+    }
+}
+
+impl sqlx::Encode<'_, sqlx::postgres::Postgres> for Untyped {
+    fn encode_by_ref(&self, _: &mut sqlx::postgres::PgArgumentBuffer) -> sqlx::encode::IsNull {
+        sqlx::encode::IsNull::Yes
+    }
+}
+
+impl<'q> sqlx::IntoArguments<'q, sqlx::postgres::Postgres> for Values {
+    fn into_arguments(self) -> sqlx::postgres::PgArguments {
+        use sqlx::Arguments;
+        let mut args = sqlx::postgres::PgArguments::default();
+        for value in self.0.into_iter() {
+            match value {
+                Value::Null => args.add(Untyped),
+                Value::Bool(b) => args.add(b),
+                Value::String(s) => args.add(s),
+                Value::DateTime(dt) => args.add(dt),
+                Value::Int(i) => args.add(i),
+                Value::Unsigned(u) => args.add(u.map(i64::from)),
+                Value::Float(f) => args.add(f),
+                Value::Bytes(b) => args.add(b),
+                Value::Json(j) => args.add(j.map(sqlx::types::Json)),
+            }
+        }
+        args
+    }
+}
+
+impl sqlx::ColumnIndex<sqlx::postgres::PgRow> for ColumnIndex {
+    fn index(&self, row: &sqlx::postgres::PgRow) -> Result<usize, sqlx::Error> {
+        match self {
+            ColumnIndex::Str(name) => name.as_str().index(row),
+            ColumnIndex::Int(index) => index.index(row),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct PostgresConnection {
+    pool: sqlx::postgres::PgPool,
+}
+
+impl PostgresConnection {
+    pub async fn connect(dsn: String) -> Result<Self, Error> {
+        let pool = sqlx::postgres::PgPoolOptions::new()
+            .connect(dsn.as_str())
+            .await?;
+        Ok(Self { pool })
+    }
+}
+
+impl Connection for PostgresConnection {
+    async fn fetch(&self, query: Query) -> Result<Vec<Row>, Error> {
+        let (sql, args) = query.build();
+        sqlx::query_with(sql.as_str(), args)
+            .fetch_all(&self.pool)
+            .await?
+            .into_iter()
+            .map(Row::try_from)
+            .collect()
+    }
+
+    async fn fetch_one(&self, query: Query) -> Result<Row, Error> {
+        let (sql, args) = query.build();
+        Row::try_from(
+            sqlx::query_with(sql.as_str(), args)
+                .fetch_one(&self.pool)
+                .await?,
+        )
+    }
+
+    async fn execute(&self, query: Query) -> Result<(), Error> {
+        let (sql, args) = query.build();
+        sqlx::query_with(sql.as_str(), args)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn begin(&self) -> Result<Transaction, Error> {
+        PostgresTransaction::begin(&self.pool)
+            .await
+            .map(|tx| Transaction::new(TransactionHandle::Postgres(tx)))
+    }
+
+    fn fetch_stream(&self, query: Query) -> RowStream {
+        let pool = self.pool.clone();
+        row_stream(move |sender| async move {
+            let (sql, args) = query.build();
+            forward_rows(sqlx::query_with(sql.as_str(), args).fetch(&pool), &sender).await;
+        })
+    }
+
+    fn after_commit(&self, hook: CommitHook) {
+        hook()
+    }
+}
+
+#[derive(Clone)]
+pub struct PostgresTransaction {
+    tx: Arc<Mutex<Option<sqlx::Transaction<'static, sqlx::postgres::Postgres>>>>,
+}
+
+impl PostgresTransaction {
+    async fn begin(pool: &sqlx::postgres::PgPool) -> Result<Self, Error> {
+        let tx = pool.begin().await?;
+        Ok(Self {
+            tx: Arc::new(Mutex::new(Some(tx))),
+        })
+    }
+
+    pub async fn fetch(&self, query: Query) -> Result<Vec<Row>, Error> {
+        let (sql, args) = query.build();
+        let mut guard = self.tx.lock().await;
+        let tx = guard.as_mut().ok_or_else(Error::transaction_finished)?;
+        sqlx::query_with(sql.as_str(), args)
+            .fetch_all(&mut **tx)
+            .await?
+            .into_iter()
+            .map(Row::try_from)
+            .collect()
+    }
+
+    pub async fn fetch_one(&self, query: Query) -> Result<Row, Error> {
+        let (sql, args) = query.build();
+        let mut guard = self.tx.lock().await;
+        let tx = guard.as_mut().ok_or_else(Error::transaction_finished)?;
+        Row::try_from(
+            sqlx::query_with(sql.as_str(), args)
+                .fetch_one(&mut **tx)
+                .await?,
+        )
+    }
+
+    pub async fn execute(&self, query: Query) -> Result<(), Error> {
+        let (sql, args) = query.build();
+        let mut guard = self.tx.lock().await;
+        let tx = guard.as_mut().ok_or_else(Error::transaction_finished)?;
+        sqlx::query_with(sql.as_str(), args)
+            .execute(&mut **tx)
+            .await?;
+        Ok(())
+    }
+
+    pub fn fetch_stream(&self, query: Query) -> RowStream {
+        let tx = self.tx.clone();
+        row_stream(move |sender| async move {
+            let (sql, args) = query.build();
+            let mut guard = tx.lock().await;
+            match guard.as_mut() {
+                Some(tx) => {
+                    let rows = sqlx::query_with(sql.as_str(), args).fetch(&mut **tx);
+                    forward_rows(rows, &sender).await;
+                }
+                None => {
+                    let _ = sender.send(Err(Error::transaction_finished())).await;
+                }
+            }
+        })
+    }
+
+    pub async fn commit(&self) -> Result<(), Error> {
+        match self.tx.lock().await.take() {
+            Some(tx) => tx.commit().await.map_err(Error::from),
+            None => Err(Error::transaction_finished()),
+        }
+    }
+
+    pub async fn rollback(&self) -> Result<(), Error> {
+        match self.tx.lock().await.take() {
+            Some(tx) => tx.rollback().await.map_err(Error::from),
+            None => Err(Error::transaction_finished()),
+        }
+    }
+}
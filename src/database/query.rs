@@ -1,4 +1,8 @@
-use super::value::{Value, Values};
+use super::{
+    dialect::Dialect,
+    error::Error,
+    value::{Value, Values},
+};
 
 #[derive(Debug, Clone)]
 pub struct Column(pub String, pub Value);
@@ -13,21 +17,33 @@ impl Row {
         self.columns.len()
     }
 
-    pub fn column_name(&self, index: ColumnIndex) -> Result<String, ()> {
-        if let Some(c) = index.column_index(self) {
-            Ok(c.0.clone())
-        } else {
-            Err(())
-        }
+    pub fn is_empty(&self) -> bool {
+        self.columns.is_empty()
     }
 
-    pub fn try_get(&self, index: ColumnIndex) -> Result<Value, ()> {
-        if let Some(c) = index.column_index(self) {
-            Ok(c.1.clone())
-        } else {
-            Err(())
-        }
+    pub fn column_name(&self, index: ColumnIndex) -> Result<String, Error> {
+        index.column(self).map(|c| c.0.clone())
     }
+
+    pub fn try_get(&self, index: ColumnIndex) -> Result<Value, Error> {
+        index.column(self).map(|c| c.1.clone())
+    }
+
+    /// Gets the column converted into `T`, reporting the column on failure.
+    pub fn get<T>(&self, index: impl Into<ColumnIndex>) -> Result<T, Error>
+    where
+        Value: TryInto<T, Error = Error>,
+    {
+        let index = index.into();
+        self.try_get(index.clone())?
+            .try_into()
+            .map_err(|_| Error::decode(index.to_string()))
+    }
+}
+
+/// Builds a value from a row, usually through `#[derive(FromRow)]`.
+pub trait FromRow: Sized {
+    fn from_row(row: &Row) -> Result<Self, Error>;
 }
 
 #[derive(Debug, Clone)]
@@ -36,67 +52,73 @@ pub enum ColumnIndex {
     Str(String),
 }
 
-impl Into<ColumnIndex> for usize {
-    fn into(self) -> ColumnIndex {
-        ColumnIndex::Int(self)
+impl From<usize> for ColumnIndex {
+    fn from(index: usize) -> Self {
+        ColumnIndex::Int(index)
     }
 }
 
-impl Into<ColumnIndex> for String {
-    fn into(self) -> ColumnIndex {
-        ColumnIndex::Str(self)
+impl From<String> for ColumnIndex {
+    fn from(name: String) -> Self {
+        ColumnIndex::Str(name)
     }
 }
 
-impl Into<ColumnIndex> for &str {
-    fn into(self) -> ColumnIndex {
-        ColumnIndex::Str(self.to_string())
+impl From<&str> for ColumnIndex {
+    fn from(name: &str) -> Self {
+        ColumnIndex::Str(name.to_string())
     }
 }
 
-impl ColumnIndex {
-    fn column_index(self, row: &Row) -> Option<Column> {
+impl std::fmt::Display for ColumnIndex {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Self::Str(s) => {
-                if let Some(c) = row.columns.iter().find(|c| c.0 == s) {
-                    Some(c.clone())
-                } else {
-                    None
-                }
-            }
-            Self::Int(i) => {
-                if let Some(c) = row.columns.get(i) {
-                    Some(c.clone())
-                } else {
-                    None
-                }
-            }
+            Self::Int(index) => write!(f, "{}", index),
+            Self::Str(name) => write!(f, "{}", name),
+        }
+    }
+}
+
+impl ColumnIndex {
+    fn column(self, row: &Row) -> Result<&Column, Error> {
+        match &self {
+            Self::Str(s) => row.columns.iter().find(|c| &c.0 == s),
+            Self::Int(i) => row.columns.get(*i),
         }
+        .ok_or_else(|| Error::decode(self.to_string()))
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct Query {
     pub(crate) stmt: Vec<String>,
     pub(crate) args: Values,
     pub(crate) sep: String,
+    pub(crate) dialect: Dialect,
 }
 
 impl Query {
     pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_dialect(dialect: Dialect) -> Self {
         Self {
-            stmt: Vec::new(),
-            args: Values::default(),
-            sep: "".to_string(),
+            dialect,
+            ..Self::default()
         }
     }
 
+    pub fn dialect(&self) -> Dialect {
+        self.dialect
+    }
+
     pub fn set_separator(&mut self, sep: &str) {
         self.sep = sep.to_string();
     }
 
     pub fn append(&mut self, other: Self) -> &mut Self {
-        let (stmt, mut values) = other.build();
+        let (stmt, mut values) = other.build_raw();
         self.stmt.push(stmt);
         self.args.append(&mut values);
         self
@@ -107,32 +129,365 @@ impl Query {
         self
     }
 
+    pub fn push_identifier(&mut self, ident: &str) -> &mut Self {
+        self.stmt.push(self.dialect.quote_identifier(ident));
+        self
+    }
+
+    /// Appends the parenthesized list of `columns`, each quoted for the dialect.
+    pub fn push_columns(&mut self, columns: &[&str]) -> &mut Self {
+        let names = columns
+            .iter()
+            .map(|c| self.dialect.quote_identifier(c))
+            .collect::<Vec<_>>();
+        self.push_str(&format!("({})", names.join(", ")))
+    }
+
     pub fn bind(&mut self, value: Value) -> &mut Self {
         self.args.push(value);
         self
     }
 
-    pub fn build(self) -> (String, Values) {
+    /// Appends `column = ?` with the column quoted for the dialect and binds
+    /// `value` to it.
+    pub fn push_eq(&mut self, column: &str, value: Value) -> &mut Self {
+        let column = self.dialect.quote_column(column);
+        self.push_str(&format!("{} = ?", column)).bind(value)
+    }
+
+    /// Appends `column IN (...)` over `values`, a condition matching nothing
+    /// when there are none since `IN ()` is not valid everywhere.
+    pub fn push_in(&mut self, column: &str, values: Vec<Value>) -> &mut Self {
+        if values.is_empty() {
+            return self.push_str("1 = 0");
+        }
+        let mut stmt = Query::with_dialect(self.dialect);
+        stmt.push_str(&format!(
+            "{} IN ({})",
+            self.dialect.quote_column(column),
+            vec!["?"; values.len()].join(", ")
+        ));
+        values.into_iter().for_each(|value| {
+            stmt.bind(value);
+        });
+        self.append(stmt)
+    }
+
+    /// Appends an update of `table` from `rows`, each holding one value per
+    /// entry of `columns` with `id` first. MySQL has no `UPDATE ... FROM`, so
+    /// the rows are joined in as a derived table there.
+    pub fn push_bulk_update(
+        &mut self,
+        table: &str,
+        columns: &[&str],
+        set_columns: &[&str],
+        rows: Vec<Vec<Value>>,
+    ) -> &mut Self {
+        let dialect = self.dialect;
+        let quote = |ident: &str| dialect.quote_identifier(ident);
+        let table = quote(table);
+        let id = quote("id");
+        match dialect {
+            Dialect::MySql => {
+                let mut data = Query::with_dialect(dialect);
+                data.set_separator(" UNION ALL ");
+                for (index, row) in rows.into_iter().enumerate() {
+                    let fields = columns
+                        .iter()
+                        .map(|c| match index {
+                            0 => format!("? AS {}", quote(c)),
+                            _ => "?".to_string(),
+                        })
+                        .collect::<Vec<_>>();
+                    data.push_str(&format!("SELECT {}", fields.join(", ")));
+                    row.into_iter().for_each(|value| {
+                        data.bind(value);
+                    });
+                }
+                let assignments = set_columns
+                    .iter()
+                    .map(|c| format!("{}.{} = _data.{}", table, quote(c), quote(c)))
+                    .collect::<Vec<_>>();
+
+                self.push_str(&format!("UPDATE {} JOIN (", table))
+                    .append(data)
+                    .push_str(&format!(
+                        ") AS _data ON {}.{} = _data.{} SET {}",
+                        table,
+                        id,
+                        id,
+                        assignments.join(", ")
+                    ))
+            }
+            Dialect::Sqlite | Dialect::Postgres => {
+                let names = columns.iter().map(|c| quote(c)).collect::<Vec<_>>();
+                let assignments = set_columns
+                    .iter()
+                    .map(|c| format!("{} = _data.{}", quote(c), quote(c)))
+                    .collect::<Vec<_>>();
+                let mut values = Query::with_dialect(dialect);
+                values.set_separator(", ");
+                for row in rows.into_iter() {
+                    values.push_str(&format!("({})", ["?"].repeat(row.len()).join(", ")));
+                    row.into_iter().for_each(|value| {
+                        values.bind(value);
+                    });
+                }
+
+                self.push_str(&format!("WITH _data ({}) AS (VALUES ", names.join(", ")))
+                    .append(values)
+                    .push_str(&format!(
+                        ") UPDATE {} SET {} FROM _data WHERE {}.{} = _data.{}",
+                        table,
+                        assignments.join(", "),
+                        table,
+                        id,
+                        id
+                    ))
+            }
+        }
+    }
+
+    /// Appends an insert of `rows` into `table` which updates `update_columns`
+    /// instead when a row collides on the unique `conflict_columns`.
+    pub fn push_upsert(
+        &mut self,
+        table: &str,
+        columns: &[&str],
+        conflict_columns: &[&str],
+        update_columns: &[&str],
+        rows: Vec<Vec<Value>>,
+    ) -> &mut Self {
+        let dialect = self.dialect;
+        let quote = |ident: &str| dialect.quote_identifier(ident);
+        let names = columns.iter().map(|c| quote(c)).collect::<Vec<_>>();
+        let on_conflict = match dialect {
+            Dialect::MySql => {
+                let assignments = match update_columns.is_empty() {
+                    true => vec![format!("{} = {}", quote(columns[0]), quote(columns[0]))],
+                    false => update_columns
+                        .iter()
+                        .map(|c| format!("{} = VALUES({})", quote(c), quote(c)))
+                        .collect::<Vec<_>>(),
+                };
+                format!(" ON DUPLICATE KEY UPDATE {}", assignments.join(", "))
+            }
+            Dialect::Sqlite | Dialect::Postgres => {
+                let targets = conflict_columns
+                    .iter()
+                    .map(|c| quote(c))
+                    .collect::<Vec<_>>();
+                match update_columns.is_empty() {
+                    true => format!(" ON CONFLICT ({}) DO NOTHING", targets.join(", ")),
+                    false => format!(
+                        " ON CONFLICT ({}) DO UPDATE SET {}",
+                        targets.join(", "),
+                        update_columns
+                            .iter()
+                            .map(|c| format!("{} = excluded.{}", quote(c), quote(c)))
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    ),
+                }
+            }
+        };
+        let table = quote(table);
+
+        let mut values = Query::with_dialect(dialect);
+        values.set_separator(", ");
+        for row in rows.into_iter() {
+            values.push_str(&format!("({})", ["?"].repeat(row.len()).join(", ")));
+            row.into_iter().for_each(|value| {
+                values.bind(value);
+            });
+        }
+
+        self.push_str(&format!(
+            "INSERT INTO {} ({}) VALUES ",
+            table,
+            names.join(", ")
+        ))
+        .append(values)
+        .push_str(&on_conflict)
+    }
+
+    fn build_raw(self) -> (String, Values) {
         (self.stmt.join(&self.sep), self.args)
     }
 
+    pub fn build(self) -> (String, Values) {
+        let dialect = self.dialect;
+        let (stmt, args) = self.build_raw();
+        (dialect.render_placeholders(stmt), args)
+    }
+
     pub fn is_empty(&self) -> bool {
-        self.stmt.len() == 0 && self.args.0.len() == 0
+        self.stmt.is_empty() && self.args.0.is_empty()
     }
 }
 
-impl Into<Query> for &str {
-    fn into(self) -> Query {
+impl From<&str> for Query {
+    fn from(stmt: &str) -> Self {
         let mut query = Query::new();
-        query.push_str(self);
+        query.push_str(stmt);
         query
     }
 }
 
-impl Into<Query> for String {
-    fn into(self) -> Query {
+impl From<String> for Query {
+    fn from(stmt: String) -> Self {
         let mut query = Query::new();
-        query.push_str(self.as_str());
+        query.push_str(stmt.as_str());
+        query
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render_placeholders() {
+        let mut query = Query::with_dialect(Dialect::Postgres);
+        query
+            .push_str("SELECT * FROM ")
+            .push_identifier("user")
+            .push_str(" WHERE username = ? AND role = '?'")
+            .bind("name".into());
+        let mut filters = Query::with_dialect(Dialect::Postgres);
+        filters.push_str(" AND id IN (?, ?)");
+        query.append(filters);
+
+        assert_eq!(
+            query.build().0,
+            r#"SELECT * FROM "user" WHERE username = $1 AND role = '?' AND id IN ($2, $3)"#,
+        );
+
+        let mut query = Query::with_dialect(Dialect::MySql);
         query
+            .push_str("SELECT * FROM ")
+            .push_identifier("user")
+            .push_str(" WHERE id = ?");
+        assert_eq!(query.build().0, "SELECT * FROM `user` WHERE id = ?");
+    }
+
+    #[test]
+    fn push_in() {
+        let mut filters = Query::with_dialect(Dialect::Postgres);
+        filters.set_separator(" AND ");
+        filters
+            .push_eq("b.user_id", "u".into())
+            .push_in("id", vec!["a".into(), "b".into()])
+            .push_in("tag_id", vec![]);
+        let (sql, args) = filters.build();
+        assert_eq!(sql, r#""b"."user_id" = $1 AND "id" IN ($2, $3) AND 1 = 0"#);
+        assert_eq!(args.0.len(), 3);
+    }
+
+    #[test]
+    fn push_columns() {
+        let mut query = Query::with_dialect(Dialect::MySql);
+        query
+            .push_str("INSERT INTO ")
+            .push_identifier("user")
+            .push_str(" ")
+            .push_columns(&["id", "role"])
+            .push_str(" VALUES (?, ?)");
+        assert_eq!(
+            query.build().0,
+            "INSERT INTO `user` (`id`, `role`) VALUES (?, ?)"
+        );
+    }
+
+    #[test]
+    fn bulk_update() {
+        let rows = || vec![vec!["1".into(), "a".into()], vec!["2".into(), "b".into()]];
+
+        let mut query = Query::with_dialect(Dialect::Sqlite);
+        query.push_bulk_update("tag", &["id", "name"], &["name"], rows());
+        let (sql, args) = query.build();
+        assert_eq!(
+            sql,
+            r#"WITH _data ("id", "name") AS (VALUES (?, ?), (?, ?)) UPDATE "tag" SET "name" = _data."name" FROM _data WHERE "tag"."id" = _data."id""#,
+        );
+        assert_eq!(args.0.len(), 4);
+
+        let mut query = Query::with_dialect(Dialect::MySql);
+        query.push_bulk_update("tag", &["id", "name"], &["name"], rows());
+        assert_eq!(
+            query.build().0,
+            "UPDATE `tag` JOIN (SELECT ? AS `id`, ? AS `name` UNION ALL SELECT ?, ?) AS _data ON `tag`.`id` = _data.`id` SET `tag`.`name` = _data.`name`",
+        );
+    }
+
+    #[test]
+    fn upsert() {
+        let rows = || vec![vec!["1".into(), "a".into()]];
+
+        let mut query = Query::with_dialect(Dialect::Postgres);
+        query.push_upsert("resource", &["id", "url"], &["url"], &[], rows());
+        assert_eq!(
+            query.build().0,
+            r#"INSERT INTO "resource" ("id", "url") VALUES ($1, $2) ON CONFLICT ("url") DO NOTHING"#,
+        );
+
+        let mut query = Query::with_dialect(Dialect::MySql);
+        query.push_upsert("resource", &["id", "url"], &["url"], &["url"], rows());
+        assert_eq!(
+            query.build().0,
+            "INSERT INTO `resource` (`id`, `url`) VALUES (?, ?) ON DUPLICATE KEY UPDATE `url` = VALUES(`url`)",
+        );
+    }
+
+    #[derive(Debug, PartialEq, crate::database::FromRow)]
+    struct Item {
+        id: String,
+        #[from_row(rename = "item_count")]
+        count: i32,
+        label: Option<String>,
+    }
+
+    #[test]
+    fn from_row() {
+        let row = |count: Value, label: Value| Row {
+            columns: vec![
+                Column("id".to_string(), "a".into()),
+                Column("item_count".to_string(), count),
+                Column("label".to_string(), label),
+            ],
+        };
+
+        assert_eq!(
+            Item::from_row(&row(1.into(), Value::String(None))).unwrap(),
+            Item {
+                id: "a".to_string(),
+                count: 1,
+                label: None,
+            }
+        );
+        assert_eq!(
+            Item::from_row(&row(Value::Int(None), Value::String(None))).unwrap_err(),
+            Error::decode("item_count")
+        );
+        assert_eq!(
+            Item::from_row(&row(1.into(), Value::Null)).unwrap().label,
+            None
+        );
+        assert_eq!(
+            Item::from_row(&row(1.into(), true.into())).unwrap_err(),
+            Error::decode("label")
+        );
+
+        let missing = Row {
+            columns: vec![
+                Column("id".to_string(), "a".into()),
+                Column("count".to_string(), 1.into()),
+                Column("label".to_string(), Value::Null),
+            ],
+        };
+        assert_eq!(
+            Item::from_row(&missing).unwrap_err(),
+            Error::decode("item_count")
+        );
     }
 }
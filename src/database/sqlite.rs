@@ -1,41 +1,48 @@
 use super::{
-    db::Connection,
+    db::{
+        forward_rows, row_stream, CommitHook, Connection, RowStream, Transaction, TransactionHandle,
+    },
+    error::Error,
     query::{Column, ColumnIndex, Query, Row},
     value::{Value, Values},
 };
+use std::sync::Arc;
+use tokio::sync::Mutex;
 
-impl From<sqlx::sqlite::SqliteRow> for Row {
-    fn from(row: sqlx::sqlite::SqliteRow) -> Self {
-        use sqlx::{Column as SqlxColumn, Row, TypeInfo};
+impl TryFrom<sqlx::sqlite::SqliteRow> for Row {
+    type Error = Error;
+
+    fn try_from(row: sqlx::sqlite::SqliteRow) -> Result<Self, Self::Error> {
+        use sqlx::{Column as SqlxColumn, Row, TypeInfo, ValueRef};
 
         let columns = row
             .columns()
-            .into_iter()
-            .map(|c| match c.type_info().name() {
-                "TEXT" => Column(
-                    c.name().to_string(),
-                    Value::String(row.try_get(c.ordinal()).unwrap()),
-                ),
-                "BOOLEAN" => Column(
-                    c.name().to_string(),
-                    Value::Bool(row.try_get(c.ordinal()).unwrap()),
-                ),
-                "INTEGER" => Column(
-                    c.name().to_string(),
-                    Value::Int(row.try_get(c.ordinal()).unwrap()),
-                ),
-                "DATETIME" => Column(
-                    c.name().to_string(),
-                    Value::DateTime(row.try_get(c.ordinal()).unwrap()),
-                ),
-                "BLOB" => Column(
-                    c.name().to_string(),
-                    Value::Bytes(row.try_get(c.ordinal()).unwrap()),
-                ),
-                _ => todo!(),
+            .iter()
+            .map(|c| {
+                let decode = || Error::decode(c.name());
+                // The declared type is missing for expressions and for types
+                // SQLite does not know, e.g. JSON, go by the stored value then
+                let raw = row.try_get_raw(c.ordinal()).map_err(|_| decode())?;
+                let type_info = match c.type_info().name() {
+                    "NULL" | "NUMERIC" => raw.type_info().into_owned(),
+                    _ => c.type_info().clone(),
+                };
+                let value = match type_info.name() {
+                    "NULL" => Value::Null,
+                    "TEXT" | "DATE" | "TIME" => {
+                        Value::String(row.try_get(c.ordinal()).map_err(|_| decode())?)
+                    }
+                    "BOOLEAN" => Value::Bool(row.try_get(c.ordinal()).map_err(|_| decode())?),
+                    "INTEGER" => Value::Int(row.try_get(c.ordinal()).map_err(|_| decode())?),
+                    "REAL" => Value::Float(row.try_get(c.ordinal()).map_err(|_| decode())?),
+                    "DATETIME" => Value::DateTime(row.try_get(c.ordinal()).map_err(|_| decode())?),
+                    "BLOB" => Value::Bytes(row.try_get(c.ordinal()).map_err(|_| decode())?),
+                    _ => return Err(decode()),
+                };
+                Ok(Column(c.name().to_string(), value))
             })
-            .collect();
-        Self { columns }
+            .collect::<Result<_, _>>()?;
+        Ok(Self { columns })
     }
 }
 
@@ -45,13 +52,15 @@ impl<'q> sqlx::IntoArguments<'q, sqlx::sqlite::Sqlite> for Values {
         let mut args = sqlx::sqlite::SqliteArguments::default();
         for value in self.0.into_iter() {
             match value {
+                Value::Null => args.add(None::<String>),
                 Value::Bool(b) => args.add(b),
                 Value::String(s) => args.add(s),
                 Value::DateTime(dt) => args.add(dt),
                 Value::Int(i) => args.add(i),
-                Value::Unsigned(u) => args.add(i32::try_from(u.unwrap()).unwrap()),
+                Value::Unsigned(u) => args.add(u),
+                Value::Float(f) => args.add(f),
                 Value::Bytes(b) => args.add(b),
-                _ => todo!(),
+                Value::Json(j) => args.add(j.map(sqlx::types::Json)),
             }
         }
         args
@@ -73,46 +82,182 @@ pub struct SqliteConnection {
 }
 
 impl SqliteConnection {
-    pub async fn connect(dsn: String) -> Result<Self, ()> {
+    pub async fn connect(dsn: String) -> Result<Self, Error> {
+        let options = dsn
+            .parse::<sqlx::sqlite::SqliteConnectOptions>()?
+            .create_if_missing(true);
         let pool = sqlx::sqlite::SqlitePoolOptions::new()
-            .connect(dsn.as_str())
-            .await
-            .unwrap();
+            .connect_with(options)
+            .await?;
         Ok(Self { pool })
     }
 }
 
 impl Connection for SqliteConnection {
-    async fn fetch(&self, query: Query) -> Result<Vec<Row>, ()> {
+    async fn fetch(&self, query: Query) -> Result<Vec<Row>, Error> {
         let (sql, args) = query.build();
-        match sqlx::query_with(sql.as_str(), args)
+        sqlx::query_with(sql.as_str(), args)
             .fetch_all(&self.pool)
-            .await
-        {
-            Ok(result) => Ok(result.into_iter().map(|row| Row::from(row)).collect()),
-            Err(_) => Err(()),
-        }
+            .await?
+            .into_iter()
+            .map(Row::try_from)
+            .collect()
     }
 
-    async fn fetch_one(&self, query: Query) -> Result<Row, ()> {
+    async fn fetch_one(&self, query: Query) -> Result<Row, Error> {
         let (sql, args) = query.build();
-        match sqlx::query_with(sql.as_str(), args)
-            .fetch_one(&self.pool)
-            .await
-        {
-            Ok(result) => Ok(Row::from(result)),
-            Err(_) => Err(()),
-        }
+        Row::try_from(
+            sqlx::query_with(sql.as_str(), args)
+                .fetch_one(&self.pool)
+                .await?,
+        )
     }
 
-    async fn execute(&self, query: Query) -> Result<(), String> {
+    async fn execute(&self, query: Query) -> Result<(), Error> {
         let (sql, args) = query.build();
-        match sqlx::query_with(sql.as_str(), args)
+        sqlx::query_with(sql.as_str(), args)
             .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn begin(&self) -> Result<Transaction, Error> {
+        SqliteTransaction::begin(&self.pool)
             .await
-        {
-            Ok(_) => Ok(()),
-            Err(e) => Err(e.to_string()),
+            .map(|tx| Transaction::new(TransactionHandle::Sqlite(tx)))
+    }
+
+    fn fetch_stream(&self, query: Query) -> RowStream {
+        let pool = self.pool.clone();
+        row_stream(move |sender| async move {
+            let (sql, args) = query.build();
+            forward_rows(sqlx::query_with(sql.as_str(), args).fetch(&pool), &sender).await;
+        })
+    }
+
+    fn after_commit(&self, hook: CommitHook) {
+        hook()
+    }
+}
+
+#[derive(Clone)]
+pub struct SqliteTransaction {
+    tx: Arc<Mutex<Option<sqlx::Transaction<'static, sqlx::sqlite::Sqlite>>>>,
+}
+
+impl SqliteTransaction {
+    async fn begin(pool: &sqlx::sqlite::SqlitePool) -> Result<Self, Error> {
+        let tx = pool.begin().await?;
+        Ok(Self {
+            tx: Arc::new(Mutex::new(Some(tx))),
+        })
+    }
+
+    pub async fn fetch(&self, query: Query) -> Result<Vec<Row>, Error> {
+        let (sql, args) = query.build();
+        let mut guard = self.tx.lock().await;
+        let tx = guard.as_mut().ok_or_else(Error::transaction_finished)?;
+        sqlx::query_with(sql.as_str(), args)
+            .fetch_all(&mut **tx)
+            .await?
+            .into_iter()
+            .map(Row::try_from)
+            .collect()
+    }
+
+    pub async fn fetch_one(&self, query: Query) -> Result<Row, Error> {
+        let (sql, args) = query.build();
+        let mut guard = self.tx.lock().await;
+        let tx = guard.as_mut().ok_or_else(Error::transaction_finished)?;
+        Row::try_from(
+            sqlx::query_with(sql.as_str(), args)
+                .fetch_one(&mut **tx)
+                .await?,
+        )
+    }
+
+    pub async fn execute(&self, query: Query) -> Result<(), Error> {
+        let (sql, args) = query.build();
+        let mut guard = self.tx.lock().await;
+        let tx = guard.as_mut().ok_or_else(Error::transaction_finished)?;
+        sqlx::query_with(sql.as_str(), args)
+            .execute(&mut **tx)
+            .await?;
+        Ok(())
+    }
+
+    pub fn fetch_stream(&self, query: Query) -> RowStream {
+        let tx = self.tx.clone();
+        row_stream(move |sender| async move {
+            let (sql, args) = query.build();
+            let mut guard = tx.lock().await;
+            match guard.as_mut() {
+                Some(tx) => {
+                    let rows = sqlx::query_with(sql.as_str(), args).fetch(&mut **tx);
+                    forward_rows(rows, &sender).await;
+                }
+                None => {
+                    let _ = sender.send(Err(Error::transaction_finished())).await;
+                }
+            }
+        })
+    }
+
+    pub async fn commit(&self) -> Result<(), Error> {
+        match self.tx.lock().await.take() {
+            Some(tx) => tx.commit().await.map_err(Error::from),
+            None => Err(Error::transaction_finished()),
+        }
+    }
+
+    pub async fn rollback(&self) -> Result<(), Error> {
+        match self.tx.lock().await.take() {
+            Some(tx) => tx.rollback().await.map_err(Error::from),
+            None => Err(Error::transaction_finished()),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::db::connect;
+
+    #[tokio::test]
+    async fn decode_values() {
+        let db = connect("sqlite::memory:".to_string()).await.unwrap();
+        Connection::execute(
+            &db,
+            "CREATE TABLE item (id TEXT, data json, created_at datetime)".into(),
+        )
+        .await
+        .unwrap();
+        let created_at = chrono::Utc::now();
+        let mut query = Query::from("INSERT INTO item VALUES (?, ?, ?)");
+        query
+            .bind(Value::Null)
+            .bind(Value::Json(Some(serde_json::json!({"a": 1}))))
+            .bind(created_at.into());
+        Connection::execute(&db, query).await.unwrap();
+
+        let rows = Connection::fetch(
+            &db,
+            "SELECT id, data, NULL AS empty, 1.5 AS ratio, COUNT(*) AS total, MAX(created_at) AS latest FROM item".into(),
+        )
+        .await
+        .unwrap();
+        let row = &rows[0];
+        assert_eq!(row.get::<Option<String>>("id").unwrap(), None);
+        assert_eq!(
+            row.get::<serde_json::Value>("data").unwrap(),
+            serde_json::json!({"a": 1})
+        );
+        assert!(matches!(row.try_get("empty".into()).unwrap(), Value::Null));
+        assert_eq!(row.get::<f64>("ratio").unwrap(), 1.5);
+        assert_eq!(row.get::<i32>("total").unwrap(), 1);
+        assert_eq!(
+            row.get::<chrono::DateTime<chrono::Utc>>("latest").unwrap(),
+            created_at
+        );
+    }
+}
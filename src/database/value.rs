@@ -1,198 +1,88 @@
+use super::error::Error;
 use chrono::{offset::Utc, DateTime};
 
 #[derive(Debug, Clone)]
 pub enum Value {
+    Null,
     Bool(Option<bool>),
+    /// Integers of any width. BIGINTs, such as counts on Postgres and MySQL,
+    /// fail to decode past `i32::MAX`.
     Int(Option<i32>),
     Unsigned(Option<u32>),
+    Float(Option<f64>),
     String(Option<String>),
     Bytes(Option<Vec<u8>>),
     DateTime(Option<DateTime<Utc>>),
-}
-
-impl TryInto<Option<String>> for Value {
-    type Error = ();
-
-    fn try_into(self) -> Result<Option<String>, Self::Error> {
-        if let Value::String(s) = self {
-            return Ok(s);
-        }
-        Err(())
-    }
-}
-
-impl TryInto<String> for Value {
-    type Error = ();
-
-    fn try_into(self) -> Result<String, Self::Error> {
-        if let Some(s) = self.try_into().unwrap() {
-            return Ok(s);
+    Json(Option<serde_json::Value>),
+}
+
+macro_rules! impl_value {
+    ($variant:ident, $type:ty $(, $pat:pat => $expr:expr)*) => {
+        impl TryInto<Option<$type>> for Value {
+            type Error = Error;
+
+            fn try_into(self) -> Result<Option<$type>, Self::Error> {
+                match self {
+                    Value::$variant(v) => Ok(v),
+                    Value::Null => Ok(None),
+                    $($pat => $expr,)*
+                    _ => Err(Error::decode("")),
+                }
+            }
         }
-        Ok(Default::default())
-    }
-}
 
-impl TryInto<Option<i32>> for Value {
-    type Error = ();
+        impl TryInto<$type> for Value {
+            type Error = Error;
 
-    fn try_into(self) -> Result<Option<i32>, Self::Error> {
-        if let Value::Int(i) = self {
-            return Ok(i);
+            fn try_into(self) -> Result<$type, Self::Error> {
+                let v: Option<$type> = self.try_into()?;
+                v.ok_or(Error::decode(""))
+            }
         }
-        Err(())
-    }
-}
-
-impl TryInto<i32> for Value {
-    type Error = ();
 
-    fn try_into(self) -> Result<i32, Self::Error> {
-        if let Some(i) = self.try_into().unwrap() {
-            return Ok(i);
+        impl From<$type> for Value {
+            fn from(v: $type) -> Self {
+                Value::$variant(Some(v))
+            }
         }
-        Ok(Default::default())
-    }
-}
 
-impl TryInto<Option<u32>> for Value {
-    type Error = ();
-
-    fn try_into(self) -> Result<Option<u32>, Self::Error> {
-        match self {
-            Value::Unsigned(u) => Ok(u),
-            Value::Int(i) => Ok(u32::try_from(i.unwrap()).map(|u| Some(u)).map_err(|_| ())?),
-            _ => Err(()),
+        impl From<Option<$type>> for Value {
+            fn from(v: Option<$type>) -> Self {
+                Value::$variant(v)
+            }
         }
-    }
-}
-
-impl TryInto<u32> for Value {
-    type Error = ();
-
-    fn try_into(self) -> Result<u32, Self::Error> {
-        if let Some(u) = self.try_into().unwrap() {
-            return Ok(u);
-        }
-        Ok(Default::default())
-    }
-}
-
-impl TryInto<Option<bool>> for Value {
-    type Error = ();
-
-    fn try_into(self) -> Result<Option<bool>, Self::Error> {
-        if let Value::Bool(b) = self {
-            return Ok(b);
-        }
-        Err(())
-    }
-}
-
-impl TryInto<bool> for Value {
-    type Error = ();
-
-    fn try_into(self) -> Result<bool, Self::Error> {
-        if let Some(b) = self.try_into().unwrap() {
-            return Ok(b);
-        }
-        Ok(Default::default())
-    }
-}
-
-impl TryInto<Option<DateTime<Utc>>> for Value {
-    type Error = ();
-
-    fn try_into(self) -> Result<Option<DateTime<Utc>>, Self::Error> {
-        if let Value::DateTime(dt) = self {
-            return Ok(dt);
-        }
-        Err(())
-    }
-}
-
-impl TryInto<DateTime<Utc>> for Value {
-    type Error = ();
-
-    fn try_into(self) -> Result<DateTime<Utc>, Self::Error> {
-        if let Some(dt) = self.try_into().unwrap() {
-            return Ok(dt);
-        }
-        Ok(Default::default())
-    }
-}
-
-impl Into<Value> for i32 {
-    fn into(self) -> Value {
-        Value::Int(Some(self))
-    }
-}
-
-impl Into<Value> for Option<i32> {
-    fn into(self) -> Value {
-        Value::Int(self)
-    }
-}
-
-impl Into<Value> for u32 {
-    fn into(self) -> Value {
-        Value::Unsigned(Some(self))
-    }
-}
-
-impl Into<Value> for Option<u32> {
-    fn into(self) -> Value {
-        Value::Unsigned(self)
-    }
-}
-
-impl Into<Value> for String {
-    fn into(self) -> Value {
-        Value::String(Some(self))
-    }
-}
-
-impl Into<Value> for Option<String> {
-    fn into(self) -> Value {
-        Value::String(self)
-    }
-}
-
-impl Into<Value> for &str {
-    fn into(self) -> Value {
-        Value::String(Some(self.to_string()))
-    }
-}
-
-impl Into<Value> for Option<&str> {
-    fn into(self) -> Value {
-        Value::String(match self {
-            Some(s) => Some(s.to_string()),
-            None => None,
-        })
-    }
-}
-
-impl Into<Value> for bool {
-    fn into(self) -> Value {
-        Value::Bool(Some(self))
-    }
-}
-
-impl Into<Value> for Option<bool> {
-    fn into(self) -> Value {
-        Value::Bool(self)
-    }
-}
-
-impl Into<Value> for DateTime<Utc> {
-    fn into(self) -> Value {
-        Value::DateTime(Some(self))
-    }
-}
-
-impl Into<Value> for Option<DateTime<Utc>> {
-    fn into(self) -> Value {
-        Value::DateTime(self)
+    };
+}
+
+impl_value!(Bool, bool);
+impl_value!(Int, i32);
+impl_value!(Unsigned, u32, Value::Int(i) => i
+    .map(u32::try_from)
+    .transpose()
+    .map_err(|_| Error::decode("")));
+impl_value!(Float, f64, Value::Int(i) => Ok(i.map(f64::from)));
+impl_value!(String, String);
+impl_value!(Bytes, Vec<u8>);
+// SQLite stores datetimes as TEXT, expressions over them are read back so
+impl_value!(DateTime, DateTime<Utc>, Value::String(s) => s
+    .map(|s| s.parse())
+    .transpose()
+    .map_err(|_| Error::decode("")));
+// SQLite has no JSON type, its JSON columns are read back as TEXT too
+impl_value!(Json, serde_json::Value, Value::String(s) => s
+    .map(|s| serde_json::from_str(&s))
+    .transpose()
+    .map_err(|_| Error::decode("")));
+
+impl From<&str> for Value {
+    fn from(s: &str) -> Self {
+        Value::String(Some(s.to_string()))
+    }
+}
+
+impl From<Option<&str>> for Value {
+    fn from(s: Option<&str>) -> Self {
+        Value::String(s.map(|s| s.to_string()))
     }
 }
 
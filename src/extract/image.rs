@@ -0,0 +1,108 @@
+use super::metatag::{FileInfo, Metatag};
+use crate::link::Fetched;
+
+fn u16_be(bytes: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from(u16::from_be_bytes(
+        bytes.get(at..at + 2)?.try_into().ok()?,
+    )))
+}
+
+fn u16_le(bytes: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from(u16::from_le_bytes(
+        bytes.get(at..at + 2)?.try_into().ok()?,
+    )))
+}
+
+fn u24_le(bytes: &[u8], at: usize) -> Option<u32> {
+    let bytes = bytes.get(at..at + 3)?;
+    Some(u32::from(bytes[0]) | u32::from(bytes[1]) << 8 | u32::from(bytes[2]) << 16)
+}
+
+/// Width and height of a PNG, GIF, JPEG or WebP image from its header.
+pub fn image_size(body: &[u8]) -> Option<(u32, u32)> {
+    if body.starts_with(b"\x89PNG\r\n\x1a\n") && body.get(12..16) == Some(b"IHDR") {
+        let size = |at| Some(u32::from_be_bytes(body.get(at..at + 4)?.try_into().ok()?));
+        Some((size(16)?, size(20)?))
+    } else if body.starts_with(b"GIF8") {
+        Some((u16_le(body, 6)?, u16_le(body, 8)?))
+    } else if body.starts_with(&[0xff, 0xd8]) {
+        jpeg_size(body)
+    } else if body.starts_with(b"RIFF") && body.get(8..12) == Some(b"WEBP") {
+        webp_size(body)
+    } else {
+        None
+    }
+}
+
+/// The size in the first start of frame segment of a JPEG.
+fn jpeg_size(body: &[u8]) -> Option<(u32, u32)> {
+    let mut at = 2;
+    loop {
+        if *body.get(at)? != 0xff {
+            return None;
+        }
+        let marker = *body.get(at + 1)?;
+        match marker {
+            // Fill bytes
+            0xff => at += 1,
+            // Markers without a length
+            0x01 | 0xd0..=0xd7 => at += 2,
+            0xc0..=0xcf if !matches!(marker, 0xc4 | 0xc8 | 0xcc) => {
+                return Some((u16_be(body, at + 7)?, u16_be(body, at + 5)?));
+            }
+            _ => at += 2 + u16_be(body, at + 2)? as usize,
+        }
+    }
+}
+
+fn webp_size(body: &[u8]) -> Option<(u32, u32)> {
+    match body.get(12..16)? {
+        b"VP8 " => Some((u16_le(body, 26)? & 0x3fff, u16_le(body, 28)? & 0x3fff)),
+        b"VP8L" => {
+            let bits = u32::from_le_bytes(body.get(21..25)?.try_into().ok()?);
+            Some((1 + (bits & 0x3fff), 1 + (bits >> 14 & 0x3fff)))
+        }
+        b"VP8X" => Some((1 + u24_le(body, 24)?, 1 + u24_le(body, 27)?)),
+        _ => None,
+    }
+}
+
+pub fn image_metatag(fetched: &Fetched) -> Metatag {
+    let size = image_size(&fetched.body);
+    let file = FileInfo {
+        width: size.map(|(width, _)| width),
+        height: size.map(|(_, height)| height),
+        ..FileInfo::new(fetched)
+    };
+    Metatag {
+        title: file.file_name.clone(),
+        image_url: Some(fetched.url.to_string()),
+        file: Some(file),
+        ..Default::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn image_sizes() {
+        let png = b"\x89PNG\r\n\x1a\n\0\0\0\x0dIHDR\0\0\x01\x2c\0\0\0\xc8\x08\x06\0\0\0";
+        assert_eq!(image_size(png), Some((300, 200)));
+        assert_eq!(image_size(b"GIF89a\x10\0\x20\0\0"), Some((16, 32)));
+        let jpeg = [
+            &[0xff, 0xd8, 0xff, 0xe0, 0x00, 0x04, 0x4a, 0x46][..],
+            &[0xff, 0xc2, 0x00, 0x11, 0x08, 0x01, 0xe0, 0x02, 0x80, 0x03][..],
+        ]
+        .concat();
+        assert_eq!(image_size(&jpeg), Some((640, 480)));
+        let webp = [
+            &b"RIFF\0\0\0\0WEBPVP8X\x0a\0\0\0\0\0\0\0"[..],
+            &[0x7f, 0x02, 0x00, 0xdf, 0x01, 0x00][..],
+        ]
+        .concat();
+        assert_eq!(image_size(&webp), Some((640, 480)));
+        assert_eq!(image_size(b"<svg/>"), None);
+    }
+}
@@ -0,0 +1,720 @@
+use super::{
+    image::image_metatag,
+    oembed::{discover, fetch_oembed, Oembed},
+    pdf::pdf_metatag,
+    site::SiteData,
+    structured::{json_ld, microdata, StructuredData},
+};
+use crate::{
+    app::{util, AppState},
+    archive::{self, is_html, Page, MAX_PAGE_SIZE},
+    database,
+    link::{resolve, FetchError, Fetched},
+    model::ResourceMetatag,
+    repo,
+};
+use scraper::{Html, Selector};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, str::FromStr};
+use url::Url;
+
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+#[serde(default)]
+pub struct Metatag {
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub image_url: Option<String>,
+    pub canonical_url: Option<String>,
+    pub site_name: Option<String>,
+    pub favicon_url: Option<String>,
+    pub author: Option<String>,
+    pub published_time: Option<String>,
+    pub language: Option<String>,
+    pub keywords: Vec<String>,
+    /// The schema.org item of the page, from JSON-LD or else microdata
+    pub structured_data: Option<StructuredData>,
+    pub oembed: Option<Oembed>,
+    /// What the url serves when it is not an HTML page
+    pub file: Option<FileInfo>,
+    /// Fields particular to the site of the page, e.g. a GitHub repository
+    pub site: Option<SiteData>,
+}
+
+impl Metatag {
+    /// Makes the urls of `self` absolute, dropping those that are not web
+    /// urls.
+    pub fn resolve_urls(self, base: &Url) -> Self {
+        let resolve =
+            |url: Option<String>| url.and_then(|url| resolve(base, &url)).map(String::from);
+        Self {
+            image_url: resolve(self.image_url),
+            canonical_url: resolve(self.canonical_url),
+            favicon_url: resolve(self.favicon_url),
+            structured_data: self.structured_data.map(|structured| StructuredData {
+                image_url: resolve(structured.image_url),
+                ..structured
+            }),
+            oembed: self.oembed.map(|oembed| Oembed {
+                thumbnail_url: resolve(oembed.thumbnail_url),
+                author_url: resolve(oembed.author_url),
+                ..oembed
+            }),
+            ..self
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+pub struct FileInfo {
+    pub content_type: String,
+    pub file_name: Option<String>,
+    pub size: usize,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+}
+
+impl FileInfo {
+    pub fn new(fetched: &Fetched) -> Self {
+        let file_name = fetched
+            .url
+            .path_segments()
+            .and_then(|mut segments| segments.next_back())
+            .map(|name| percent_encoding::percent_decode_str(name).decode_utf8_lossy())
+            .and_then(|name| non_empty(&name));
+        Self {
+            content_type: fetched.content_type.clone(),
+            file_name,
+            size: fetched.body.len(),
+            ..Default::default()
+        }
+    }
+}
+
+/// Where a field of a `Metatag` came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Source {
+    /// `<title>`, `<meta name>` and `<link rel>` tags
+    Html,
+    OpenGraph,
+    Twitter,
+    JsonLd,
+    Microdata,
+    Oembed,
+}
+
+impl FromStr for Source {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "html" => Ok(Self::Html),
+            "opengraph" => Ok(Self::OpenGraph),
+            "twitter" => Ok(Self::Twitter),
+            "jsonld" => Ok(Self::JsonLd),
+            "microdata" => Ok(Self::Microdata),
+            "oembed" => Ok(Self::Oembed),
+            s => Err(format!("unknown metadata source {}", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Field {
+    Title,
+    Description,
+    ImageUrl,
+    CanonicalUrl,
+    SiteName,
+    FaviconUrl,
+    Author,
+    PublishedTime,
+    Language,
+    Keywords,
+}
+
+/// The order sources are tried in for each field, the first having a value
+/// wins. Sources left out are not used at all.
+#[derive(Debug, Clone)]
+pub struct Precedence {
+    sources: Vec<Source>,
+    fields: HashMap<Field, Vec<Source>>,
+}
+
+/// OpenGraph images of video and social sites tend to be their logo, their
+/// oEmbed thumbnail is preferred.
+impl Default for Precedence {
+    fn default() -> Self {
+        Self::new(vec![
+            Source::Html,
+            Source::OpenGraph,
+            Source::Twitter,
+            Source::JsonLd,
+            Source::Microdata,
+            Source::Oembed,
+        ])
+        .with_field(
+            Field::ImageUrl,
+            vec![
+                Source::Oembed,
+                Source::Html,
+                Source::OpenGraph,
+                Source::Twitter,
+                Source::JsonLd,
+                Source::Microdata,
+            ],
+        )
+    }
+}
+
+impl Precedence {
+    pub fn new(sources: Vec<Source>) -> Self {
+        Self {
+            sources,
+            fields: HashMap::new(),
+        }
+    }
+
+    /// Overrides the order of `field`.
+    pub fn with_field(mut self, field: Field, sources: Vec<Source>) -> Self {
+        self.fields.insert(field, sources);
+        self
+    }
+
+    pub fn sources(&self, field: Field) -> &[Source] {
+        self.fields.get(&field).unwrap_or(&self.sources)
+    }
+}
+
+/// Parses a comma separated list of sources, e.g. `opengraph,twitter,html`.
+impl FromStr for Precedence {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.split(',')
+            .map(Source::from_str)
+            .collect::<Result<_, _>>()
+            .map(Self::new)
+    }
+}
+
+/// Metadata of `document`, each field from the first source by `precedence`
+/// having it.
+pub fn metatag(
+    document: &Html,
+    precedence: &Precedence,
+    oembed: Option<Oembed>,
+) -> Option<Metatag> {
+    let json_ld = json_ld(document);
+    let microdata = microdata(document);
+    let extracted = [
+        (Source::Html, extract_from_html(document)),
+        (Source::OpenGraph, extract_from_opengraph(document)),
+        (Source::Twitter, extract_from_twitter(document)),
+        (Source::JsonLd, extract_from_structured(json_ld.clone())),
+        (
+            Source::Microdata,
+            extract_from_structured(microdata.clone()),
+        ),
+        (Source::Oembed, extract_from_oembed(oembed.as_ref())),
+    ]
+    .into_iter()
+    .collect::<HashMap<_, _>>();
+    let pick = |field: Field, get: fn(&Metatag) -> &Option<String>| {
+        precedence
+            .sources(field)
+            .iter()
+            .filter_map(|source| extracted.get(source))
+            .find_map(|m| get(m).clone())
+    };
+
+    let metatag = Metatag {
+        title: pick(Field::Title, |m| &m.title),
+        description: pick(Field::Description, |m| &m.description),
+        image_url: pick(Field::ImageUrl, |m| &m.image_url),
+        canonical_url: pick(Field::CanonicalUrl, |m| &m.canonical_url),
+        site_name: pick(Field::SiteName, |m| &m.site_name),
+        favicon_url: pick(Field::FaviconUrl, |m| &m.favicon_url),
+        author: pick(Field::Author, |m| &m.author),
+        published_time: pick(Field::PublishedTime, |m| &m.published_time),
+        language: pick(Field::Language, |m| &m.language),
+        keywords: precedence
+            .sources(Field::Keywords)
+            .iter()
+            .filter_map(|source| extracted.get(source))
+            .map(|m| &m.keywords)
+            .find(|keywords| !keywords.is_empty())
+            .cloned()
+            .unwrap_or_default(),
+        structured_data: json_ld.or(microdata),
+        oembed,
+        file: None,
+        site: None,
+    };
+    Some(metatag).filter(|m| *m != Metatag::default())
+}
+
+/// Error of a metadata lookup.
+#[derive(Debug)]
+pub enum MetatagError {
+    Fetch(archive::Error),
+    Database(database::Error),
+}
+
+impl std::fmt::Display for MetatagError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Fetch(e) => e.fmt(f),
+            Self::Database(e) => write!(f, "database error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for MetatagError {}
+
+impl From<archive::Error> for MetatagError {
+    fn from(e: archive::Error) -> Self {
+        Self::Fetch(e)
+    }
+}
+
+impl From<FetchError> for MetatagError {
+    fn from(e: FetchError) -> Self {
+        Self::Fetch(e.into())
+    }
+}
+
+impl From<database::Error> for MetatagError {
+    fn from(e: database::Error) -> Self {
+        Self::Database(e)
+    }
+}
+
+/// Fetches `url` and extracts the metadata of the page, PDF or image it
+/// serves. When `url` is of a bookmarked resource, what was last fetched for
+/// it is used instead unless that is older than the configured TTL or
+/// `refresh` is set, and stale metadata is revalidated with the validators of
+/// its response. Lookups of other urls are not cached.
+pub async fn fetch_metatag(
+    app_state: &AppState,
+    url: &str,
+    refresh: bool,
+) -> Result<Option<Metatag>, MetatagError> {
+    let conn = app_state.database().connection();
+    let Some(resource) = repo::resource::find_resource_of_url(app_state, &conn, url).await? else {
+        let fetched = app_state.fetcher().fetch(url, MAX_PAGE_SIZE).await?;
+        return Ok(describe(app_state, fetched).await?);
+    };
+    let cached = match repo::metatag::find_metatag(app_state, &conn, resource.id.clone()).await {
+        Ok(cached) => Some(cached),
+        Err(database::Error::NotFound) => None,
+        Err(e) => return Err(e.into()),
+    };
+
+    let now = util::now();
+    let validators = match &cached {
+        Some(cached) if !refresh && cached.is_fresh(now, app_state.metatag_ttl()) => {
+            return Ok(cached.metatag());
+        }
+        Some(cached) if !refresh => (cached.etag.as_deref(), cached.last_modified.as_deref()),
+        _ => (None, None),
+    };
+    let fetched = app_state
+        .fetcher()
+        .fetch_modified(&resource.url, MAX_PAGE_SIZE, validators.0, validators.1)
+        .await?;
+    let entry = match (fetched, cached) {
+        (Some(fetched), cached) => {
+            let mut entry = ResourceMetatag {
+                id: cached.map_or_else(util::new_uid, |cached| cached.id),
+                resource_id: resource.id,
+                etag: fetched.etag.clone(),
+                last_modified: fetched.last_modified.clone(),
+                fetched_at: Some(now),
+                ..Default::default()
+            };
+            entry.set_metatag(describe(app_state, fetched).await?.as_ref());
+            entry
+        }
+        (None, Some(cached)) => ResourceMetatag {
+            fetched_at: Some(now),
+            ..cached
+        },
+        // Not modified, though nothing was asked to be
+        (None, None) => return Err(FetchError::Status(304).into()),
+    };
+    repo::metatag::upsert_metatag(app_state, &conn, entry.clone()).await?;
+    Ok(entry.metatag())
+}
+
+/// Metadata of a fetched page or file.
+async fn describe(
+    app_state: &AppState,
+    fetched: Fetched,
+) -> Result<Option<Metatag>, archive::Error> {
+    match fetched.content_type.as_str() {
+        content_type if is_html(content_type) => {
+            Ok(page_metatag(app_state, &Page::decode(fetched)).await)
+        }
+        "application/pdf" => Ok(Some(pdf_metatag(&fetched))),
+        content_type if content_type.starts_with("image/") => Ok(Some(image_metatag(&fetched))),
+        _ => Err(archive::Error::Unsupported(fetched.content_type)),
+    }
+}
+
+/// Metadata of `page`, along with its oEmbed document when the page
+/// advertises one or a provider serves it.
+pub async fn page_metatag(app_state: &AppState, page: &Page) -> Option<Metatag> {
+    let endpoint = {
+        let document = Html::parse_document(&page.html);
+        discover(&document, &page.base_url(&document))
+    }
+    .or_else(|| app_state.oembed_providers().endpoint(page.url.as_str()));
+    let oembed = match endpoint {
+        Some(endpoint) => match fetch_oembed(app_state.fetcher(), &endpoint).await {
+            Ok(oembed) => Some(oembed),
+            Err(e) => {
+                log::debug!("oEmbed of {} failed - {}", page.url, e);
+                None
+            }
+        },
+        None => None,
+    };
+
+    let document = Html::parse_document(&page.html);
+    let site = app_state.site_extractors().extract(&document, &page.url);
+    let metatag = match (
+        metatag(&document, app_state.metatag_precedence(), oembed),
+        site,
+    ) {
+        (Some(metatag), site) => Metatag { site, ..metatag },
+        (None, Some(site)) => Metatag {
+            site: Some(site),
+            ..Default::default()
+        },
+        (None, None) => return None,
+    };
+    Some(metatag.resolve_urls(&page.base_url(&document)))
+}
+
+pub(super) fn non_empty(value: &str) -> Option<String> {
+    Some(value.trim().to_string()).filter(|value| !value.is_empty())
+}
+
+/// The first non-empty `attr` of the elements matching `selector`.
+fn select_attr(document: &Html, selector: &str, attr: &str) -> Option<String> {
+    document
+        .select(&Selector::parse(selector).unwrap())
+        .filter_map(|e| e.value().attr(attr))
+        .find_map(non_empty)
+}
+
+/// Content of the `<meta>` tags named `name`, by `property` as OpenGraph
+/// has it or by `name` as many pages do anyway.
+pub(super) fn select_meta(document: &Html, name: &str) -> Vec<String> {
+    let selector = format!(r#"meta[property="{0}" i], meta[name="{0}" i]"#, name);
+    document
+        .select(&Selector::parse(&selector).unwrap())
+        .filter_map(|e| e.value().attr("content"))
+        .filter_map(non_empty)
+        .collect()
+}
+
+fn first_meta(document: &Html, names: &[&str]) -> Option<String> {
+    names
+        .iter()
+        .find_map(|name| select_meta(document, name).into_iter().next())
+}
+
+fn extract_from_html(document: &Html) -> Metatag {
+    Metatag {
+        title: document
+            .select(&Selector::parse("title").unwrap())
+            .next()
+            .and_then(|e| non_empty(&e.text().collect::<String>())),
+        description: first_meta(document, &["description"]),
+        image_url: select_attr(document, r#"link[rel~="image_src" i]"#, "href"),
+        canonical_url: select_attr(document, r#"link[rel~="canonical" i]"#, "href"),
+        site_name: first_meta(document, &["application-name"]),
+        favicon_url: select_attr(document, r#"link[rel~="icon" i]"#, "href"),
+        author: first_meta(document, &["author"]),
+        published_time: first_meta(document, &["date", "dcterms.created", "dc.date"]),
+        language: select_attr(document, "html[lang]", "lang").or_else(|| {
+            select_attr(
+                document,
+                r#"meta[http-equiv="content-language" i]"#,
+                "content",
+            )
+        }),
+        keywords: first_meta(document, &["keywords"])
+            .map(|keywords| keywords.split(',').filter_map(non_empty).collect())
+            .unwrap_or_default(),
+        structured_data: None,
+        oembed: None,
+        file: None,
+        site: None,
+    }
+}
+
+fn extract_from_opengraph(document: &Html) -> Metatag {
+    Metatag {
+        title: first_meta(document, &["og:title"]),
+        description: first_meta(document, &["og:description"]),
+        image_url: first_meta(
+            document,
+            &["og:image", "og:image:url", "og:image:secure_url"],
+        ),
+        canonical_url: first_meta(document, &["og:url"]),
+        site_name: first_meta(document, &["og:site_name"]),
+        favicon_url: None,
+        author: first_meta(document, &["article:author"]),
+        published_time: first_meta(document, &["article:published_time"]),
+        // Locales are written `en_US`
+        language: first_meta(document, &["og:locale"]).map(|locale| locale.replace('_', "-")),
+        keywords: select_meta(document, "article:tag"),
+        structured_data: None,
+        oembed: None,
+        file: None,
+        site: None,
+    }
+}
+
+fn extract_from_twitter(document: &Html) -> Metatag {
+    Metatag {
+        title: first_meta(document, &["twitter:title"]),
+        description: first_meta(document, &["twitter:description"]),
+        image_url: first_meta(document, &["twitter:image", "twitter:image:src"]),
+        ..Default::default()
+    }
+}
+
+fn extract_from_structured(structured: Option<StructuredData>) -> Metatag {
+    let Some(structured) = structured else {
+        return Metatag::default();
+    };
+    Metatag {
+        title: structured.headline,
+        description: structured.description,
+        image_url: structured.image_url,
+        author: structured.author,
+        published_time: structured.date_published,
+        ..Default::default()
+    }
+}
+
+fn extract_from_oembed(oembed: Option<&Oembed>) -> Metatag {
+    let Some(oembed) = oembed else {
+        return Metatag::default();
+    };
+    Metatag {
+        title: oembed.title.clone(),
+        image_url: oembed.thumbnail_url.clone(),
+        site_name: oembed.provider_name.clone(),
+        author: oembed.author_name.clone(),
+        ..Default::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PAGE: &str = r#"<html lang="en"><head>
+        <title> Plain title </title>
+        <link rel="canonical" href="/post">
+        <link rel="shortcut icon" href="/favicon.png">
+        <meta name="keywords" content="rust, , web">
+        <meta property="og:title" content="OpenGraph title">
+        <meta property="og:description" content="">
+        <meta property="og:site_name" content="Example">
+        <meta property="og:locale" content="en_GB">
+        <meta property="article:published_time" content="2024-05-01T10:00:00Z">
+        <meta property="article:tag" content="bookmarks">
+        <meta property="article:tag" content="archiving">
+        <meta name="twitter:description" content="Twitter description">
+        <meta name="twitter:image" content="https://example.com/a.png">
+        </head></html>"#;
+
+    #[test]
+    fn metatag_per_field() {
+        let document = Html::parse_document(PAGE);
+        assert_eq!(
+            metatag(&document, &Precedence::default(), None),
+            Some(Metatag {
+                title: Some("Plain title".to_string()),
+                description: Some("Twitter description".to_string()),
+                image_url: Some("https://example.com/a.png".to_string()),
+                canonical_url: Some("/post".to_string()),
+                site_name: Some("Example".to_string()),
+                favicon_url: Some("/favicon.png".to_string()),
+                author: None,
+                published_time: Some("2024-05-01T10:00:00Z".to_string()),
+                language: Some("en".to_string()),
+                keywords: vec!["rust".to_string(), "web".to_string()],
+                structured_data: None,
+                oembed: None,
+                file: None,
+                site: None,
+            })
+        );
+        assert_eq!(
+            metatag(
+                &Html::parse_document("<p>none</p>"),
+                &Precedence::default(),
+                None
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn metatag_precedence() {
+        let document = Html::parse_document(PAGE);
+        let precedence = "opengraph, twitter, html"
+            .parse::<Precedence>()
+            .unwrap()
+            .with_field(Field::Description, vec![Source::Html]);
+        let m = metatag(&document, &precedence, None).unwrap();
+        assert_eq!(m.title.as_deref(), Some("OpenGraph title"));
+        assert_eq!(m.description, None);
+        assert_eq!(m.language.as_deref(), Some("en-GB"));
+        assert_eq!(m.keywords, vec!["bookmarks", "archiving"]);
+
+        let m = m.resolve_urls(&Url::parse("https://example.com/blog/").unwrap());
+        assert_eq!(m.canonical_url.as_deref(), Some("https://example.com/post"));
+        assert_eq!(
+            m.favicon_url.as_deref(),
+            Some("https://example.com/favicon.png")
+        );
+
+        assert!("opengraph,rss".parse::<Precedence>().is_err());
+    }
+
+    #[test]
+    fn metatag_structured() {
+        let document = Html::parse_document(include_str!("../../fixtures/structured/recipe.html"));
+        let m = metatag(&document, &Precedence::default(), None).unwrap();
+        assert_eq!(
+            m.title.as_deref(),
+            Some("Mountain sourdough - The Crumb Journal")
+        );
+        assert_eq!(m.author.as_deref(), Some("Ana Lima, Ben Ode"));
+        assert_eq!(m.published_time.as_deref(), Some("2024-03-02"));
+        assert_eq!(
+            m.structured_data.and_then(|s| s.kind).as_deref(),
+            Some("Recipe")
+        );
+
+        let m = metatag(&document, &"jsonld,html".parse().unwrap(), None).unwrap();
+        assert_eq!(m.title.as_deref(), Some("Mountain sourdough"));
+    }
+
+    #[test]
+    fn metatag_oembed() {
+        let document = Html::parse_document(PAGE);
+        let oembed = Oembed {
+            kind: "video".to_string(),
+            author_name: Some("Channel".to_string()),
+            thumbnail_url: Some("https://example.com/thumb.jpg".to_string()),
+            ..Default::default()
+        };
+        let m = metatag(&document, &Precedence::default(), Some(oembed.clone())).unwrap();
+        assert_eq!(m.title.as_deref(), Some("Plain title"));
+        assert_eq!(
+            m.image_url.as_deref(),
+            Some("https://example.com/thumb.jpg")
+        );
+        assert_eq!(m.author.as_deref(), Some("Channel"));
+        assert_eq!(m.oembed, Some(oembed));
+    }
+
+    #[tokio::test]
+    async fn fetch_metatag_cached() {
+        use crate::{link::FetchPolicy, testutil};
+        use axum::{
+            http::{header, HeaderMap, StatusCode},
+            response::IntoResponse,
+            routing::get,
+            Router,
+        };
+        use std::sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        };
+
+        // Serves a new title on every request it does not answer with 304
+        let hits = Arc::new(AtomicUsize::new(0));
+        let counter = hits.clone();
+        let router = Router::new().route(
+            "/page",
+            get(move |headers: HeaderMap| async move {
+                let hit = counter.fetch_add(1, Ordering::SeqCst) + 1;
+                if headers.contains_key(header::IF_NONE_MATCH) {
+                    return StatusCode::NOT_MODIFIED.into_response();
+                }
+                let html = format!("<title>Page {}</title>", hit);
+                (
+                    [(header::CONTENT_TYPE, "text/html"), (header::ETAG, "\"v\"")],
+                    html,
+                )
+                    .into_response()
+            }),
+        );
+        let url = format!("{}/page", testutil::serve(router).await);
+
+        let (app_state, _worker) = testutil::app_state().await;
+        let app_state = app_state.with_fetch_policy(FetchPolicy {
+            allow_private: true,
+            ..Default::default()
+        });
+        let title = |app_state: AppState, refresh: bool| {
+            let url = url.clone();
+            async move {
+                fetch_metatag(&app_state, &url, refresh)
+                    .await
+                    .unwrap()
+                    .and_then(|m| m.title)
+            }
+        };
+
+        // Lookups of urls nobody bookmarked are neither cached nor enrolled
+        assert_eq!(
+            title(app_state.clone(), false).await.as_deref(),
+            Some("Page 1")
+        );
+        assert_eq!(
+            title(app_state.clone(), false).await.as_deref(),
+            Some("Page 2")
+        );
+        let conn = app_state.database().connection();
+        assert!(
+            repo::resource::find_resource_of_url(&app_state, &conn, &url)
+                .await
+                .unwrap()
+                .is_none()
+        );
+
+        repo::resource::upsert_resource(&app_state, &conn, &url)
+            .await
+            .unwrap();
+        assert_eq!(
+            title(app_state.clone(), false).await.as_deref(),
+            Some("Page 3")
+        );
+        assert_eq!(
+            title(app_state.clone(), false).await.as_deref(),
+            Some("Page 3")
+        );
+        assert_eq!(hits.load(Ordering::SeqCst), 3);
+
+        let stale = app_state.clone().with_metatag_ttl(chrono::Duration::zero());
+        assert_eq!(title(stale, false).await.as_deref(), Some("Page 3"));
+        assert_eq!(hits.load(Ordering::SeqCst), 4);
+
+        assert_eq!(
+            title(app_state.clone(), true).await.as_deref(),
+            Some("Page 5")
+        );
+        assert_eq!(title(app_state, false).await.as_deref(), Some("Page 5"));
+        assert_eq!(hits.load(Ordering::SeqCst), 5);
+    }
+}
@@ -0,0 +1,15 @@
+mod image;
+mod metatag;
+mod oembed;
+mod pdf;
+mod readable;
+mod site;
+mod structured;
+
+pub use image::*;
+pub use metatag::*;
+pub use oembed::*;
+pub use pdf::*;
+pub use readable::*;
+pub use site::*;
+pub use structured::*;
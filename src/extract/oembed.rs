@@ -0,0 +1,341 @@
+use crate::link::{resolve, FetchError, Fetcher};
+use regex::Regex;
+use scraper::{ElementRef, Html, Node, Selector};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use url::Url;
+
+pub const MAX_OEMBED_SIZE: usize = 1 << 20;
+
+/// Providers of the registry when none are configured, in the format of
+/// https://oembed.com/providers.json
+const DEFAULT_PROVIDERS: &str = include_str!("oembed_providers.json");
+
+/// Tags kept in embed HTML, unknown ones are replaced by their content.
+const ALLOWED_TAGS: [&str; 12] = [
+    "iframe",
+    "blockquote",
+    "p",
+    "a",
+    "br",
+    "em",
+    "strong",
+    "span",
+    "div",
+    "figure",
+    "figcaption",
+    "img",
+];
+const IFRAME_ATTRS: [&str; 6] = [
+    "width",
+    "height",
+    "title",
+    "allow",
+    "allowfullscreen",
+    "frameborder",
+];
+/// Embedded players need scripts, the sandbox keeps them from navigating
+/// the page they are embedded in. Without `allow-same-origin` they run in an
+/// opaque origin, so they can neither read that page nor lift the sandbox.
+const IFRAME_SANDBOX: &str = "allow-scripts allow-popups allow-presentation";
+
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+pub struct Oembed {
+    /// `video`, `photo`, `rich` or `link`
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub title: Option<String>,
+    pub author_name: Option<String>,
+    pub author_url: Option<String>,
+    pub provider_name: Option<String>,
+    pub thumbnail_url: Option<String>,
+    /// Sanitized embed HTML
+    pub html: Option<String>,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+}
+
+impl Oembed {
+    pub fn parse(body: &[u8]) -> Option<Self> {
+        let value = serde_json::from_slice::<Value>(body).ok()?;
+        let text = |name: &str| {
+            value
+                .get(name)
+                .and_then(Value::as_str)
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+        };
+        // Providers send sizes as numbers or as strings
+        let size = |name: &str| match value.get(name)? {
+            Value::Number(n) => n.as_u64().and_then(|n| u32::try_from(n).ok()),
+            Value::String(s) => s.trim().parse().ok(),
+            _ => None,
+        };
+        let kind = text("type")?;
+        Some(Self {
+            title: text("title"),
+            author_name: text("author_name"),
+            author_url: text("author_url"),
+            provider_name: text("provider_name"),
+            // Photos are their own thumbnail
+            thumbnail_url: text("thumbnail_url").or_else(|| match kind.as_str() {
+                "photo" => text("url"),
+                _ => None,
+            }),
+            html: text("html").map(|html| sanitize(&html)),
+            width: size("width"),
+            height: size("height"),
+            kind,
+        })
+    }
+}
+
+/// The JSON oEmbed endpoint `document` advertises.
+pub fn discover(document: &Html, base: &Url) -> Option<Url> {
+    document
+        .select(
+            &Selector::parse(r#"link[rel~="alternate" i][type="application/json+oembed" i][href]"#)
+                .unwrap(),
+        )
+        .filter_map(|link| link.value().attr("href"))
+        .find_map(|href| resolve(base, href))
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Provider {
+    pub provider_name: String,
+    #[serde(default)]
+    pub endpoints: Vec<Endpoint>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Endpoint {
+    /// Urls the endpoint serves, `*` matching anything
+    #[serde(default)]
+    pub schemes: Vec<String>,
+    pub url: String,
+}
+
+/// oEmbed providers for sites that do not advertise their endpoint.
+#[derive(Debug, Clone)]
+pub struct Providers {
+    endpoints: Vec<(Regex, String)>,
+}
+
+impl Default for Providers {
+    fn default() -> Self {
+        Self::from_json(DEFAULT_PROVIDERS).unwrap()
+    }
+}
+
+impl Providers {
+    pub fn new(providers: Vec<Provider>) -> Self {
+        let endpoints = providers
+            .into_iter()
+            .flat_map(|provider| provider.endpoints)
+            .flat_map(|endpoint| {
+                endpoint
+                    .schemes
+                    .iter()
+                    .filter_map(|scheme| scheme_pattern(scheme))
+                    .map(|pattern| (pattern, endpoint.url.clone()))
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        Self { endpoints }
+    }
+
+    /// Reads a registry in the format of https://oembed.com/providers.json
+    pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(json).map(Self::new)
+    }
+
+    /// The JSON oEmbed request for `url`, from the first provider serving it.
+    pub fn endpoint(&self, url: &str) -> Option<Url> {
+        let (_, endpoint) = self
+            .endpoints
+            .iter()
+            .find(|(pattern, _)| pattern.is_match(url))?;
+        let mut endpoint = Url::parse(&endpoint.replace("{format}", "json")).ok()?;
+        endpoint
+            .query_pairs_mut()
+            .append_pair("url", url)
+            .append_pair("format", "json");
+        Some(endpoint)
+    }
+}
+
+fn scheme_pattern(scheme: &str) -> Option<Regex> {
+    let pattern = regex::escape(scheme).replace(r"\*", ".*");
+    Regex::new(&format!("^{}$", pattern)).ok()
+}
+
+pub async fn fetch_oembed(fetcher: &Fetcher, endpoint: &Url) -> Result<Oembed, FetchError> {
+    let fetched = fetcher.fetch(endpoint.as_str(), MAX_OEMBED_SIZE).await?;
+    Oembed::parse(&fetched.body)
+        .ok_or_else(|| FetchError::Request("invalid oEmbed document".to_string()))
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Keeps the markup of embed HTML that shows something, iframes only when
+/// served over https, and drops scripts, styles and event handlers.
+pub fn sanitize(html: &str) -> String {
+    let fragment = Html::parse_fragment(html);
+    let mut sanitized = String::new();
+    sanitize_children(fragment.root_element(), &mut sanitized);
+    sanitized
+}
+
+fn sanitize_children(element: ElementRef, html: &mut String) {
+    for child in element.children() {
+        match child.value() {
+            Node::Text(text) => html.push_str(&escape(text)),
+            Node::Element(_) => sanitize_element(ElementRef::wrap(child).unwrap(), html),
+            _ => {}
+        }
+    }
+}
+
+fn sanitize_element(element: ElementRef, html: &mut String) {
+    let value = element.value();
+    let name = value.name();
+    if matches!(name, "script" | "style" | "noscript" | "template") {
+        return;
+    }
+    if !ALLOWED_TAGS.contains(&name) {
+        return sanitize_children(element, html);
+    }
+
+    let https = |attr: &str| {
+        value
+            .attr(attr)
+            .and_then(|src| Url::parse(src.trim()).ok())
+            .filter(|url| url.scheme() == "https")
+    };
+    let mut attrs = String::new();
+    match name {
+        "iframe" => {
+            let Some(src) = https("src") else {
+                return;
+            };
+            attrs.push_str(&format!(r#" src="{}""#, escape(src.as_str())));
+            for attr in IFRAME_ATTRS {
+                if let Some(v) = value.attr(attr) {
+                    attrs.push_str(&format!(r#" {}="{}""#, attr, escape(v)));
+                }
+            }
+            attrs.push_str(&format!(r#" sandbox="{}""#, IFRAME_SANDBOX));
+        }
+        "img" => {
+            let Some(src) = https("src") else {
+                return;
+            };
+            attrs.push_str(&format!(
+                r#" src="{}" alt="{}""#,
+                escape(src.as_str()),
+                escape(value.attr("alt").unwrap_or_default())
+            ));
+        }
+        "a" => {
+            let href = value
+                .attr("href")
+                .and_then(|href| Url::parse(href.trim()).ok())
+                .filter(|url| matches!(url.scheme(), "http" | "https"));
+            if let Some(href) = href {
+                attrs.push_str(&format!(
+                    r#" href="{}" rel="noopener nofollow""#,
+                    escape(href.as_str())
+                ));
+            }
+        }
+        _ => {}
+    }
+
+    html.push_str(&format!("<{}{}>", name, attrs));
+    if !matches!(name, "br" | "img") {
+        sanitize_children(element, html);
+        html.push_str(&format!("</{}>", name));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_oembed() {
+        let oembed = Oembed::parse(
+            br#"{
+                "type": "video",
+                "version": "1.0",
+                "title": "Shaping a boule",
+                "author_name": "The Crumb Journal",
+                "provider_name": "YouTube",
+                "thumbnail_url": "https://i.ytimg.com/vi/abc/hqdefault.jpg",
+                "width": "200",
+                "height": 113,
+                "html": "<iframe width=\"200\" src=\"https://www.youtube.com/embed/abc\" onload=\"steal()\" sandbox=\"allow-scripts allow-same-origin\" allowfullscreen></iframe><script src=\"https://evil.example/x.js\"></script>"
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(oembed.kind, "video");
+        assert_eq!(oembed.author_name.as_deref(), Some("The Crumb Journal"));
+        assert_eq!((oembed.width, oembed.height), (Some(200), Some(113)));
+        assert_eq!(
+            oembed.html.as_deref(),
+            Some(
+                r#"<iframe src="https://www.youtube.com/embed/abc" width="200" allowfullscreen="" sandbox="allow-scripts allow-popups allow-presentation"></iframe>"#
+            )
+        );
+        assert_eq!(Oembed::parse(b"{}"), None);
+    }
+
+    #[test]
+    fn sanitize_embed_html() {
+        assert_eq!(
+            sanitize(
+                r#"<blockquote class="tweet" onclick="x()"><p>Hi <a href="javascript:x()">@a</a> <a href="https://x.com/a/status/1">link</a></p><iframe src="http://example.com/"></iframe></blockquote><script>x()</script>"#
+            ),
+            r#"<blockquote><p>Hi <a>@a</a> <a href="https://x.com/a/status/1" rel="noopener nofollow">link</a></p></blockquote>"#
+        );
+    }
+
+    #[test]
+    fn discover_and_providers() {
+        let document = Html::parse_document(
+            r#"<link rel="alternate" type="application/json+oembed" href="/oembed?url=a">"#,
+        );
+        let base = Url::parse("https://example.com/watch").unwrap();
+        assert_eq!(
+            discover(&document, &base).map(String::from),
+            Some("https://example.com/oembed?url=a".to_string())
+        );
+
+        let providers = Providers::default();
+        assert_eq!(
+            providers
+                .endpoint("https://www.youtube.com/watch?v=abc")
+                .map(String::from),
+            Some(
+                "https://www.youtube.com/oembed?url=https%3A%2F%2Fwww.youtube.com%2Fwatch%3Fv%3Dabc&format=json"
+                    .to_string()
+            )
+        );
+        assert_eq!(providers.endpoint("https://example.com/watch?v=abc"), None);
+
+        let providers = Providers::from_json(
+            r#"[{"provider_name": "Wiki", "endpoints": [{"schemes": ["https://wiki.example/*"], "url": "https://wiki.example/oembed.{format}"}]}]"#,
+        )
+        .unwrap();
+        assert_eq!(
+            providers.endpoint("https://wiki.example/Page").map(String::from),
+            Some("https://wiki.example/oembed.json?url=https%3A%2F%2Fwiki.example%2FPage&format=json".to_string())
+        );
+    }
+}
@@ -0,0 +1,221 @@
+use super::metatag::{FileInfo, Metatag};
+use crate::link::Fetched;
+use regex::bytes::Regex;
+use std::sync::OnceLock;
+
+fn info_reference_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| Regex::new(r"/Info\s*(\d+)\s+(\d+)\s+R").unwrap())
+}
+
+fn xmp_pattern(property: &str) -> Regex {
+    Regex::new(&format!(
+        r"(?s)<dc:{0}>.*?<rdf:li[^>]*>(.*?)</rdf:li>.*?</dc:{0}>",
+        property
+    ))
+    .unwrap()
+}
+
+/// Title and author of a PDF, from its document information dictionary or
+/// else its XMP metadata.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct PdfInfo {
+    pub title: Option<String>,
+    pub author: Option<String>,
+}
+
+pub fn pdf_info(body: &[u8]) -> Option<PdfInfo> {
+    if !body.starts_with(b"%PDF-") {
+        return None;
+    }
+    // Strings of encrypted documents cannot be read without decrypting
+    let info = match find(body, b"/Encrypt").is_some() {
+        true => None,
+        false => info_dictionary(body),
+    };
+    let entry = |key: &[u8]| info.and_then(|info| dictionary_string(info, key));
+    let xmp = |property: &str| {
+        xmp_pattern(property)
+            .captures(body)
+            .and_then(|captures| non_empty(unescape_xml(&String::from_utf8_lossy(&captures[1]))))
+    };
+    Some(PdfInfo {
+        title: entry(b"/Title").or_else(|| xmp("title")),
+        author: entry(b"/Author").or_else(|| xmp("creator")),
+    })
+}
+
+pub fn pdf_metatag(fetched: &Fetched) -> Metatag {
+    let file = FileInfo::new(fetched);
+    let info = pdf_info(&fetched.body).unwrap_or_default();
+    Metatag {
+        title: info.title.or_else(|| file.file_name.clone()),
+        author: info.author,
+        file: Some(file),
+        ..Default::default()
+    }
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+/// The object the last trailer refers to as `/Info`, updates of a document
+/// being appended to it.
+fn info_dictionary(body: &[u8]) -> Option<&[u8]> {
+    let captures = info_reference_pattern().captures_iter(body).last()?;
+    let object = format!(
+        r"(?:^|[^0-9]){}\s+{}\s+obj",
+        String::from_utf8_lossy(&captures[1]),
+        String::from_utf8_lossy(&captures[2])
+    );
+    let start = Regex::new(&object).unwrap().find(body)?.end();
+    let end = find(&body[start..], b"endobj").map_or(body.len(), |end| start + end);
+    Some(&body[start..end])
+}
+
+/// The string value of `key` in `dictionary`.
+fn dictionary_string(dictionary: &[u8], key: &[u8]) -> Option<String> {
+    let mut rest = &dictionary[find(dictionary, key)? + key.len()..];
+    // Skips longer keys starting with `key`
+    if rest.first().is_some_and(|b| b.is_ascii_alphanumeric()) {
+        return dictionary_string(rest, key);
+    }
+    rest = rest.trim_ascii_start();
+    let bytes = match rest.first()? {
+        b'(' => literal_string(&rest[1..])?,
+        b'<' if rest.get(1) != Some(&b'<') => hex_string(&rest[1..])?,
+        _ => return None,
+    };
+    non_empty(text_string(&bytes))
+}
+
+fn literal_string(bytes: &[u8]) -> Option<Vec<u8>> {
+    let mut string = Vec::new();
+    let mut depth = 0;
+    let mut bytes = bytes.iter().copied().peekable();
+    while let Some(b) = bytes.next() {
+        match b {
+            b'\\' => match bytes.next()? {
+                b'n' => string.push(b'\n'),
+                b'r' => string.push(b'\r'),
+                b't' => string.push(b'\t'),
+                b'b' => string.push(0x08),
+                b'f' => string.push(0x0c),
+                digit @ b'0'..=b'7' => {
+                    let mut code = u32::from(digit - b'0');
+                    for _ in 0..2 {
+                        match bytes.peek() {
+                            Some(digit @ b'0'..=b'7') => {
+                                code = code * 8 + u32::from(digit - b'0');
+                                bytes.next();
+                            }
+                            _ => break,
+                        }
+                    }
+                    string.push(code as u8);
+                }
+                // A backslash at the end of a line continues the string
+                b'\r' => {
+                    bytes.next_if_eq(&b'\n');
+                }
+                b'\n' => {}
+                escaped => string.push(escaped),
+            },
+            b'(' => {
+                depth += 1;
+                string.push(b);
+            }
+            b')' if depth == 0 => return Some(string),
+            b')' => {
+                depth -= 1;
+                string.push(b);
+            }
+            b => string.push(b),
+        }
+    }
+    None
+}
+
+fn hex_string(bytes: &[u8]) -> Option<Vec<u8>> {
+    let end = bytes.iter().position(|&b| b == b'>')?;
+    let mut digits = bytes[..end]
+        .iter()
+        .filter_map(|&b| (b as char).to_digit(16))
+        .map(|digit| digit as u8)
+        .collect::<Vec<_>>();
+    // A missing last digit is taken as 0
+    if digits.len() % 2 == 1 {
+        digits.push(0);
+    }
+    Some(
+        digits
+            .chunks(2)
+            .map(|pair| pair[0] << 4 | pair[1])
+            .collect(),
+    )
+}
+
+/// Text strings are UTF-16 with a BOM, UTF-8 with one in PDF 2.0, or else
+/// PDFDocEncoding, which is close enough to Latin-1.
+fn text_string(bytes: &[u8]) -> String {
+    if let Some(utf16) = bytes.strip_prefix(&[0xfe, 0xff]) {
+        encoding_rs::UTF_16BE
+            .decode_without_bom_handling(utf16)
+            .0
+            .into_owned()
+    } else if let Some(utf8) = bytes.strip_prefix(&[0xef, 0xbb, 0xbf]) {
+        String::from_utf8_lossy(utf8).into_owned()
+    } else {
+        bytes.iter().map(|&b| b as char).collect()
+    }
+}
+
+fn unescape_xml(s: &str) -> String {
+    s.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+fn non_empty(s: String) -> Option<String> {
+    Some(s.trim().to_string()).filter(|s| !s.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn document_information() {
+        let pdf = include_bytes!("../../fixtures/file/paper.pdf");
+        assert_eq!(
+            pdf_info(pdf),
+            Some(PdfInfo {
+                title: Some("Crumb structure (at altitude)".to_string()),
+                author: Some("Ana Lima".to_string()),
+            })
+        );
+        assert_eq!(pdf_info(b"<html>"), None);
+    }
+
+    #[test]
+    fn xmp_metadata() {
+        let pdf = b"%PDF-1.7\n1 0 obj <</Type/Metadata/Subtype/XML>> stream\n\
+            <x:xmpmeta><rdf:RDF><rdf:Description>\
+            <dc:title><rdf:Alt><rdf:li xml:lang=\"x-default\">Bread &amp; Butter</rdf:li></rdf:Alt></dc:title>\
+            <dc:creator><rdf:Seq><rdf:li>Ben Ode</rdf:li></rdf:Seq></dc:creator>\
+            </rdf:Description></rdf:RDF></x:xmpmeta>\nendstream endobj\n\
+            trailer <</Size 2 /Encrypt 3 0 R /Info 2 0 R>>";
+        assert_eq!(
+            pdf_info(pdf),
+            Some(PdfInfo {
+                title: Some("Bread & Butter".to_string()),
+                author: Some("Ben Ode".to_string()),
+            })
+        );
+    }
+}
@@ -0,0 +1,531 @@
+use crate::link::resolve;
+use ego_tree::NodeId;
+use regex::Regex;
+use scraper::{ElementRef, Html, Node, Selector};
+use std::{collections::HashMap, sync::OnceLock};
+use url::Url;
+
+/// Pages with less text than this in their main content are not articles.
+pub const MIN_ARTICLE_LENGTH: usize = 250;
+/// Paragraphs shorter than this are not scored.
+const MIN_PARAGRAPH_LENGTH: usize = 25;
+
+/// Never part of an article, skipped with whatever they contain.
+const SKIPPED_TAGS: &[&str] = &[
+    "aside", "button", "canvas", "dialog", "embed", "footer", "form", "header", "iframe", "input",
+    "nav", "noscript", "object", "script", "select", "style", "svg", "template", "textarea",
+];
+/// Kept in the sanitized HTML, other elements are replaced by their content.
+const ALLOWED_TAGS: &[&str] = &[
+    "a",
+    "b",
+    "blockquote",
+    "br",
+    "caption",
+    "code",
+    "dd",
+    "dl",
+    "dt",
+    "em",
+    "figcaption",
+    "figure",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "hr",
+    "i",
+    "img",
+    "li",
+    "mark",
+    "ol",
+    "p",
+    "pre",
+    "s",
+    "small",
+    "strong",
+    "sub",
+    "sup",
+    "table",
+    "tbody",
+    "td",
+    "tfoot",
+    "th",
+    "thead",
+    "tr",
+    "u",
+    "ul",
+];
+const VOID_TAGS: &[&str] = &["br", "hr", "img"];
+/// Start a new paragraph of the plain text.
+const BLOCK_TAGS: &[&str] = &[
+    "article",
+    "blockquote",
+    "br",
+    "dd",
+    "div",
+    "dl",
+    "dt",
+    "figcaption",
+    "figure",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "hr",
+    "li",
+    "main",
+    "ol",
+    "p",
+    "pre",
+    "section",
+    "table",
+    "tr",
+    "ul",
+];
+
+fn unlikely_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| {
+        Regex::new(
+            r"(?i)\bads?\b|advert|banner|breadcrumb|comment|cookie|disqus|footer|masthead|menu|modal|navbar|newsletter|pagination|popup|promo|related|share|sidebar|social|sponsor|subscribe|widget",
+        )
+        .unwrap()
+    })
+}
+
+fn positive_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN
+        .get_or_init(|| Regex::new(r"(?i)article|body|content|entry|main|post|story|text").unwrap())
+}
+
+/// The main content of a page.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Readable {
+    pub text: String,
+    /// Only plain markup is kept, with absolute links and image sources
+    pub html: String,
+    pub word_count: usize,
+}
+
+fn class_and_id(element: ElementRef) -> String {
+    let element = element.value();
+    format!(
+        "{} {}",
+        element.attr("class").unwrap_or_default(),
+        element.id().unwrap_or_default()
+    )
+}
+
+/// Navigation, ads and other boilerplate.
+fn is_skipped(element: ElementRef) -> bool {
+    let value = element.value();
+    if SKIPPED_TAGS.contains(&value.name())
+        || value.attr("hidden").is_some()
+        || value.attr("aria-hidden") == Some("true")
+        || value
+            .attr("style")
+            .is_some_and(|style| style.replace(' ', "").contains("display:none"))
+    {
+        return true;
+    }
+    let names = class_and_id(element);
+    !matches!(value.name(), "body" | "article" | "main")
+        && unlikely_pattern().is_match(&names)
+        && !positive_pattern().is_match(&names)
+}
+
+/// Text of `element` outside of skipped elements, with whitespace collapsed.
+fn inner_text(element: ElementRef) -> String {
+    fn collect(element: ElementRef, text: &mut String) {
+        for child in element.children() {
+            match child.value() {
+                Node::Text(t) => text.push_str(t),
+                Node::Element(_) => {
+                    let child = ElementRef::wrap(child).unwrap();
+                    if !is_skipped(child) {
+                        collect(child, text);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+    let mut text = String::new();
+    collect(element, &mut text);
+    collapse_whitespace(text.trim())
+}
+
+/// Share of the text of `element` which is link text.
+fn link_density(element: ElementRef) -> f64 {
+    let length = inner_text(element).len();
+    if length == 0 {
+        return 0.0;
+    }
+    let link_length: usize = element
+        .select(&Selector::parse("a").unwrap())
+        .map(|a| inner_text(a).len())
+        .sum();
+    link_length as f64 / length as f64
+}
+
+fn has_block_children(element: ElementRef) -> bool {
+    element
+        .children()
+        .filter_map(ElementRef::wrap)
+        .any(|child| BLOCK_TAGS.contains(&child.value().name()) || child.value().name() == "img")
+}
+
+/// Whether `element` holds the text of a paragraph, which `div`s used for
+/// one do too.
+fn is_paragraph(element: ElementRef) -> bool {
+    match element.value().name() {
+        "p" | "pre" | "td" | "blockquote" => true,
+        "div" => !has_block_children(element),
+        _ => false,
+    }
+}
+
+fn initial_score(element: ElementRef) -> f64 {
+    let tag_score = match element.value().name() {
+        "article" => 10.0,
+        "div" | "main" | "section" => 5.0,
+        "pre" | "td" | "blockquote" => 3.0,
+        "address" | "ol" | "ul" | "dl" | "dd" | "dt" | "li" => -3.0,
+        "h1" | "h2" | "h3" | "h4" | "h5" | "h6" | "th" => -5.0,
+        _ => 0.0,
+    };
+    let names = class_and_id(element);
+    let class_score = match (
+        positive_pattern().is_match(&names),
+        unlikely_pattern().is_match(&names),
+    ) {
+        (true, false) => 25.0,
+        (false, true) => -25.0,
+        _ => 0.0,
+    };
+    tag_score + class_score
+}
+
+/// Scores the ancestors of every paragraph by how much text it has, the
+/// parent getting the full score and the grandparent half of it.
+fn score_paragraphs(element: ElementRef, scores: &mut HashMap<NodeId, f64>) {
+    for child in element.children().filter_map(ElementRef::wrap) {
+        if is_skipped(child) {
+            continue;
+        }
+        if is_paragraph(child) {
+            let text = inner_text(child);
+            if text.len() >= MIN_PARAGRAPH_LENGTH {
+                let score =
+                    1.0 + text.matches(',').count() as f64 + (text.len() / 100).min(3) as f64;
+                let ancestors = child.ancestors().filter_map(ElementRef::wrap).take(2);
+                for (ancestor, share) in ancestors.zip([1.0, 0.5]) {
+                    *scores
+                        .entry(ancestor.id())
+                        .or_insert_with(|| initial_score(ancestor)) += score * share;
+                }
+            }
+        }
+        score_paragraphs(child, scores);
+    }
+}
+
+/// Appends `element` to the sanitized HTML and the plain text.
+fn render(element: ElementRef, base: &Url, in_pre: bool, html: &mut String, text: &mut String) {
+    fn escape(s: &str) -> String {
+        s.replace('&', "&amp;")
+            .replace('<', "&lt;")
+            .replace('>', "&gt;")
+            .replace('"', "&quot;")
+    }
+    fn break_text(text: &mut String) {
+        let trimmed = text.trim_end_matches(' ').len();
+        text.truncate(trimmed);
+        if !text.is_empty() && !text.ends_with("\n\n") {
+            text.push_str(if text.ends_with('\n') { "\n" } else { "\n\n" });
+        }
+    }
+
+    let value = element.value();
+    let name = value.name();
+    let is_block = BLOCK_TAGS.contains(&name);
+    let in_pre = in_pre || name == "pre";
+    let tag = match name {
+        // A `div` standing for a paragraph keeps being one
+        "div" if !has_block_children(element) => Some("p"),
+        name if ALLOWED_TAGS.contains(&name) => Some(name),
+        _ => None,
+    };
+    let attrs = match name {
+        "a" => value
+            .attr("href")
+            .and_then(|href| resolve(base, href))
+            .map(|url| format!(r#" href="{}""#, escape(url.as_str())))
+            .unwrap_or_default(),
+        "img" => {
+            let src = ["src", "data-src"]
+                .iter()
+                .filter_map(|attr| value.attr(attr))
+                .find_map(|src| resolve(base, src));
+            let Some(src) = src else {
+                return;
+            };
+            format!(
+                r#" src="{}" alt="{}""#,
+                escape(src.as_str()),
+                escape(value.attr("alt").unwrap_or_default())
+            )
+        }
+        "td" | "th" => ["colspan", "rowspan"]
+            .iter()
+            .filter_map(|attr| {
+                value
+                    .attr(attr)
+                    .map(|v| format!(r#" {}="{}""#, attr, escape(v)))
+            })
+            .collect(),
+        _ => String::new(),
+    };
+
+    if is_block {
+        break_text(text);
+    }
+    if let Some(tag) = tag {
+        html.push_str(&format!("<{}{}>", tag, attrs));
+    }
+    if !VOID_TAGS.contains(&name) {
+        for child in element.children() {
+            match child.value() {
+                Node::Text(t) if !in_pre && t.trim().is_empty() && is_block_boundary(child) => {}
+                Node::Text(t) => {
+                    let t = match in_pre {
+                        true => t.to_string(),
+                        false => collapse_whitespace(t),
+                    };
+                    html.push_str(&escape(&t));
+                    // No spaces at the start of paragraphs or after others
+                    text.push_str(match text.is_empty() || text.ends_with(['\n', ' ']) {
+                        true => t.trim_start_matches(' '),
+                        false => &t,
+                    });
+                }
+                Node::Element(_) => {
+                    let child = ElementRef::wrap(child).unwrap();
+                    if !is_skipped(child) && !is_link_list(child) {
+                        render(child, base, in_pre, html, text);
+                    }
+                }
+                _ => {}
+            }
+        }
+        if let Some(tag) = tag {
+            html.push_str(&format!("</{}>", tag));
+        }
+    }
+    if is_block {
+        break_text(text);
+    }
+}
+
+/// Whether the text `node` is between blocks, where whitespace is not
+/// rendered.
+fn is_block_boundary(node: ego_tree::NodeRef<Node>) -> bool {
+    let is_block = |sibling: Option<ego_tree::NodeRef<Node>>| {
+        sibling.is_none_or(|sibling| {
+            sibling
+                .value()
+                .as_element()
+                .is_some_and(|e| BLOCK_TAGS.contains(&e.name()) || e.name() == "img")
+        })
+    };
+    is_block(node.prev_sibling()) || is_block(node.next_sibling())
+}
+
+/// Lists of links within the content, e.g. of related articles.
+fn is_link_list(element: ElementRef) -> bool {
+    matches!(
+        element.value().name(),
+        "div" | "section" | "ul" | "ol" | "table"
+    ) && link_density(element) > 0.5
+}
+
+fn collapse_whitespace(s: &str) -> String {
+    let mut collapsed = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c.is_whitespace() {
+            true if collapsed.ends_with(' ') => {}
+            true => collapsed.push(' '),
+            false => collapsed.push(c),
+        }
+    }
+    collapsed
+}
+
+/// Finds the main content of `document` the way readability does: paragraphs
+/// score their ancestors, the best scored element is taken along with its
+/// siblings which look like part of the content.
+pub fn readable(document: &Html, base: &Url) -> Option<Readable> {
+    let body = document.select(&Selector::parse("body").unwrap()).next()?;
+    let mut scores = HashMap::new();
+    score_paragraphs(body, &mut scores);
+
+    let scored = |id: NodeId| {
+        let element = ElementRef::wrap(document.tree.get(id)?)?;
+        let score = scores.get(&id)? * (1.0 - link_density(element));
+        Some((element, score))
+    };
+    let (top, top_score) = scores
+        .keys()
+        .filter_map(|id| scored(*id))
+        .max_by(|a, b| a.1.total_cmp(&b.1))?;
+
+    let threshold = (top_score * 0.2).max(10.0);
+    let contents = match top.parent().and_then(ElementRef::wrap) {
+        Some(parent) => parent
+            .children()
+            .filter_map(ElementRef::wrap)
+            .filter(|sibling| {
+                if sibling.id() == top.id() {
+                    return true;
+                }
+                if is_skipped(*sibling) {
+                    return false;
+                }
+                if scored(sibling.id()).is_some_and(|(_, score)| score >= threshold) {
+                    return true;
+                }
+                let length = inner_text(*sibling).len();
+                sibling.value().name() == "p"
+                    && length >= MIN_PARAGRAPH_LENGTH
+                    && link_density(*sibling) < 0.25
+            })
+            .collect::<Vec<_>>(),
+        None => vec![top],
+    };
+
+    let mut html = String::new();
+    let mut text = String::new();
+    for element in contents {
+        render(element, base, false, &mut html, &mut text);
+    }
+    let html = html.trim().to_string();
+    let text = text.trim().to_string();
+    if text.len() < MIN_ARTICLE_LENGTH {
+        return None;
+    }
+    Some(Readable {
+        word_count: text.split_whitespace().count(),
+        text,
+        html,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn readable_fixture(html: &str, url: &str) -> Option<Readable> {
+        let document = Html::parse_document(html);
+        readable(&document, &Url::parse(url).unwrap())
+    }
+
+    #[test]
+    fn blog() {
+        let readable = readable_fixture(
+            include_str!("../../fixtures/readable/blog.html"),
+            "https://crumb.example.com/posts/altitude",
+        )
+        .unwrap();
+
+        assert!(readable
+            .text
+            .starts_with("Sourdough at high altitude\n\nBy Ana"));
+        assert!(readable
+            .text
+            .contains("\n\nHydration\n\nDry mountain air pulls moisture"));
+        assert!(readable.text.contains("flour   500 g\nwater   390 g"));
+        assert!(readable.text.ends_with("for more."));
+        for boilerplate in [
+            "Recipes",
+            "Popular posts",
+            "premium flour",
+            "Share on",
+            "Comments",
+            "©",
+            "trackPageView",
+        ] {
+            assert!(
+                !readable.text.contains(boilerplate),
+                "{} was kept",
+                boilerplate
+            );
+            assert!(
+                !readable.html.contains(boilerplate),
+                "{} was kept",
+                boilerplate
+            );
+        }
+
+        assert!(readable
+            .html
+            .starts_with("<h1>Sourdough at high altitude</h1>"));
+        assert!(readable.html.contains(
+            r#"<a href="https://crumb.example.com/posts/dutch-oven">our guide to baking in a Dutch oven</a>"#
+        ));
+        assert!(readable.html.contains(
+            r#"<img src="https://crumb.example.com/images/loaf.jpg" alt="A dark, blistered loaf">"#
+        ));
+        assert!(!readable.html.contains("class="));
+        assert_eq!(
+            readable.word_count,
+            readable.text.split_whitespace().count()
+        );
+    }
+
+    #[test]
+    fn news() {
+        let readable = readable_fixture(
+            include_str!("../../fixtures/readable/news.html"),
+            "https://news.example.com/local/cycling",
+        )
+        .unwrap();
+
+        assert!(readable
+            .text
+            .starts_with("The city council on Tuesday approved"));
+        assert!(readable.text.contains("trade on the main shopping streets"));
+        assert!(readable
+            .html
+            .contains(r#"<a href="https://news.example.com/local/plan.pdf">full plan</a>"#));
+        for boilerplate in [
+            "Subscribe",
+            "cookies",
+            "Related",
+            "Bus lanes",
+            "morning briefing",
+        ] {
+            assert!(
+                !readable.text.contains(boilerplate),
+                "{} was kept",
+                boilerplate
+            );
+        }
+    }
+
+    #[test]
+    fn not_an_article() {
+        assert_eq!(
+            readable_fixture(
+                include_str!("../../fixtures/readable/listing.html"),
+                "https://links.example.com/archive",
+            ),
+            None
+        );
+    }
+}
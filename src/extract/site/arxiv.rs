@@ -0,0 +1,96 @@
+use super::{select_text, SiteData, SiteExtractor};
+use crate::extract::metatag::select_meta;
+use regex::Regex;
+use scraper::Html;
+use std::sync::OnceLock;
+use url::Url;
+
+fn subject_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| Regex::new(r"\(([a-z-]+(?:\.[A-Za-z-]+)?)\)").unwrap())
+}
+
+/// Papers on arXiv, with their authors, abstract and subjects.
+pub struct Arxiv;
+
+impl SiteExtractor for Arxiv {
+    fn name(&self) -> &str {
+        "arxiv"
+    }
+
+    fn hosts(&self) -> &[&str] {
+        &["arxiv.org", "*.arxiv.org"]
+    }
+
+    fn extract(&self, document: &Html, _url: &Url) -> Option<SiteData> {
+        let meta = |name: &str| select_meta(document, name);
+        let arxiv_id = meta("citation_arxiv_id").into_iter().next()?;
+        // Authors are listed as `Last, First`
+        let authors = meta("citation_author")
+            .into_iter()
+            .map(|author| match author.split_once(',') {
+                Some((last, first)) => format!("{} {}", first.trim(), last.trim()),
+                None => author,
+            })
+            .collect::<Vec<_>>();
+        // The primary subject comes first
+        let subjects = select_text(document, ".subjects")
+            .map(|subjects| {
+                subject_pattern()
+                    .captures_iter(&subjects)
+                    .map(|captures| captures[1].to_string())
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+
+        let mut data = SiteData::default();
+        data.set("arxiv_id", arxiv_id);
+        data.set("authors", authors);
+        data.set("abstract", meta("citation_abstract").into_iter().next());
+        data.set(
+            "published",
+            meta("citation_date")
+                .into_iter()
+                .next()
+                .map(|date| date.replace('/', "-")),
+        );
+        data.set("pdf_url", meta("citation_pdf_url").into_iter().next());
+        data.set("subjects", subjects.clone());
+        for subject in subjects {
+            data.suggest_tag("arxiv", &subject);
+        }
+        Some(data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn arxiv_paper() {
+        let document = Html::parse_document(include_str!("../../../fixtures/site/arxiv.html"));
+        let url = Url::parse("https://arxiv.org/abs/2403.01234v2").unwrap();
+        let data = Arxiv.extract(&document, &url).unwrap();
+        assert_eq!(
+            serde_json::Value::Object(data.fields),
+            json!({
+                "arxiv_id": "2403.01234",
+                "authors": ["Ana Lima", "Ben Ode", "Carla van der Berg"],
+                "abstract": "We measure how the crumb of sourdough bread changes with the air pressure it rises in. Loaves proofed at 2,400 m show larger, more irregular alveoli.",
+                "published": "2024-03-02",
+                "pdf_url": "http://arxiv.org/pdf/2403.01234",
+                "subjects": ["physics.food-ph", "cond-mat.soft", "cs.CV"],
+            })
+        );
+        assert_eq!(
+            data.tag_paths,
+            [
+                "/arxiv/physics.food-ph",
+                "/arxiv/cond-mat.soft",
+                "/arxiv/cs.cv"
+            ]
+        );
+    }
+}
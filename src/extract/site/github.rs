@@ -0,0 +1,81 @@
+use super::{count, select_attr, select_text, select_texts, SiteData, SiteExtractor};
+use crate::extract::metatag::select_meta;
+use scraper::Html;
+use url::Url;
+
+/// Repositories on GitHub, with their stars, forks, main language and
+/// topics.
+pub struct GitHub;
+
+impl SiteExtractor for GitHub {
+    fn name(&self) -> &str {
+        "github"
+    }
+
+    fn hosts(&self) -> &[&str] {
+        &["github.com", "www.github.com"]
+    }
+
+    fn extract(&self, document: &Html, _url: &Url) -> Option<SiteData> {
+        let repository = select_meta(document, "octolytics-dimension-repository_nwo")
+            .into_iter()
+            .next()?;
+        let language = select_text(document, r#"a[href*="/search?l="] .text-bold"#);
+        let topics = select_texts(document, "a.topic-tag");
+
+        let mut data = SiteData::default();
+        data.set("repository", repository);
+        data.set(
+            "stars",
+            select_attr(document, "#repo-stars-counter-star", "title").and_then(|s| count(&s)),
+        );
+        data.set(
+            "forks",
+            select_attr(document, "#repo-network-counter", "title").and_then(|s| count(&s)),
+        );
+        data.set("language", language.clone());
+        data.set("topics", topics.clone());
+        if let Some(language) = language {
+            data.suggest_tag("language", &language);
+        }
+        for topic in topics {
+            data.suggest_tag("topic", &topic);
+        }
+        Some(data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn github_repository() {
+        let document = Html::parse_document(include_str!("../../../fixtures/site/github.html"));
+        let url = Url::parse("https://github.com/crumbjournal/levain").unwrap();
+        let data = GitHub.extract(&document, &url).unwrap();
+        assert_eq!(
+            serde_json::Value::Object(data.fields),
+            json!({
+                "repository": "crumbjournal/levain",
+                "stars": 12873,
+                "forks": 1204,
+                "language": "Rust",
+                "topics": ["sourdough", "baking", "cli"],
+            })
+        );
+        assert_eq!(
+            data.tag_paths,
+            [
+                "/language/rust",
+                "/topic/sourdough",
+                "/topic/baking",
+                "/topic/cli"
+            ]
+        );
+
+        let document = Html::parse_document("<title>GitHub</title>");
+        assert_eq!(GitHub.extract(&document, &url), None);
+    }
+}
@@ -0,0 +1,204 @@
+mod arxiv;
+mod github;
+mod wikipedia;
+mod youtube;
+
+pub use arxiv::*;
+pub use github::*;
+pub use wikipedia::*;
+pub use youtube::*;
+
+use scraper::{ElementRef, Html, Selector};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use url::Url;
+
+/// Fields particular to the site of a page, e.g. the stars of a GitHub
+/// repository, which generic meta tags do not carry.
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+#[serde(default)]
+pub struct SiteData {
+    /// Name of the extractor that read them, e.g. `github`
+    pub site: String,
+    pub fields: Map<String, Value>,
+    /// Tags to suggest for bookmarks of the page, e.g. `/language/rust`
+    pub tag_paths: Vec<String>,
+}
+
+impl SiteData {
+    /// Sets field `name`, unless `value` is missing or empty.
+    pub fn set(&mut self, name: &str, value: impl Into<Value>) {
+        let value = value.into();
+        let is_empty = match &value {
+            Value::Null => true,
+            Value::String(s) => s.is_empty(),
+            Value::Array(values) => values.is_empty(),
+            _ => false,
+        };
+        if !is_empty {
+            self.fields.insert(name.to_string(), value);
+        }
+    }
+
+    /// Suggests the tag `/<prefix>/<name>`, `name` made a slug of.
+    pub fn suggest_tag(&mut self, prefix: &str, name: &str) {
+        let slug = slug(name);
+        if slug.is_empty() {
+            return;
+        }
+        let path = format!("/{}/{}", prefix, slug);
+        if !self.tag_paths.contains(&path) {
+            self.tag_paths.push(path);
+        }
+    }
+}
+
+/// Reads what is particular to the pages of a site.
+pub trait SiteExtractor: Send + Sync {
+    /// Name the data it reads is marked with, e.g. `github`
+    fn name(&self) -> &str;
+
+    /// Hosts of the site, `*.` matching any subdomain.
+    fn hosts(&self) -> &[&str];
+
+    /// `None` when `document` is not a page it knows, e.g. a search page.
+    fn extract(&self, document: &Html, url: &Url) -> Option<SiteData>;
+}
+
+/// Extractors tried on a page by its host, the first one matching it being
+/// used.
+pub struct SiteExtractors {
+    extractors: Vec<Box<dyn SiteExtractor>>,
+}
+
+impl Default for SiteExtractors {
+    fn default() -> Self {
+        Self::new(vec![
+            Box::new(GitHub),
+            Box::new(YouTube),
+            Box::new(Arxiv),
+            Box::new(Wikipedia),
+        ])
+    }
+}
+
+impl SiteExtractors {
+    pub fn new(extractors: Vec<Box<dyn SiteExtractor>>) -> Self {
+        Self { extractors }
+    }
+
+    /// Adds `extractor` ahead of the others, so it can replace one for the
+    /// same hosts.
+    pub fn with(mut self, extractor: impl SiteExtractor + 'static) -> Self {
+        self.extractors.insert(0, Box::new(extractor));
+        self
+    }
+
+    pub fn extractor(&self, url: &Url) -> Option<&dyn SiteExtractor> {
+        let host = url.host_str()?;
+        self.extractors
+            .iter()
+            .find(|extractor| {
+                extractor
+                    .hosts()
+                    .iter()
+                    .any(|pattern| host_matches(pattern, host))
+            })
+            .map(|extractor| extractor.as_ref())
+    }
+
+    pub fn extract(&self, document: &Html, url: &Url) -> Option<SiteData> {
+        let extractor = self.extractor(url)?;
+        extractor.extract(document, url).map(|data| SiteData {
+            site: extractor.name().to_string(),
+            ..data
+        })
+    }
+}
+
+fn host_matches(pattern: &str, host: &str) -> bool {
+    match pattern.strip_prefix("*.") {
+        Some(domain) => host
+            .strip_suffix(domain)
+            .is_some_and(|subdomain| subdomain.ends_with('.')),
+        None => host == pattern,
+    }
+}
+
+/// Lowercased words of `name` joined by `-`, keeping the `.` of e.g. `cs.CL`
+/// and the `+` of `C++`.
+fn slug(name: &str) -> String {
+    name.replace('#', "sharp")
+        .to_lowercase()
+        .split(|c: char| !(c.is_alphanumeric() || matches!(c, '.' | '+')))
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+/// Text of `element` with its whitespace collapsed.
+fn text(element: ElementRef) -> String {
+    element
+        .text()
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn select_texts(document: &Html, selector: &str) -> Vec<String> {
+    document
+        .select(&Selector::parse(selector).unwrap())
+        .map(text)
+        .filter(|text| !text.is_empty())
+        .collect()
+}
+
+fn select_text(document: &Html, selector: &str) -> Option<String> {
+    select_texts(document, selector).into_iter().next()
+}
+
+fn select_attr(document: &Html, selector: &str, attr: &str) -> Option<String> {
+    document
+        .select(&Selector::parse(selector).unwrap())
+        .filter_map(|e| e.value().attr(attr))
+        .find_map(super::metatag::non_empty)
+}
+
+/// A count as shown by e.g. `title="12,345"`.
+fn count(s: &str) -> Option<u64> {
+    s.replace(',', "").trim().parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn select_by_host() {
+        let extractors = SiteExtractors::default();
+        let name = |url: &str| {
+            extractors
+                .extractor(&Url::parse(url).unwrap())
+                .map(|extractor| extractor.name().to_string())
+        };
+        assert_eq!(
+            name("https://github.com/rust-lang/rust").as_deref(),
+            Some("github")
+        );
+        assert_eq!(
+            name("https://m.youtube.com/watch?v=a").as_deref(),
+            Some("youtube")
+        );
+        assert_eq!(
+            name("https://de.wikipedia.org/wiki/Brot").as_deref(),
+            Some("wikipedia")
+        );
+        assert_eq!(name("https://notgithub.com/a/b"), None);
+        assert_eq!(name("https://wikipedia.org.example.com/"), None);
+
+        assert_eq!(slug("Howto & Style"), "howto-style");
+        assert_eq!(slug("C#"), "csharp");
+        assert_eq!(slug("cs.CL"), "cs.cl");
+    }
+}
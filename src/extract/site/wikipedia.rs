@@ -0,0 +1,110 @@
+use super::{select_attr, select_text, select_texts, SiteData, SiteExtractor};
+use scraper::{ElementRef, Html, Node, Selector};
+use url::Url;
+
+/// Articles on Wikipedia, with their short description, lead sentence and
+/// categories.
+pub struct Wikipedia;
+
+impl Wikipedia {
+    /// Text of the first paragraph of the article, without the references
+    /// marked in it.
+    fn summary(document: &Html) -> Option<String> {
+        let is_reference = |element: ElementRef| {
+            element.value().name() == "sup" && element.value().classes().any(|c| c == "reference")
+        };
+        document
+            .select(&Selector::parse(".mw-parser-output > p:not(.mw-empty-elt)").unwrap())
+            .map(|paragraph| {
+                paragraph
+                    .descendants()
+                    .filter(|node| {
+                        !node
+                            .ancestors()
+                            .filter_map(ElementRef::wrap)
+                            .any(is_reference)
+                    })
+                    .filter_map(|node| match node.value() {
+                        Node::Text(text) => Some(&**text),
+                        _ => None,
+                    })
+                    .collect::<String>()
+                    .split_whitespace()
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            })
+            .find(|summary| !summary.is_empty())
+    }
+}
+
+impl SiteExtractor for Wikipedia {
+    fn name(&self) -> &str {
+        "wikipedia"
+    }
+
+    fn hosts(&self) -> &[&str] {
+        &["*.wikipedia.org"]
+    }
+
+    fn extract(&self, document: &Html, url: &Url) -> Option<SiteData> {
+        let title = select_text(document, "#firstHeading")?;
+        let categories = select_texts(document, "#mw-normal-catlinks li a");
+
+        let mut data = SiteData::default();
+        data.set("title", title);
+        // Editions are served from a subdomain per language
+        data.set(
+            "language",
+            url.host_str()
+                .and_then(|host| host.split('.').next())
+                .filter(|language| !matches!(*language, "www" | "m")),
+        );
+        data.set(
+            "short_description",
+            select_text(document, ".shortdescription"),
+        );
+        data.set("summary", Self::summary(document));
+        data.set(
+            "wikidata_id",
+            select_attr(document, "#t-wikibase a", "href")
+                .and_then(|href| href.rsplit('/').next().map(String::from)),
+        );
+        data.set("categories", categories.clone());
+        for category in categories {
+            data.suggest_tag("category", &category);
+        }
+        Some(data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn wikipedia_article() {
+        let document = Html::parse_document(include_str!("../../../fixtures/site/wikipedia.html"));
+        let url = Url::parse("https://en.wikipedia.org/wiki/Sourdough").unwrap();
+        let data = Wikipedia.extract(&document, &url).unwrap();
+        assert_eq!(
+            serde_json::Value::Object(data.fields),
+            json!({
+                "title": "Sourdough",
+                "language": "en",
+                "short_description": "Bread made by fermentation with wild yeast",
+                "summary": "Sourdough is a type of bread that uses the fermentation by naturally occurring yeast and lactobacillus bacteria to raise the dough. In addition to leavening the bread, the fermentation produces lactic acid, which gives the bread its sour taste.",
+                "wikidata_id": "Q1364",
+                "categories": ["Sourdough breads", "Ancient dishes", "Fermented foods"],
+            })
+        );
+        assert_eq!(
+            data.tag_paths,
+            [
+                "/category/sourdough-breads",
+                "/category/ancient-dishes",
+                "/category/fermented-foods"
+            ]
+        );
+    }
+}
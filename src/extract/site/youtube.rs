@@ -0,0 +1,107 @@
+use super::{count, select_attr, SiteData, SiteExtractor};
+use scraper::Html;
+use url::Url;
+
+/// Videos on YouTube, with their duration, channel and views.
+pub struct YouTube;
+
+impl YouTube {
+    /// The id of the video at `url`, e.g. `/watch?v=<id>` or `/shorts/<id>`.
+    fn video_id(url: &Url) -> Option<String> {
+        match url.path() {
+            "/watch" => url
+                .query_pairs()
+                .find(|(name, _)| name == "v")
+                .map(|(_, id)| id.into_owned()),
+            path => path
+                .strip_prefix("/shorts/")
+                .map(|id| id.trim_end_matches('/').to_string()),
+        }
+        .filter(|id| !id.is_empty())
+    }
+}
+
+impl SiteExtractor for YouTube {
+    fn name(&self) -> &str {
+        "youtube"
+    }
+
+    fn hosts(&self) -> &[&str] {
+        &["youtube.com", "*.youtube.com"]
+    }
+
+    fn extract(&self, document: &Html, url: &Url) -> Option<SiteData> {
+        let video = |prop: &str| {
+            select_attr(
+                document,
+                &format!(r#"[itemtype$="/VideoObject"] > [itemprop="{}"]"#, prop),
+                "content",
+            )
+        };
+        let video_id = Self::video_id(url).or_else(|| video("identifier"))?;
+        let channel = select_attr(
+            document,
+            r#"[itemprop="author"] [itemprop="name"]"#,
+            "content",
+        );
+        let genre = video("genre");
+
+        let mut data = SiteData::default();
+        data.set("video_id", video_id);
+        data.set("duration", video("duration"));
+        data.set("channel", channel.clone());
+        data.set(
+            "channel_url",
+            select_attr(document, r#"[itemprop="author"] [itemprop="url"]"#, "href"),
+        );
+        data.set("channel_id", video("channelId"));
+        data.set("views", video("interactionCount").and_then(|s| count(&s)));
+        data.set(
+            "published",
+            video("datePublished").or_else(|| video("uploadDate")),
+        );
+        data.set("genre", genre.clone());
+        if let Some(channel) = channel {
+            data.suggest_tag("channel", &channel);
+        }
+        if let Some(genre) = genre {
+            data.suggest_tag("genre", &genre);
+        }
+        Some(data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn youtube_video() {
+        let document = Html::parse_document(include_str!("../../../fixtures/site/youtube.html"));
+        let url = Url::parse("https://www.youtube.com/watch?v=k3Yb0uLe9Xz&t=42").unwrap();
+        let data = YouTube.extract(&document, &url).unwrap();
+        assert_eq!(
+            serde_json::Value::Object(data.fields),
+            json!({
+                "video_id": "k3Yb0uLe9Xz",
+                "duration": "PT12M41S",
+                "channel": "The Crumb Journal",
+                "channel_url": "http://www.youtube.com/@crumbjournal",
+                "channel_id": "UC4vQ2xXcrumbJ0urnal9aZw",
+                "views": 48210,
+                "published": "2024-03-02T09:00:00-08:00",
+                "genre": "Howto & Style",
+            })
+        );
+        assert_eq!(
+            data.tag_paths,
+            ["/channel/the-crumb-journal", "/genre/howto-style"]
+        );
+
+        let url = Url::parse("https://www.youtube.com/shorts/s0rtId").unwrap();
+        assert_eq!(YouTube::video_id(&url).as_deref(), Some("s0rtId"));
+        let url = Url::parse("https://www.youtube.com/results?search_query=bread").unwrap();
+        assert_eq!(YouTube.extract(&Html::parse_document(""), &url), None);
+    }
+}
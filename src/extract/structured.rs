@@ -0,0 +1,284 @@
+use crate::model::{Tag, TaggedItem};
+use scraper::{ElementRef, Html, Selector};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+/// Types that describe the site around the page rather than what it is
+/// about.
+const SUPPORTING_TYPES: [&str; 9] = [
+    "WebSite",
+    "WebPage",
+    "Organization",
+    "Person",
+    "BreadcrumbList",
+    "ImageObject",
+    "SearchAction",
+    "SiteNavigationElement",
+    "ListItem",
+];
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Price {
+    pub amount: String,
+    pub currency: Option<String>,
+}
+
+/// A summary of the main schema.org item of a page.
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
+pub struct StructuredData {
+    /// The schema.org type, e.g. `Recipe` or `ScholarlyArticle`
+    #[serde(rename = "type")]
+    pub kind: Option<String>,
+    pub headline: Option<String>,
+    pub description: Option<String>,
+    pub image_url: Option<String>,
+    pub author: Option<String>,
+    pub date_published: Option<String>,
+    /// An ISO 8601 duration, e.g. `PT1H30M`
+    pub duration: Option<String>,
+    pub price: Option<Price>,
+}
+
+impl StructuredData {
+    /// Typed tag values to prefill a bookmark with, e.g. `/type` of `Recipe`.
+    pub fn tag_values(&self) -> Vec<(Tag, TaggedItem)> {
+        let price = self.price.as_ref().map(|price| match &price.currency {
+            Some(currency) => format!("{} {}", price.amount, currency),
+            None => price.amount.clone(),
+        });
+        [
+            ("type", "text", self.kind.clone()),
+            ("author", "text", self.author.clone()),
+            ("published", "datetime", self.date_published.clone()),
+            ("duration", "duration", self.duration.clone()),
+            ("price", "money", price),
+        ]
+        .into_iter()
+        .filter_map(|(path, value_type, value)| {
+            let tag = Tag {
+                value_type: Some(value_type.to_string()),
+                ..Tag::from_path(path.to_string())
+            };
+            let item = TaggedItem {
+                value: Some(value?),
+                ..Default::default()
+            };
+            Some((tag, item))
+        })
+        .collect()
+    }
+}
+
+/// Summary of the main item of the JSON-LD scripts of `document`.
+pub fn json_ld(document: &Html) -> Option<StructuredData> {
+    let items = document
+        .select(&Selector::parse(r#"script[type="application/ld+json" i]"#).unwrap())
+        .filter_map(|script| serde_json::from_str::<Value>(&script.text().collect::<String>()).ok())
+        .flat_map(flatten)
+        .collect::<Vec<_>>();
+    summarize(&items)
+}
+
+/// Summary of the main top-level microdata item of `document`.
+pub fn microdata(document: &Html) -> Option<StructuredData> {
+    let items = document
+        .select(&Selector::parse("[itemscope]:not([itemprop])").unwrap())
+        .map(microdata_item)
+        .collect::<Vec<_>>();
+    summarize(&items)
+}
+
+/// The items of a JSON-LD document, which may be a list of them or keep them
+/// in a `@graph`.
+fn flatten(value: Value) -> Vec<Value> {
+    match value {
+        Value::Array(values) => values.into_iter().flat_map(flatten).collect(),
+        Value::Object(mut object) => match object.remove("@graph") {
+            Some(graph) => flatten(graph),
+            None => vec![Value::Object(object)],
+        },
+        _ => Vec::new(),
+    }
+}
+
+fn item_type(item: &Value) -> Option<&str> {
+    match item.get("@type")? {
+        Value::String(kind) => Some(kind),
+        Value::Array(kinds) => kinds.first()?.as_str(),
+        _ => None,
+    }
+}
+
+fn summarize(items: &[Value]) -> Option<StructuredData> {
+    let item = items
+        .iter()
+        .find(|item| item_type(item).is_some_and(|kind| !SUPPORTING_TYPES.contains(&kind)))
+        .or_else(|| items.iter().find(|item| item_type(item).is_some()))?;
+    let field = |names: &[&str]| names.iter().find_map(|name| text(item.get(name)?));
+
+    Some(StructuredData {
+        kind: item_type(item).map(String::from),
+        headline: field(&["headline", "name"]),
+        description: field(&["description"]),
+        image_url: item.get("image").and_then(url),
+        author: item.get("author").and_then(names),
+        date_published: field(&["datePublished", "uploadDate", "dateCreated"]),
+        duration: field(&["duration", "totalTime"]),
+        price: item.get("offers").and_then(price),
+    })
+}
+
+/// A string or number, or the first of a list of them.
+fn text(value: &Value) -> Option<String> {
+    let text = match value {
+        Value::String(s) => s.trim().to_string(),
+        Value::Number(n) => n.to_string(),
+        Value::Array(values) => return values.iter().find_map(text),
+        _ => return None,
+    };
+    Some(text).filter(|text| !text.is_empty())
+}
+
+fn url(value: &Value) -> Option<String> {
+    match value {
+        Value::Object(object) => object.get("url").and_then(url),
+        Value::Array(values) => values.iter().find_map(url),
+        value => text(value),
+    }
+}
+
+/// Names of the people in `value`, which may be names or `Person` items.
+fn names(value: &Value) -> Option<String> {
+    let names = match value {
+        Value::Array(values) => values.iter().filter_map(names).collect::<Vec<_>>(),
+        Value::Object(object) => return object.get("name").and_then(text),
+        value => return text(value),
+    };
+    Some(names.join(", ")).filter(|names| !names.is_empty())
+}
+
+fn price(offers: &Value) -> Option<Price> {
+    let offer = match offers {
+        Value::Array(offers) => return offers.iter().find_map(price),
+        Value::Object(offer) => offer,
+        _ => return None,
+    };
+    let amount = ["price", "lowPrice"]
+        .iter()
+        .find_map(|name| text(offer.get(*name)?))
+        .or_else(|| text(offer.get("priceSpecification")?.get("price")?))?;
+    let currency = offer
+        .get("priceCurrency")
+        .or_else(|| offer.get("priceSpecification")?.get("priceCurrency"))
+        .and_then(text);
+    Some(Price { amount, currency })
+}
+
+/// A microdata item as the JSON-LD item it would be.
+fn microdata_item(element: ElementRef) -> Value {
+    let mut item = Map::new();
+    if let Some(kind) = element
+        .value()
+        .attr("itemtype")
+        .and_then(|itemtype| itemtype.split_whitespace().next())
+        .and_then(|itemtype| itemtype.rsplit('/').next())
+    {
+        item.insert("@type".to_string(), kind.into());
+    }
+    microdata_properties(element, &mut item);
+    Value::Object(item)
+}
+
+/// Adds the properties below `element` to `item`, those of nested items
+/// belonging to them.
+fn microdata_properties(element: ElementRef, item: &mut Map<String, Value>) {
+    for child in element.children().filter_map(ElementRef::wrap) {
+        let is_item = child.value().attr("itemscope").is_some();
+        if let Some(names) = child.value().attr("itemprop") {
+            let value = match is_item {
+                true => microdata_item(child),
+                false => property_value(child).into(),
+            };
+            for name in names.split_whitespace() {
+                item.entry(name).or_insert_with(|| value.clone());
+            }
+        }
+        if !is_item {
+            microdata_properties(child, item);
+        }
+    }
+}
+
+fn property_value(element: ElementRef) -> String {
+    let attr = match element.value().name() {
+        "meta" => element.value().attr("content"),
+        "a" | "link" | "area" => element.value().attr("href"),
+        "img" | "audio" | "video" | "source" | "embed" | "iframe" => element.value().attr("src"),
+        "time" => element.value().attr("datetime"),
+        "data" | "meter" => element.value().attr("value"),
+        _ => None,
+    };
+    attr.map(String::from)
+        .unwrap_or_else(|| element.text().collect::<String>())
+        .trim()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recipe_json_ld() {
+        let document = Html::parse_document(include_str!("../../fixtures/structured/recipe.html"));
+        let recipe = json_ld(&document).unwrap();
+        assert_eq!(
+            recipe,
+            StructuredData {
+                kind: Some("Recipe".to_string()),
+                headline: Some("Mountain sourdough".to_string()),
+                description: Some("A loaf for thin air.".to_string()),
+                image_url: Some("https://example.com/loaf.jpg".to_string()),
+                author: Some("Ana Lima, Ben Ode".to_string()),
+                date_published: Some("2024-03-02".to_string()),
+                duration: Some("PT26H".to_string()),
+                price: None,
+            }
+        );
+        assert_eq!(
+            recipe
+                .tag_values()
+                .into_iter()
+                .map(|(tag, item)| (tag.path, tag.value_type.unwrap(), item.value.unwrap()))
+                .collect::<Vec<_>>(),
+            vec![
+                ("/type".into(), "text".into(), "Recipe".into()),
+                ("/author".into(), "text".into(), "Ana Lima, Ben Ode".into()),
+                ("/published".into(), "datetime".into(), "2024-03-02".into()),
+                ("/duration".into(), "duration".into(), "PT26H".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn product_microdata() {
+        let document = Html::parse_document(include_str!("../../fixtures/structured/product.html"));
+        assert_eq!(json_ld(&document), None);
+        assert_eq!(
+            microdata(&document),
+            Some(StructuredData {
+                kind: Some("Product".to_string()),
+                headline: Some("Field notebook".to_string()),
+                image_url: Some("/notebook.jpg".to_string()),
+                author: None,
+                description: Some("Ninety-six dotted pages.".to_string()),
+                date_published: None,
+                duration: None,
+                price: Some(Price {
+                    amount: "12.50".to_string(),
+                    currency: Some("EUR".to_string()),
+                }),
+            })
+        );
+    }
+}
@@ -1,22 +1,18 @@
 use argon2::{
-    password_hash::{rand_core::OsRng, PasswordHash, SaltString},
+    password_hash::{self, rand_core::OsRng, PasswordHash, SaltString},
     Argon2,
 };
 
-pub fn hash_password(password: String) -> Result<String, ()> {
+pub fn hash_password(password: String) -> password_hash::Result<String> {
     use argon2::password_hash::PasswordHasher;
     let salt = SaltString::generate(&mut OsRng);
     Argon2::default()
         .hash_password(password.as_bytes(), &salt)
-        .map_err(|_| ())
         .map(|hashed| hashed.to_string())
 }
 
-pub fn verify_password(password: String, hashed_password: String) -> Result<(), ()> {
+pub fn verify_password(password: String, hashed_password: String) -> password_hash::Result<()> {
     use argon2::password_hash::PasswordVerifier;
-    let parsed_hash = PasswordHash::new(&hashed_password).unwrap();
-    Argon2::default()
-        .verify_password(password.as_bytes(), &parsed_hash)
-        .map_err(|_| ())
-        .map(|_| ())
+    let parsed_hash = PasswordHash::new(&hashed_password)?;
+    Argon2::default().verify_password(password.as_bytes(), &parsed_hash)
 }
@@ -1,7 +1,15 @@
+extern crate self as achiet;
+
 pub mod api;
 pub mod app;
+pub mod archive;
 pub mod database;
+pub mod extract;
 pub mod hash;
+pub mod link;
 pub mod model;
 pub mod repo;
 pub mod taskqueue;
+
+#[cfg(test)]
+mod testutil;
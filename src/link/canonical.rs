@@ -0,0 +1,230 @@
+use serde::Deserialize;
+use std::collections::HashMap;
+use url::{form_urlencoded, Url};
+
+/// Query parameters that only track where a visit came from.
+pub const TRACKING_PARAMS: &[&str] = &[
+    "fbclid", "gclid", "dclid", "msclkid", "mc_cid", "mc_eid", "igshid", "yclid",
+];
+pub const TRACKING_PREFIXES: &[&str] = &["utm_"];
+
+/// Adjustments for one host and its subdomains, on top of the default rules.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HostRule {
+    /// Query parameters dropped as well as the tracking ones.
+    pub strip_params: Vec<String>,
+    /// When set, every query parameter not listed is dropped.
+    pub keep_params: Option<Vec<String>>,
+    /// Drops a leading `www.` from the host.
+    pub strip_www: bool,
+    /// Keeps the trailing slash of paths, for sites that serve `/a/` and `/a`
+    /// as different pages.
+    pub keep_trailing_slash: bool,
+    /// Keeps the fragment, for sites routing on it.
+    pub keep_fragment: bool,
+}
+
+impl HostRule {
+    /// Whether the query parameter `key`, lowercased, is kept.
+    fn keeps_param(&self, key: &str) -> bool {
+        !TRACKING_PARAMS.contains(&key)
+            && !TRACKING_PREFIXES.iter().any(|p| key.starts_with(p))
+            && !self
+                .strip_params
+                .iter()
+                .any(|p| p.eq_ignore_ascii_case(key))
+            && self
+                .keep_params
+                .as_ref()
+                .is_none_or(|keep| keep.iter().any(|p| p.eq_ignore_ascii_case(key)))
+    }
+}
+
+/// Rewrites urls so that links to the same page compare equal: the scheme and
+/// host are lowercased, IDN hosts are punycoded, default ports, tracking
+/// parameters, fragments and trailing slashes are dropped and the query
+/// parameters are sorted by key. The parameters kept are left as written.
+///
+/// The result identifies a page only, it may not be served at all, so the
+/// original url is the one to request.
+#[derive(Debug, Clone, Default)]
+pub struct Canonicalizer {
+    rules: HashMap<String, HostRule>,
+}
+
+impl Canonicalizer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_rule(mut self, host: &str, rule: HostRule) -> Self {
+        self.rules.insert(host.to_lowercase(), rule);
+        self
+    }
+
+    /// Reads the rules of a JSON object keyed by host, e.g.
+    /// `{"youtube.com": {"strip_www": true, "keep_params": ["v", "t"]}}`
+    pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+        let rules = serde_json::from_str::<HashMap<String, HostRule>>(json)?;
+        Ok(rules
+            .into_iter()
+            .fold(Self::new(), |canonicalizer, (host, rule)| {
+                canonicalizer.with_rule(&host, rule)
+            }))
+    }
+
+    /// The rule of the closest domain of `host`, e.g. `example.com` applies to
+    /// `blog.example.com` unless it has a rule of its own.
+    fn rule(&self, host: &str) -> Option<&HostRule> {
+        let mut domain = host;
+        loop {
+            if let Some(rule) = self.rules.get(domain) {
+                return Some(rule);
+            }
+            domain = domain.split_once('.')?.1;
+        }
+    }
+
+    pub fn canonicalize(&self, url: &str) -> Option<Url> {
+        // Parsing lowercases the scheme and host of web urls, converts IDN
+        // hosts to punycode and drops default ports.
+        let mut url = Url::parse(url.trim()).ok()?;
+        let default_rule = HostRule::default();
+        let rule = url
+            .host_str()
+            .and_then(|host| self.rule(host))
+            .unwrap_or(&default_rule);
+
+        if let Some(host) = url.host_str() {
+            let mut canonical_host = host.to_lowercase();
+            if rule.strip_www {
+                if let Some(stripped) = canonical_host.strip_prefix("www.") {
+                    canonical_host = stripped.to_string();
+                }
+            }
+            if canonical_host != host {
+                url.set_host(Some(&canonical_host)).ok()?;
+            }
+        }
+
+        // Parameters are matched by their decoded key but kept as written, as
+        // `?a`, `?a=` and `?a=%20` may well be different pages
+        let query = url.query().map(|query| {
+            let mut params = query
+                .split('&')
+                .filter_map(|param| {
+                    let (key, _) = form_urlencoded::parse(param.as_bytes()).next()?;
+                    Some((key.into_owned(), param))
+                })
+                .filter(|(key, _)| rule.keeps_param(&key.to_lowercase()))
+                .collect::<Vec<_>>();
+            // Stable, so repeated keys keep their order
+            params.sort_by(|(a, _), (b, _)| a.cmp(b));
+            params
+                .into_iter()
+                .map(|(_, param)| param)
+                .collect::<Vec<_>>()
+                .join("&")
+        });
+        url.set_query(query.as_deref().filter(|query| !query.is_empty()));
+
+        if !rule.keep_trailing_slash && url.path().len() > 1 && url.path().ends_with('/') {
+            let path = url.path().trim_end_matches('/').to_string();
+            url.set_path(if path.is_empty() { "/" } else { &path });
+        }
+        if !rule.keep_fragment {
+            url.set_fragment(None);
+        }
+
+        Some(url)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn canonical(canonicalizer: &Canonicalizer, url: &str) -> String {
+        canonicalizer.canonicalize(url).unwrap().to_string()
+    }
+
+    #[test]
+    fn canonicalize() {
+        let canonicalizer = Canonicalizer::new();
+        assert_eq!(
+            canonical(
+                &canonicalizer,
+                "HTTPS://EXAMPLE.com:443/a/?utm_source=x&b=2&a=1#top"
+            ),
+            "https://example.com/a?a=1&b=2",
+        );
+        assert_eq!(
+            canonical(&canonicalizer, "http://example.com:8080/?fbclid=1&gclid=2"),
+            "http://example.com:8080/",
+        );
+        assert_eq!(
+            canonical(&canonicalizer, "https://bücher.example/a//"),
+            "https://xn--bcher-kva.example/a",
+        );
+        assert!(canonicalizer.canonicalize("example.com/a").is_none());
+
+        // Parameters are kept as written, never merged with other spellings
+        assert_eq!(
+            canonical(&canonicalizer, "https://example.com/?q=a%20b&flag&x=2&x=1"),
+            "https://example.com/?flag&q=a%20b&x=2&x=1",
+        );
+        assert_ne!(
+            canonical(&canonicalizer, "https://example.com/?flag"),
+            canonical(&canonicalizer, "https://example.com/?flag="),
+        );
+        assert_ne!(
+            canonical(&canonicalizer, "https://example.com/?q=a+b"),
+            canonical(&canonicalizer, "https://example.com/?q=a%2Bb"),
+        );
+    }
+
+    #[test]
+    fn host_rules() {
+        let canonicalizer = Canonicalizer::new()
+            .with_rule(
+                "youtube.com",
+                HostRule {
+                    keep_params: Some(vec!["v".to_string()]),
+                    strip_www: true,
+                    ..Default::default()
+                },
+            )
+            .with_rule(
+                "app.example.com",
+                HostRule {
+                    strip_params: vec!["session".to_string()],
+                    keep_trailing_slash: true,
+                    keep_fragment: true,
+                    ..Default::default()
+                },
+            );
+        assert_eq!(
+            canonical(
+                &canonicalizer,
+                "https://www.youtube.com/watch?v=abc&t=10&feature=share"
+            ),
+            "https://youtube.com/watch?v=abc",
+        );
+        assert_eq!(
+            canonical(
+                &canonicalizer,
+                "https://app.example.com/a/?session=1&q=x#/inbox"
+            ),
+            "https://app.example.com/a/?q=x#/inbox",
+        );
+        assert_eq!(
+            canonical(&canonicalizer, "https://www.example.com/a/"),
+            "https://www.example.com/a",
+        );
+        assert_eq!(
+            canonical(&canonicalizer, "https://www.www.youtube.com/watch?v=abc"),
+            "https://www.youtube.com/watch?v=abc",
+        );
+    }
+}
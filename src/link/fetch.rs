@@ -0,0 +1,484 @@
+use reqwest::{
+    dns::{Addrs, Name, Resolve, Resolving},
+    header::{CONTENT_TYPE, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED},
+    redirect, Client, RequestBuilder, Response, StatusCode,
+};
+use std::{
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+    sync::Arc,
+    time::Duration,
+};
+use url::{Host, Url};
+
+/// What requests made on behalf of users may reach.
+#[derive(Debug, Clone)]
+pub struct FetchPolicy {
+    pub schemes: Vec<String>,
+    /// Whether loopback, private and link-local addresses may be fetched,
+    /// e.g. for intranet bookmarks
+    pub allow_private: bool,
+    pub max_redirects: usize,
+    /// Bytes of a response body read at most
+    pub max_size: usize,
+    pub timeout: Duration,
+}
+
+impl Default for FetchPolicy {
+    fn default() -> Self {
+        Self {
+            schemes: vec!["http".to_string(), "https".to_string()],
+            allow_private: false,
+            max_redirects: 5,
+            max_size: 10 << 20,
+            timeout: Duration::from_secs(20),
+        }
+    }
+}
+
+impl FetchPolicy {
+    /// Checks the scheme of `url`, and its address when the host is an IP.
+    /// Hostnames are checked once resolved.
+    pub fn check(&self, url: &Url) -> Result<(), FetchError> {
+        if !self.schemes.iter().any(|scheme| scheme == url.scheme()) {
+            return Err(FetchError::Scheme(url.scheme().to_string()));
+        }
+        let ip = match url.host() {
+            None => return Err(FetchError::Url("missing host".to_string())),
+            Some(Host::Domain(_)) => return Ok(()),
+            Some(Host::Ipv4(ip)) => IpAddr::V4(ip),
+            Some(Host::Ipv6(ip)) => IpAddr::V6(ip),
+        };
+        match self.allow_private || is_public(ip) {
+            true => Ok(()),
+            false => Err(FetchError::Address(ip.to_string())),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FetchError {
+    Url(String),
+    Scheme(String),
+    Address(String),
+    TooManyRedirects,
+    TooLarge,
+    Timeout,
+    Status(u16),
+    Request(String),
+}
+
+impl std::fmt::Display for FetchError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Url(msg) => write!(f, "invalid url: {}", msg),
+            Self::Scheme(scheme) => write!(f, "scheme {} is not allowed", scheme),
+            Self::Address(host) => write!(f, "address of {} is not allowed", host),
+            Self::TooManyRedirects => write!(f, "too many redirects"),
+            Self::TooLarge => write!(f, "response too large"),
+            Self::Timeout => write!(f, "timed out"),
+            Self::Status(status) => write!(f, "responded with status {}", status),
+            Self::Request(msg) => write!(f, "request failed: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for FetchError {}
+
+impl From<reqwest::Error> for FetchError {
+    fn from(e: reqwest::Error) -> Self {
+        // Refusals of the resolver and the redirect policy come back as the
+        // source of the request error
+        let mut source = std::error::Error::source(&e);
+        while let Some(error) = source {
+            if let Some(refused) = error.downcast_ref::<FetchError>() {
+                return refused.clone();
+            }
+            source = error.source();
+        }
+        if e.is_timeout() {
+            Self::Timeout
+        } else if let Some(status) = e.status() {
+            Self::Status(status.as_u16())
+        } else {
+            Self::Request(e.to_string())
+        }
+    }
+}
+
+/// Whether `ip` is reachable from the internet at large, as opposed to
+/// loopback, private, link-local and reserved addresses.
+pub fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => is_public_v4(ip),
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_public_v4(ip),
+            None => is_public_v6(ip),
+        },
+    }
+}
+
+fn is_public_v4(ip: Ipv4Addr) -> bool {
+    let [a, b, c, _] = ip.octets();
+    !(ip.is_unspecified()
+        || ip.is_loopback()
+        || ip.is_private()
+        || ip.is_link_local()
+        || ip.is_broadcast()
+        || ip.is_documentation()
+        || ip.is_multicast()
+        || a == 0
+        // Shared address space of carrier-grade NAT
+        || (a == 100 && (64..128).contains(&b))
+        // IETF protocol assignments
+        || (a == 192 && b == 0 && c == 0)
+        // Benchmarking and reserved
+        || (a == 198 && (18..20).contains(&b))
+        || a >= 240)
+}
+
+fn is_public_v6(ip: Ipv6Addr) -> bool {
+    let segments = ip.segments();
+    !(ip.is_unspecified()
+        || ip.is_loopback()
+        || ip.is_multicast()
+        || ip.is_unique_local()
+        || ip.is_unicast_link_local()
+        // Documentation
+        || (segments[0] == 0x2001 && segments[1] == 0xdb8)
+        // IPv4-compatible, NAT64 including its local-use 64:ff9b:1::/48,
+        // Teredo and 6to4, which would reach IPv4 addresses unchecked
+        || segments[..6] == [0; 6]
+        || (segments[0] == 0x64 && segments[1] == 0xff9b)
+        || (segments[0] == 0x2001 && segments[1] == 0)
+        || segments[0] == 0x2002)
+}
+
+/// Resolves hostnames to their public addresses only, so neither the first
+/// request nor a redirect can reach the internal network by name.
+struct PublicResolver;
+
+impl Resolve for PublicResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        Box::pin(async move {
+            let host = name.as_str().to_string();
+            let addrs = tokio::net::lookup_host((host.as_str(), 0))
+                .await?
+                .filter(|addr| is_public(addr.ip()))
+                .collect::<Vec<SocketAddr>>();
+            if addrs.is_empty() {
+                return Err(FetchError::Address(host).into());
+            }
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
+
+/// A response body read in full.
+pub struct Fetched {
+    /// Where the request ended up after redirects
+    pub url: Url,
+    /// The lowercased mime type, without parameters, sniffed from the body
+    /// when the server did not say
+    pub content_type: String,
+    /// The charset parameter of the content type
+    pub charset: Option<String>,
+    /// Validators to revalidate the response with
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+    pub body: Vec<u8>,
+}
+
+/// The mime type of `body` going by its first bytes, for the types handled
+/// beyond HTML.
+fn sniff(body: &[u8]) -> Option<&'static str> {
+    let start = body.trim_ascii_start();
+    let starts_with_ignore_case = |prefix: &[u8]| {
+        start
+            .get(..prefix.len())
+            .is_some_and(|start| start.eq_ignore_ascii_case(prefix))
+    };
+    if body.starts_with(b"%PDF-") {
+        Some("application/pdf")
+    } else if body.starts_with(b"\x89PNG\r\n\x1a\n") {
+        Some("image/png")
+    } else if body.starts_with(b"GIF87a") || body.starts_with(b"GIF89a") {
+        Some("image/gif")
+    } else if body.starts_with(&[0xff, 0xd8, 0xff]) {
+        Some("image/jpeg")
+    } else if body.starts_with(b"RIFF") && body.get(8..12) == Some(b"WEBP") {
+        Some("image/webp")
+    } else if starts_with_ignore_case(b"<!doctype html") || starts_with_ignore_case(b"<html") {
+        Some("text/html")
+    } else {
+        None
+    }
+}
+
+/// Client for requests made on behalf of users, which enforces a
+/// `FetchPolicy` on every request and redirect.
+#[derive(Clone)]
+pub struct Fetcher {
+    client: Client,
+    policy: Arc<FetchPolicy>,
+}
+
+impl Default for Fetcher {
+    fn default() -> Self {
+        Self::new(FetchPolicy::default())
+    }
+}
+
+impl Fetcher {
+    pub fn new(policy: FetchPolicy) -> Self {
+        let policy = Arc::new(policy);
+        let redirect_policy = policy.clone();
+        // A proxy from the environment would resolve hosts itself, past the
+        // address checks of the resolver
+        let mut builder = Client::builder()
+            .no_proxy()
+            .user_agent(concat!("achiet/", env!("CARGO_PKG_VERSION")))
+            .timeout(policy.timeout)
+            .redirect(redirect::Policy::custom(move |attempt| {
+                if attempt.previous().len() > redirect_policy.max_redirects {
+                    return attempt.error(FetchError::TooManyRedirects);
+                }
+                match redirect_policy.check(attempt.url()) {
+                    Ok(()) => attempt.follow(),
+                    Err(e) => attempt.error(e),
+                }
+            }));
+        if !policy.allow_private {
+            builder = builder.dns_resolver(Arc::new(PublicResolver));
+        }
+        Self {
+            client: builder.build().unwrap(),
+            policy,
+        }
+    }
+
+    pub fn policy(&self) -> &FetchPolicy {
+        &self.policy
+    }
+
+    /// Parses `url` and checks it against the policy.
+    pub fn parse(&self, url: &str) -> Result<Url, FetchError> {
+        let url = Url::parse(url).map_err(|e| FetchError::Url(e.to_string()))?;
+        self.policy.check(&url)?;
+        Ok(url)
+    }
+
+    pub fn get(&self, url: &str) -> Result<RequestBuilder, FetchError> {
+        Ok(self.client.get(self.parse(url)?))
+    }
+
+    pub fn head(&self, url: &str) -> Result<RequestBuilder, FetchError> {
+        Ok(self.client.head(self.parse(url)?))
+    }
+
+    /// GETs `url` and reads its body, of `limit` bytes at most and never more
+    /// than the policy allows.
+    pub async fn fetch(&self, url: &str, limit: usize) -> Result<Fetched, FetchError> {
+        let response = self.get(url)?.send().await?.error_for_status()?;
+        self.read(response, limit).await
+    }
+
+    /// Like `fetch`, but conditional on the response having changed since the
+    /// one with the given validators, `None` when it has not.
+    pub async fn fetch_modified(
+        &self,
+        url: &str,
+        limit: usize,
+        etag: Option<&str>,
+        last_modified: Option<&str>,
+    ) -> Result<Option<Fetched>, FetchError> {
+        let mut request = self.get(url)?;
+        if let Some(etag) = etag {
+            request = request.header(IF_NONE_MATCH, etag);
+        }
+        if let Some(last_modified) = last_modified {
+            request = request.header(IF_MODIFIED_SINCE, last_modified);
+        }
+        let response = request.send().await?;
+        if response.status() == StatusCode::NOT_MODIFIED {
+            return Ok(None);
+        }
+        self.read(response.error_for_status()?, limit)
+            .await
+            .map(Some)
+    }
+
+    async fn read(&self, mut response: Response, limit: usize) -> Result<Fetched, FetchError> {
+        let limit = limit.min(self.policy.max_size);
+        if response.content_length().unwrap_or(0) > limit as u64 {
+            return Err(FetchError::TooLarge);
+        }
+        let url = response.url().clone();
+        let header = |name| {
+            response
+                .headers()
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(String::from)
+        };
+        let etag = header(ETAG);
+        let last_modified = header(LAST_MODIFIED);
+        let header = header(CONTENT_TYPE).unwrap_or_default();
+        let mut params = header.split(';');
+        let mut content_type = params.next().unwrap_or_default().trim().to_lowercase();
+        let charset = params
+            .filter_map(|param| param.split_once('='))
+            .find(|(name, _)| name.trim().eq_ignore_ascii_case("charset"))
+            .map(|(_, value)| value.trim().trim_matches('"').to_lowercase());
+        let mut body = Vec::new();
+        while let Some(chunk) = response.chunk().await? {
+            if body.len() + chunk.len() > limit {
+                return Err(FetchError::TooLarge);
+            }
+            body.extend_from_slice(&chunk);
+        }
+        if matches!(content_type.as_str(), "" | "application/octet-stream") {
+            if let Some(sniffed) = sniff(&body) {
+                content_type = sniffed.to_string();
+            }
+        }
+        Ok(Fetched {
+            url,
+            content_type,
+            charset,
+            etag,
+            last_modified,
+            body,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::link::serve_stub;
+
+    #[test]
+    fn public_addresses() {
+        for ip in ["93.184.215.14", "2606:2800:21f:cb07:6820:80da:af6b:8b2c"] {
+            assert!(is_public(ip.parse().unwrap()), "{}", ip);
+        }
+        for ip in [
+            "127.0.0.1",
+            "10.1.2.3",
+            "172.16.0.1",
+            "192.168.1.1",
+            "169.254.169.254",
+            "100.64.0.1",
+            "192.0.0.170",
+            "0.0.0.0",
+            "::1",
+            "::",
+            "fd00::1",
+            "fe80::1",
+            "::ffff:127.0.0.1",
+            "64:ff9b::a00:1",
+            "64:ff9b:1::a00:1",
+            "::7f00:1",
+            "2001:0:4136:e378:8000:63bf:3fff:fdd2",
+            "2002:a9fe:a9fe::1",
+        ] {
+            assert!(!is_public(ip.parse().unwrap()), "{}", ip);
+        }
+    }
+
+    #[test]
+    fn sniff_types() {
+        assert_eq!(sniff(b"%PDF-1.7\n"), Some("application/pdf"));
+        assert_eq!(sniff(b"GIF89a"), Some("image/gif"));
+        assert_eq!(sniff(b"\n  <!DOCTYPE HTML>"), Some("text/html"));
+        assert_eq!(sniff(b"{}"), None);
+    }
+
+    #[tokio::test]
+    async fn fetch_policy() {
+        let base = serve_stub().await;
+        let fetcher = Fetcher::default();
+
+        let refused = |url: &str| fetcher.parse(url).unwrap_err();
+        assert_eq!(
+            refused("file:///etc/passwd"),
+            FetchError::Scheme("file".to_string())
+        );
+        assert_eq!(
+            refused("http://169.254.169.254/latest/meta-data/"),
+            FetchError::Address("169.254.169.254".to_string())
+        );
+        assert_eq!(
+            refused("http://[::ffff:7f00:1]/"),
+            FetchError::Address("::ffff:127.0.0.1".to_string())
+        );
+        assert!(matches!(refused("not a url"), FetchError::Url(_)));
+
+        let error = fetcher
+            .fetch(&format!("{}/ok", base), 1024)
+            .await
+            .err()
+            .unwrap();
+        assert_eq!(error, FetchError::Address("127.0.0.1".to_string()));
+        let error = fetcher
+            .fetch(&base.replace("127.0.0.1", "localhost"), 1024)
+            .await
+            .err()
+            .unwrap();
+        assert_eq!(error, FetchError::Address("localhost".to_string()));
+
+        let fetcher = Fetcher::new(FetchPolicy {
+            allow_private: true,
+            max_redirects: 0,
+            ..Default::default()
+        });
+        let ok = fetcher.fetch(&format!("{}/ok", base), 1024).await.unwrap();
+        assert_eq!(ok.body, b"ok");
+        assert_eq!(ok.content_type, "text/plain");
+        assert_eq!(ok.charset.as_deref(), Some("utf-8"));
+        assert_eq!(
+            fetcher.fetch(&format!("{}/ok", base), 1).await.err(),
+            Some(FetchError::TooLarge)
+        );
+        assert_eq!(
+            fetcher.fetch(&format!("{}/moved", base), 1024).await.err(),
+            Some(FetchError::TooManyRedirects)
+        );
+        assert_eq!(
+            fetcher.fetch(&format!("{}/gone", base), 1024).await.err(),
+            Some(FetchError::Status(404))
+        );
+    }
+
+    #[tokio::test]
+    async fn fetch_modified() {
+        let url = format!("{}/cached", serve_stub().await);
+        let fetcher = Fetcher::new(FetchPolicy {
+            allow_private: true,
+            ..Default::default()
+        });
+
+        let fetched = fetcher
+            .fetch_modified(&url, 1024, None, None)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(fetched.etag.as_deref(), Some("\"v1\""));
+        assert_eq!(
+            fetcher
+                .fetch_modified(&url, 1024, Some("\"v1\""), None)
+                .await
+                .unwrap()
+                .map(|fetched| fetched.body),
+            None
+        );
+        assert_eq!(
+            fetcher
+                .fetch_modified(&url, 1024, Some("\"v0\""), None)
+                .await
+                .unwrap()
+                .map(|fetched| fetched.body),
+            Some(b"cached".to_vec())
+        );
+    }
+}
@@ -0,0 +1,132 @@
+use super::Fetcher;
+use reqwest::StatusCode;
+use url::Url;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LinkStatus {
+    /// `None` when no response came back at all
+    pub status_code: Option<u16>,
+    /// Where the url ended up when it redirected
+    pub final_url: Option<String>,
+}
+
+impl LinkStatus {
+    pub fn is_ok(&self) -> bool {
+        matches!(self.status_code, Some(200..=299))
+    }
+}
+
+/// Requests `url` following redirects. HEAD is tried first and GET only when
+/// it fails or is refused, as servers often answer HEAD with a 4xx even where
+/// GET works. The body is not read.
+pub async fn check(fetcher: &Fetcher, url: &str) -> LinkStatus {
+    let (head, get) = match (fetcher.head(url), fetcher.get(url)) {
+        (Ok(head), Ok(get)) => (head, get),
+        (Err(e), _) | (_, Err(e)) => {
+            log::debug!("link check of {} refused - {}", url, e);
+            return LinkStatus {
+                status_code: None,
+                final_url: None,
+            };
+        }
+    };
+    let response = match head.send().await {
+        Ok(response)
+            if !response.status().is_client_error()
+                && response.status() != StatusCode::NOT_IMPLEMENTED =>
+        {
+            Ok(response)
+        }
+        _ => get.send().await,
+    };
+
+    match response {
+        Ok(response) => {
+            let requested = Url::parse(url).ok();
+            LinkStatus {
+                status_code: Some(response.status().as_u16()),
+                final_url: Some(response.url())
+                    .filter(|final_url| Some(*final_url) != requested.as_ref())
+                    .map(|final_url| final_url.to_string()),
+            }
+        }
+        Err(e) => {
+            log::debug!("link check of {} failed - {}", url, e);
+            LinkStatus {
+                status_code: None,
+                final_url: None,
+            }
+        }
+    }
+}
+
+/// Serves `/ok`, `/moved` redirecting there, `/gone`, and `/get-only` and
+/// `/head-forbidden` which refuse HEAD, on a local port, returning its base
+/// url.
+#[cfg(test)]
+pub(crate) async fn serve_stub() -> String {
+    use axum::{
+        http::{header, HeaderMap},
+        response::{IntoResponse, Redirect},
+        routing::{get, head},
+        Router,
+    };
+
+    let router = Router::new()
+        .route("/ok", get(|| async { "ok" }))
+        .route("/moved", get(|| async { Redirect::permanent("/ok") }))
+        .route("/gone", get(|| async { StatusCode::NOT_FOUND }))
+        .route(
+            "/get-only",
+            head(|| async { StatusCode::METHOD_NOT_ALLOWED }).get(|| async { "ok" }),
+        )
+        .route(
+            "/head-forbidden",
+            head(|| async { StatusCode::FORBIDDEN }).get(|| async { "ok" }),
+        )
+        .route(
+            "/cached",
+            get(|headers: HeaderMap| async move {
+                match headers.get(header::IF_NONE_MATCH) {
+                    Some(etag) if etag == "\"v1\"" => StatusCode::NOT_MODIFIED.into_response(),
+                    _ => ([(header::ETAG, "\"v1\"")], "cached").into_response(),
+                }
+            }),
+        );
+    crate::testutil::serve(router).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::link::FetchPolicy;
+
+    #[tokio::test]
+    async fn check_links() {
+        let base = serve_stub().await;
+        let fetcher = Fetcher::new(FetchPolicy {
+            allow_private: true,
+            ..Default::default()
+        });
+
+        let status = check(&fetcher, &format!("{}/ok", base)).await;
+        assert!(status.is_ok());
+        assert_eq!(status.final_url, None);
+
+        let status = check(&fetcher, &format!("{}/moved", base)).await;
+        assert_eq!(status.status_code, Some(200));
+        assert_eq!(status.final_url, Some(format!("{}/ok", base)));
+
+        let status = check(&fetcher, &format!("{}/gone", base)).await;
+        assert_eq!(status.status_code, Some(404));
+        assert!(!status.is_ok());
+
+        assert!(check(&fetcher, &format!("{}/get-only", base)).await.is_ok());
+        assert!(check(&fetcher, &format!("{}/head-forbidden", base))
+            .await
+            .is_ok());
+
+        let status = check(&fetcher, "http://127.0.0.1:1/").await;
+        assert_eq!(status.status_code, None);
+    }
+}
@@ -0,0 +1,16 @@
+mod canonical;
+mod fetch;
+mod health;
+
+pub use canonical::*;
+pub use fetch::*;
+pub use health::*;
+
+use url::Url;
+
+/// Web urls of `href` relative to `base`, data urls and the like are skipped.
+pub fn resolve(base: &Url, href: &str) -> Option<Url> {
+    base.join(href.trim())
+        .ok()
+        .filter(|url| matches!(url.scheme(), "http" | "https"))
+}
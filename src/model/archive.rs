@@ -0,0 +1,16 @@
+use crate::database::FromRow;
+use chrono::{offset::Utc, DateTime};
+
+pub const ARCHIVE_TABLE: &str = "archive";
+
+/// The offline copy of a resource, the latest one replacing the previous.
+#[derive(Debug, Default, Clone, PartialEq, FromRow)]
+pub struct Archive {
+    pub id: String,
+    pub resource_id: String,
+    /// Where the copy is kept in the archive storage
+    pub storage_key: String,
+    pub content_type: String,
+    pub size: i32,
+    pub created_at: Option<DateTime<Utc>>,
+}
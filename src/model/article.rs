@@ -0,0 +1,25 @@
+use crate::database::FromRow;
+use chrono::{offset::Utc, DateTime};
+
+pub const ARTICLE_TABLE: &str = "article";
+
+/// Reading speed reading times are estimated with.
+pub const WORDS_PER_MINUTE: i32 = 200;
+
+/// The readable content of a resource.
+#[derive(Debug, Default, Clone, PartialEq, FromRow)]
+pub struct Article {
+    pub id: String,
+    pub resource_id: String,
+    pub text: String,
+    /// Sanitized HTML of the content
+    pub html: String,
+    pub word_count: i32,
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+impl Article {
+    pub fn reading_minutes(&self) -> i32 {
+        (self.word_count + WORDS_PER_MINUTE - 1) / WORDS_PER_MINUTE
+    }
+}
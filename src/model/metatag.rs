@@ -0,0 +1,33 @@
+use crate::{database::FromRow, extract::Metatag};
+use chrono::{offset::Utc, DateTime, Duration};
+
+pub const METATAG_TABLE: &str = "metatag";
+
+/// Metadata last fetched for a resource, with the validators of the
+/// response it was extracted from.
+#[derive(Debug, Default, Clone, PartialEq, FromRow)]
+pub struct ResourceMetatag {
+    pub id: String,
+    pub resource_id: String,
+    /// `Metatag` as JSON, `None` when the resource had none
+    pub data: Option<String>,
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+    pub fetched_at: Option<DateTime<Utc>>,
+}
+
+impl ResourceMetatag {
+    pub fn metatag(&self) -> Option<Metatag> {
+        serde_json::from_str(self.data.as_deref()?).ok()
+    }
+
+    pub fn set_metatag(&mut self, metatag: Option<&Metatag>) {
+        self.data = metatag.and_then(|metatag| serde_json::to_string(metatag).ok());
+    }
+
+    /// Whether it was fetched less than `ttl` before `now`.
+    pub fn is_fresh(&self, now: DateTime<Utc>, ttl: Duration) -> bool {
+        self.fetched_at
+            .is_some_and(|fetched_at| now < fetched_at + ttl)
+    }
+}
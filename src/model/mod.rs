@@ -1,9 +1,15 @@
+pub mod archive;
+pub mod article;
 pub mod bookmark;
+pub mod metatag;
 pub mod resource;
 pub mod tag;
 pub mod user;
 
+pub use archive::*;
+pub use article::*;
 pub use bookmark::*;
+pub use metatag::*;
 pub use resource::*;
 pub use tag::*;
 pub use user::*;
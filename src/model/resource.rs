@@ -1,13 +1,86 @@
+use crate::database::FromRow;
 use chrono::{offset::Utc, DateTime};
+use url::Url;
 
-#[derive(Debug, Default, Clone)]
+pub const RESOURCE_TABLE: &str = "resource";
+
+/// Longest canonical url kept as a resource, MySQL only indexes this many
+/// characters of `resource.canonical_url` and would otherwise treat longer
+/// urls as duplicates.
+pub const MAX_URL_LENGTH: usize = 768;
+
+#[derive(Debug, Default, Clone, PartialEq, FromRow)]
 pub struct Resource {
     pub id: String,
+    /// The url as first bookmarked, which is the one requested
     pub url: String,
+    /// What bookmarks of the same page have in common, see `Canonicalizer`
+    pub canonical_url: String,
     pub protocol: String,
     pub host: String,
     pub path: Option<String>,
     pub query: Option<String>,
     pub created_at: Option<DateTime<Utc>>,
     pub updated_at: Option<DateTime<Utc>>,
+    /// Status of the last link check, `None` when the request failed
+    pub status_code: Option<i32>,
+    /// Where the url redirected to when it was last checked
+    pub final_url: Option<String>,
+    pub last_checked_at: Option<DateTime<Utc>>,
+    pub last_ok_at: Option<DateTime<Utc>>,
+    /// When a check was last dispatched, not again until it went stale
+    pub check_queued_at: Option<DateTime<Utc>>,
+    /// Metadata of the page, filled in where empty when it is bookmarked
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub image_url: Option<String>,
+}
+
+impl Resource {
+    /// Splits a canonical url into its parts, `None` when it is too long to be
+    /// indexed.
+    pub fn from_url(url: &Url) -> Option<Self> {
+        let host = match (url.host_str(), url.port()) {
+            (Some(host), Some(port)) => format!("{}:{}", host, port),
+            (host, _) => host.unwrap_or_default().to_string(),
+        };
+        Some(Self {
+            url: url.to_string(),
+            canonical_url: url.to_string(),
+            protocol: url.scheme().to_string(),
+            host,
+            path: Some(url.path().to_string()).filter(|p| !p.is_empty()),
+            query: url.query().map(|q| q.to_string()),
+            ..Default::default()
+        })
+        .filter(|resource| resource.canonical_url.len() <= MAX_URL_LENGTH)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resource_from_url() {
+        let url = Url::parse("https://example.com:8443/a/b?q=1").unwrap();
+        assert_eq!(
+            Resource::from_url(&url),
+            Some(Resource {
+                url: "https://example.com:8443/a/b?q=1".to_string(),
+                canonical_url: "https://example.com:8443/a/b?q=1".to_string(),
+                protocol: "https".to_string(),
+                host: "example.com:8443".to_string(),
+                path: Some("/a/b".to_string()),
+                query: Some("q=1".to_string()),
+                ..Default::default()
+            }),
+        );
+        let url = Url::parse(&format!(
+            "https://example.com/{}",
+            "a".repeat(MAX_URL_LENGTH)
+        ))
+        .unwrap();
+        assert_eq!(Resource::from_url(&url), None);
+    }
 }
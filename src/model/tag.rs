@@ -1,9 +1,9 @@
-use crate::database::Row;
+use crate::database::FromRow;
 use chrono::{offset::Utc, DateTime};
 
 pub const TAG_TABLE: &str = "tag";
 
-#[derive(Debug, Default, Clone)]
+#[derive(Debug, Default, Clone, PartialEq, FromRow)]
 pub struct Tag {
     pub id: String,
     pub path: String,
@@ -20,7 +20,7 @@ pub struct Tag {
 
 impl Tag {
     pub fn from_path(path: String) -> Self {
-        let formatted = path.trim_start_matches("/").trim_end_matches("/");
+        let formatted = path.trim_start_matches('/').trim_end_matches('/');
         let mut splits = formatted.split("/").collect::<Vec<_>>();
         let depth = splits.len();
         let name = splits.pop().unwrap_or(formatted);
@@ -28,7 +28,7 @@ impl Tag {
 
         Self {
             id: String::new(),
-            path: "/".to_string() + &formatted,
+            path: "/".to_string() + formatted,
             prefix: "/".to_string() + &prefix,
             name: name.to_string(),
             depth: u32::try_from(depth).unwrap(),
@@ -37,38 +37,6 @@ impl Tag {
     }
 }
 
-impl TryFrom<Row> for Tag {
-    type Error = ();
-
-    fn try_from(row: Row) -> Result<Self, Self::Error> {
-        Ok(Self {
-            id: row.try_get("id".into()).unwrap().try_into().unwrap(),
-            path: row.try_get("path".into()).unwrap().try_into().unwrap(),
-            prefix: row.try_get("prefix".into()).unwrap().try_into().unwrap(),
-            name: row.try_get("name".into()).unwrap().try_into().unwrap(),
-            label: row.try_get("label".into()).unwrap().try_into().unwrap(),
-            parent_id: row.try_get("parent_id".into()).unwrap().try_into().unwrap(),
-            depth: row.try_get("depth".into()).unwrap().try_into().unwrap(),
-            value_type: row
-                .try_get("value_type".into())
-                .unwrap()
-                .try_into()
-                .unwrap(),
-            user_id: row.try_get("user_id".into()).unwrap().try_into().unwrap(),
-            created_at: row
-                .try_get("created_at".into())
-                .unwrap()
-                .try_into()
-                .unwrap(),
-            updated_at: row
-                .try_get("updated_at".into())
-                .unwrap()
-                .try_into()
-                .unwrap(),
-        })
-    }
-}
-
 #[derive(Debug, Clone)]
 pub enum TaggedType {
     Bookmark,
@@ -78,12 +46,11 @@ impl TaggedType {
     pub fn table<'a>(&self) -> &'a str {
         match self {
             TaggedType::Bookmark => "tagged_bookmark",
-            _ => "",
         }
     }
 }
 
-#[derive(Debug, Default, Clone)]
+#[derive(Debug, Default, Clone, FromRow)]
 pub struct TaggedItem {
     pub id: String,
     pub ref_id: String,
@@ -91,19 +58,6 @@ pub struct TaggedItem {
     pub value: Option<String>,
 }
 
-impl TryFrom<Row> for TaggedItem {
-    type Error = ();
-
-    fn try_from(row: Row) -> Result<Self, Self::Error> {
-        Ok(Self {
-            id: row.try_get("id".into()).unwrap().try_into().unwrap(),
-            ref_id: row.try_get("ref_id".into()).unwrap().try_into().unwrap(),
-            tag_id: row.try_get("tag_id".into()).unwrap().try_into().unwrap(),
-            value: row.try_get("value".into()).unwrap().try_into().unwrap(),
-        })
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
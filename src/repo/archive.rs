@@ -0,0 +1,50 @@
+use crate::{
+    app::AppState,
+    database::{Connection, Error, FromRow},
+    model::{Archive, ARCHIVE_TABLE},
+};
+
+pub async fn find_archive(
+    app_state: &AppState,
+    conn: &impl Connection,
+    resource_id: String,
+) -> Result<Archive, Error> {
+    let mut query = app_state.new_query();
+    query
+        .push_str("SELECT * FROM ")
+        .push_identifier(ARCHIVE_TABLE)
+        .push_str(" WHERE ")
+        .push_eq("resource_id", resource_id.into());
+    Archive::from_row(&conn.fetch_one(query).await?)
+}
+
+/// Records `archive` as the copy of its resource, replacing any earlier one.
+pub async fn upsert_archive(
+    app_state: &AppState,
+    conn: &impl Connection,
+    archive: Archive,
+) -> Result<(), Error> {
+    let mut query = app_state.new_query();
+    query.push_upsert(
+        ARCHIVE_TABLE,
+        &[
+            "id",
+            "resource_id",
+            "storage_key",
+            "content_type",
+            "size",
+            "created_at",
+        ],
+        &["resource_id"],
+        &["storage_key", "content_type", "size", "created_at"],
+        vec![vec![
+            archive.id.into(),
+            archive.resource_id.into(),
+            archive.storage_key.into(),
+            archive.content_type.into(),
+            archive.size.into(),
+            archive.created_at.into(),
+        ]],
+    );
+    conn.execute(query).await
+}
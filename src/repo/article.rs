@@ -0,0 +1,51 @@
+use crate::{
+    app::AppState,
+    database::{Connection, Error, FromRow},
+    model::{Article, ARTICLE_TABLE},
+};
+
+pub async fn find_article(
+    app_state: &AppState,
+    conn: &impl Connection,
+    resource_id: String,
+) -> Result<Article, Error> {
+    let mut query = app_state.new_query();
+    query
+        .push_str("SELECT * FROM ")
+        .push_identifier(ARTICLE_TABLE)
+        .push_str(" WHERE ")
+        .push_eq("resource_id", resource_id.into());
+    Article::from_row(&conn.fetch_one(query).await?)
+}
+
+/// Records `article` as the content of its resource, replacing any earlier
+/// one.
+pub async fn upsert_article(
+    app_state: &AppState,
+    conn: &impl Connection,
+    article: Article,
+) -> Result<(), Error> {
+    let mut query = app_state.new_query();
+    query.push_upsert(
+        ARTICLE_TABLE,
+        &[
+            "id",
+            "resource_id",
+            "text",
+            "html",
+            "word_count",
+            "created_at",
+        ],
+        &["resource_id"],
+        &["text", "html", "word_count", "created_at"],
+        vec![vec![
+            article.id.into(),
+            article.resource_id.into(),
+            article.text.into(),
+            article.html.into(),
+            article.word_count.into(),
+            article.created_at.into(),
+        ]],
+    );
+    conn.execute(query).await
+}
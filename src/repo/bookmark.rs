@@ -0,0 +1,103 @@
+use crate::{
+    app::{util, AppState},
+    database::{Connection, Error},
+    extract::Metatag,
+    model::BOOKMARK_TABLE,
+    repo,
+};
+
+/// Sets the title, description and preview image of a bookmark from `metatag`
+/// where they are empty, as long as it still points to `url`. Values set by
+/// the user in the meantime are kept.
+pub async fn fill_bookmark_metadata(
+    app_state: &AppState,
+    conn: &impl Connection,
+    bookmark_id: String,
+    url: String,
+    metatag: Metatag,
+) -> Result<(), Error> {
+    let mut values = app_state.new_query();
+    values.set_separator(", ");
+    repo::metatag::push_missing_metadata(&mut values, &metatag);
+    if values.is_empty() {
+        return Ok(());
+    }
+    values.push_eq("updated_at", util::now().into());
+
+    let mut query = app_state.new_query();
+    query
+        .push_str("UPDATE ")
+        .push_identifier(BOOKMARK_TABLE)
+        .push_str(" SET ")
+        .append(values)
+        .push_str(" WHERE ")
+        .push_eq("id", bookmark_id.into())
+        .push_str(" AND ")
+        .push_eq("url", url.into());
+    conn.execute(query).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{database::FromRow, model::Bookmark, testutil};
+
+    #[tokio::test]
+    async fn fill_metadata() {
+        let (app_state, _worker) = testutil::app_state().await;
+        let db = app_state.database().clone();
+        testutil::create_user(&app_state, "u").await;
+        Connection::execute(
+            &db,
+            "INSERT INTO bookmark (id, user_id, title, url, description) VALUES ('b', 'u', 'Mine', 'https://example.com/', '')".into(),
+        )
+        .await
+        .unwrap();
+        let metatag = Metatag {
+            title: Some("Theirs".to_string()),
+            description: Some("Found".to_string()),
+            image_url: Some("https://example.com/a.png".to_string()),
+            ..Default::default()
+        };
+        let find = || async {
+            Bookmark::from_row(
+                &Connection::fetch_one(&db, "SELECT * FROM bookmark".into())
+                    .await
+                    .unwrap(),
+            )
+            .unwrap()
+        };
+
+        let moved = Metatag {
+            title: Some("Moved".to_string()),
+            ..Default::default()
+        };
+        fill_bookmark_metadata(
+            &app_state,
+            &db,
+            "b".into(),
+            "https://example.com/a".into(),
+            moved,
+        )
+        .await
+        .unwrap();
+        assert_eq!(find().await.title.as_deref(), Some("Mine"));
+
+        fill_bookmark_metadata(
+            &app_state,
+            &db,
+            "b".into(),
+            "https://example.com/".into(),
+            metatag,
+        )
+        .await
+        .unwrap();
+        let bookmark = find().await;
+        assert_eq!(bookmark.title.as_deref(), Some("Mine"));
+        assert_eq!(bookmark.description.as_deref(), Some("Found"));
+        assert_eq!(
+            bookmark.image_url.as_deref(),
+            Some("https://example.com/a.png")
+        );
+    }
+}
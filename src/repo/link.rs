@@ -0,0 +1,158 @@
+use crate::{
+    app::{util, AppState},
+    database::{Connection, Error, FromRow, Value},
+    link::LinkStatus,
+    model::{Resource, BOOKMARK_TABLE, RESOURCE_TABLE},
+    repo::page::{self, Cursor},
+};
+use chrono::{offset::Utc, DateTime};
+use serde::Deserialize;
+
+/// Bookmarked resources never checked or last checked before
+/// `checked_before`, the stalest first. Resources whose check was queued since
+/// are left out.
+pub async fn find_resources_to_check(
+    app_state: &AppState,
+    conn: &impl Connection,
+    checked_before: DateTime<Utc>,
+    limit: u32,
+) -> Result<Vec<Resource>, Error> {
+    let mut query = app_state.new_query();
+    query
+        .push_str("SELECT * FROM ")
+        .push_identifier(RESOURCE_TABLE)
+        .push_str(" WHERE (last_checked_at IS NULL OR last_checked_at < ?)")
+        .bind(checked_before.into())
+        .push_str(" AND (check_queued_at IS NULL OR check_queued_at < ?)")
+        .bind(checked_before.into())
+        .push_str(" AND id IN (SELECT resource_id FROM ")
+        .push_identifier(BOOKMARK_TABLE)
+        .push_str(")")
+        .push_str(" ORDER BY CASE WHEN last_checked_at IS NULL THEN 0 ELSE 1 END, last_checked_at")
+        .push_str(" LIMIT ?")
+        .bind(limit.into());
+
+    conn.fetch(query)
+        .await?
+        .iter()
+        .map(Resource::from_row)
+        .collect()
+}
+
+/// Records that checks of `resource_ids` were queued at `queued_at`.
+pub async fn mark_link_checks_queued(
+    app_state: &AppState,
+    conn: &impl Connection,
+    resource_ids: Vec<String>,
+    queued_at: DateTime<Utc>,
+) -> Result<(), Error> {
+    if resource_ids.is_empty() {
+        return Ok(());
+    }
+    let mut query = app_state.new_query();
+    query
+        .push_str("UPDATE ")
+        .push_identifier(RESOURCE_TABLE)
+        .push_str(" SET ")
+        .push_eq("check_queued_at", queued_at.into())
+        .push_str(" WHERE ")
+        .push_in("id", resource_ids.into_iter().map(Value::from).collect());
+    conn.execute(query).await
+}
+
+pub async fn update_link_status(
+    app_state: &AppState,
+    conn: &impl Connection,
+    resource_id: String,
+    status: &LinkStatus,
+) -> Result<(), Error> {
+    let now = util::now();
+    let mut values = app_state.new_query();
+    values.set_separator(", ");
+    values
+        .push_eq("status_code", status.status_code.map(i32::from).into())
+        .push_eq("final_url", status.final_url.clone().into())
+        .push_eq("last_checked_at", now.into());
+    if status.is_ok() {
+        values.push_eq("last_ok_at", now.into());
+    }
+
+    let mut query = app_state.new_query();
+    query
+        .push_str("UPDATE ")
+        .push_identifier(RESOURCE_TABLE)
+        .push_str(" SET ")
+        .append(values)
+        .push_str(" WHERE ")
+        .push_eq("id", resource_id.into());
+    conn.execute(query).await
+}
+
+#[derive(Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum LinkState {
+    /// The last check failed or got an error status
+    Broken,
+    /// The last check succeeded through a redirect
+    Redirected,
+}
+
+#[derive(Default)]
+pub struct SearchLink {
+    pub user_id: Option<String>,
+    pub state: Option<LinkState>,
+    pub limit: Option<u32>,
+    pub cursor: Option<Cursor>,
+}
+
+/// A bookmark with the outcome of the last check of its resource.
+#[derive(Debug, Clone, FromRow)]
+pub struct BookmarkLink {
+    pub id: String,
+    pub title: Option<String>,
+    pub url: Option<String>,
+    pub status_code: Option<i32>,
+    pub final_url: Option<String>,
+    pub last_checked_at: Option<DateTime<Utc>>,
+    pub last_ok_at: Option<DateTime<Utc>>,
+}
+
+/// Checked bookmarks which are broken or redirected, or both when no `state`
+/// is given.
+pub async fn find_bookmark_links(
+    app_state: &AppState,
+    conn: &impl Connection,
+    search_params: SearchLink,
+) -> Result<Vec<BookmarkLink>, Error> {
+    const BROKEN: &str = "(status_code IS NULL OR status_code >= 400)";
+    const REDIRECTED: &str = "(status_code < 400 AND final_url IS NOT NULL)";
+
+    let mut filters = app_state.new_query();
+    filters.set_separator(" AND ");
+    filters.push_str("last_checked_at IS NOT NULL");
+    if let Some(user_id) = search_params.user_id {
+        filters.push_eq("user_id", user_id.into());
+    }
+    filters.push_str(&match search_params.state {
+        Some(LinkState::Broken) => BROKEN.to_string(),
+        Some(LinkState::Redirected) => REDIRECTED.to_string(),
+        None => format!("({} OR {})", BROKEN, REDIRECTED),
+    });
+    page::push_cursor(&mut filters, search_params.cursor);
+
+    let mut query = app_state.new_query();
+    query
+        .push_str("SELECT * FROM (SELECT b.id, b.user_id, b.title, b.url, r.status_code, r.final_url, r.last_checked_at, r.last_ok_at FROM ")
+        .push_identifier(BOOKMARK_TABLE)
+        .push_str(" b JOIN ")
+        .push_identifier(RESOURCE_TABLE)
+        .push_str(" r ON r.id = b.resource_id) link WHERE ")
+        .append(filters);
+    page::push_limit(&mut query, search_params.limit);
+
+    conn.fetch(query)
+        .await?
+        .iter()
+        .map(BookmarkLink::from_row)
+        .collect()
+}
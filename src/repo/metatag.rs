@@ -0,0 +1,75 @@
+use crate::{
+    app::AppState,
+    database::{Connection, Error, FromRow, Query},
+    extract::Metatag,
+    model::{ResourceMetatag, METATAG_TABLE},
+};
+
+/// Appends assignments of the title, description and preview image of
+/// `metatag` to the columns of the same names which are empty, cut to the
+/// size of those columns.
+pub fn push_missing_metadata(values: &mut Query, metatag: &Metatag) {
+    let title = metatag
+        .title
+        .as_ref()
+        .map(|title| title.chars().take(250).collect::<String>());
+    let image_url = metatag.image_url.clone().filter(|url| url.len() <= 1000);
+    for (column, value) in [
+        ("title", title),
+        ("description", metatag.description.clone()),
+        ("image_url", image_url),
+    ] {
+        if let Some(value) = value {
+            let column = values.dialect().quote_identifier(column);
+            values
+                .push_str(&format!("{0} = COALESCE(NULLIF({0}, ''), ?)", column))
+                .bind(value.into());
+        }
+    }
+}
+
+pub async fn find_metatag(
+    app_state: &AppState,
+    conn: &impl Connection,
+    resource_id: String,
+) -> Result<ResourceMetatag, Error> {
+    let mut query = app_state.new_query();
+    query
+        .push_str("SELECT * FROM ")
+        .push_identifier(METATAG_TABLE)
+        .push_str(" WHERE ")
+        .push_eq("resource_id", resource_id.into());
+    ResourceMetatag::from_row(&conn.fetch_one(query).await?)
+}
+
+/// Records `metatag` as the metadata of its resource, replacing any earlier
+/// one.
+pub async fn upsert_metatag(
+    app_state: &AppState,
+    conn: &impl Connection,
+    metatag: ResourceMetatag,
+) -> Result<(), Error> {
+    let mut query = app_state.new_query();
+    query.push_upsert(
+        METATAG_TABLE,
+        &[
+            "id",
+            "resource_id",
+            "data",
+            "etag",
+            "last_modified",
+            "fetched_at",
+        ],
+        &["resource_id"],
+        &["data", "etag", "last_modified", "fetched_at"],
+        vec![vec![
+            metatag.id.into(),
+            metatag.resource_id.into(),
+            metatag.data.into(),
+            metatag.etag.into(),
+            metatag.last_modified.into(),
+            metatag.fetched_at.into(),
+        ]],
+    );
+    conn.execute(query).await
+}
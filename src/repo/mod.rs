@@ -1 +1,8 @@
+pub mod archive;
+pub mod article;
+pub mod bookmark;
+pub mod link;
+pub mod metatag;
+pub mod page;
+pub mod resource;
 pub mod tag;
@@ -0,0 +1,109 @@
+use crate::database::Query;
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+pub const DEFAULT_LIMIT: u32 = 50;
+pub const MAX_LIMIT: u32 = 500;
+
+/// Opaque position in a listing, the id of the last row of the previous page.
+/// Ids are ULIDs, so id order is creation order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cursor(String);
+
+impl Cursor {
+    pub fn new(id: impl Into<String>) -> Self {
+        Self(id.into())
+    }
+
+    pub fn id(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Serialize for Cursor {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&URL_SAFE_NO_PAD.encode(&self.0))
+    }
+}
+
+impl<'de> Deserialize<'de> for Cursor {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        URL_SAFE_NO_PAD
+            .decode(s)
+            .ok()
+            .and_then(|id| String::from_utf8(id).ok())
+            .map(Self)
+            .ok_or_else(|| de::Error::custom("invalid cursor"))
+    }
+}
+
+/// Page size for a listing, `DEFAULT_LIMIT` unless requested otherwise.
+pub fn limit(requested: Option<u32>) -> u32 {
+    requested.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT)
+}
+
+pub fn push_cursor(filters: &mut Query, cursor: Option<Cursor>) {
+    if let Some(cursor) = cursor {
+        let id = filters.dialect().quote_identifier("id");
+        filters
+            .push_str(&format!("{} > ?", id))
+            .bind(cursor.0.into());
+    }
+}
+
+/// Orders `query` by id and, with a `limit`, reads one row past it so that
+/// `Page::new` can tell whether another page follows.
+pub fn push_limit(query: &mut Query, limit: Option<u32>) {
+    query.push_str(" ORDER BY ").push_identifier("id");
+    if let Some(limit) = limit {
+        query.push_str(" LIMIT ?").bind((limit + 1).into());
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub next_cursor: Option<Cursor>,
+}
+
+impl<T> Page<T> {
+    /// Builds a page from rows read with `push_limit`.
+    pub fn new(mut items: Vec<T>, limit: u32, id: impl Fn(&T) -> &str) -> Self {
+        let limit = limit as usize;
+        let next_cursor = if items.len() > limit {
+            items.truncate(limit);
+            items.last().map(|item| Cursor::new(id(item)))
+        } else {
+            None
+        };
+        Self { items, next_cursor }
+    }
+
+    pub fn map<U>(self, f: impl FnMut(T) -> U) -> Page<U> {
+        Page {
+            items: self.items.into_iter().map(f).collect(),
+            next_cursor: self.next_cursor,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn page() {
+        let ids = vec!["01a", "01b", "01c"];
+        let page = Page::new(ids.clone(), 2, |id| id);
+        assert_eq!(page.items, vec!["01a", "01b"]);
+        assert_eq!(page.next_cursor, Some(Cursor::new("01b")));
+        assert!(Page::new(ids, 3, |id| id).next_cursor.is_none());
+
+        let json = serde_json::to_string(&Cursor::new("01b")).unwrap();
+        assert_ne!(json, "\"01b\"");
+        let cursor: Cursor = serde_json::from_str(&json).unwrap();
+        assert_eq!(cursor.id(), "01b");
+        assert!(serde_json::from_str::<Cursor>("\"%%\"").is_err());
+    }
+}
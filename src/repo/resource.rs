@@ -0,0 +1,352 @@
+use crate::{
+    app::{util, AppState},
+    database::{Connection, Error, FromRow, Query},
+    extract::Metatag,
+    model::{Resource, BOOKMARK_TABLE, RESOURCE_TABLE},
+    repo,
+};
+use chrono::{offset::Utc, DateTime};
+use url::Url;
+
+pub async fn find_resource(
+    app_state: &AppState,
+    conn: &impl Connection,
+    id: String,
+) -> Result<Resource, Error> {
+    let mut query = app_state.new_query();
+    query
+        .push_str("SELECT * FROM ")
+        .push_identifier(RESOURCE_TABLE)
+        .push_str(" WHERE ")
+        .push_eq("id", id.into());
+    Resource::from_row(&conn.fetch_one(query).await?)
+}
+
+pub async fn find_resource_by_canonical_url(
+    app_state: &AppState,
+    conn: &impl Connection,
+    canonical_url: String,
+) -> Result<Resource, Error> {
+    let mut query = app_state.new_query();
+    query
+        .push_str("SELECT * FROM ")
+        .push_identifier(RESOURCE_TABLE)
+        .push_str(" WHERE ")
+        .push_eq("canonical_url", canonical_url.into());
+    Resource::from_row(&conn.fetch_one(query).await?)
+}
+
+/// The stored resource of the canonical form of `url`, if it was ever
+/// bookmarked.
+pub async fn find_resource_of_url(
+    app_state: &AppState,
+    conn: &impl Connection,
+    url: &str,
+) -> Result<Option<Resource>, Error> {
+    let Some(url) = app_state.canonicalizer().canonicalize(url) else {
+        return Ok(None);
+    };
+    match find_resource_by_canonical_url(app_state, conn, url.to_string()).await {
+        Ok(resource) => Ok(Some(resource)),
+        Err(Error::NotFound) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// Returns the resource shared by every bookmark of the canonical form of
+/// `url`, creating it with `url` to request when it is new. Urls that do not
+/// parse have no resource.
+pub async fn upsert_resource(
+    app_state: &AppState,
+    conn: &impl Connection,
+    url: &str,
+) -> Result<Option<Resource>, Error> {
+    let Some((url, input)) = Url::parse(url.trim()).ok().and_then(|parsed| {
+        let canonical = app_state.canonicalizer().canonicalize(url)?;
+        Some((parsed.to_string(), Resource::from_url(&canonical)?))
+    }) else {
+        return Ok(None);
+    };
+    let resource = Resource {
+        id: util::new_uid(),
+        url,
+        created_at: Some(util::now()),
+        updated_at: Some(util::now()),
+        ..input
+    };
+
+    let mut query = app_state.new_query();
+    query.push_upsert(
+        RESOURCE_TABLE,
+        &[
+            "id",
+            "url",
+            "canonical_url",
+            "protocol",
+            "host",
+            "path",
+            "query",
+            "created_at",
+            "updated_at",
+        ],
+        &["canonical_url"],
+        &[],
+        vec![vec![
+            resource.id.into(),
+            resource.url.into(),
+            resource.canonical_url.clone().into(),
+            resource.protocol.into(),
+            resource.host.into(),
+            resource.path.into(),
+            resource.query.into(),
+            resource.created_at.into(),
+            resource.updated_at.into(),
+        ]],
+    );
+    conn.execute(query).await?;
+
+    find_resource_by_canonical_url(app_state, conn, resource.canonical_url)
+        .await
+        .map(Some)
+}
+
+/// Sets the title, description and preview image of a resource from `metatag`
+/// where they are empty.
+pub async fn fill_resource_metadata(
+    app_state: &AppState,
+    conn: &impl Connection,
+    resource_id: String,
+    metatag: &Metatag,
+) -> Result<(), Error> {
+    let mut values = app_state.new_query();
+    values.set_separator(", ");
+    repo::metatag::push_missing_metadata(&mut values, metatag);
+    if values.is_empty() {
+        return Ok(());
+    }
+    values.push_eq("updated_at", util::now().into());
+
+    let mut query = app_state.new_query();
+    query
+        .push_str("UPDATE ")
+        .push_identifier(RESOURCE_TABLE)
+        .push_str(" SET ")
+        .append(values)
+        .push_str(" WHERE ")
+        .push_eq("id", resource_id.into());
+    conn.execute(query).await
+}
+
+/// Appends a filter on the `host` column, `*.example.com` also matching every
+/// subdomain of `example.com`. The column holds `host:port` on non-default
+/// ports, which a filter without a port matches too.
+pub fn push_host_filter(filters: &mut Query, host: &str) {
+    let host = host.to_lowercase();
+    let (domain, subdomains) = match host.strip_prefix("*.") {
+        Some(domain) => (domain, true),
+        None => (host.as_str(), false),
+    };
+    let column = filters.dialect().quote_identifier("host");
+    let like = format!("{} LIKE ? ESCAPE '!'", column);
+    let escaped = domain
+        .replace('!', "!!")
+        .replace('%', "!%")
+        .replace('_', "!_");
+    let mut patterns = vec![format!("{}:%", escaped)];
+    if subdomains {
+        patterns.push(format!("%.{}", escaped));
+        patterns.push(format!("%.{}:%", escaped));
+    }
+
+    let mut matches = Query::with_dialect(filters.dialect());
+    matches.set_separator(" OR ");
+    matches.push_eq("host", domain.into());
+    for pattern in patterns {
+        matches.push_str(&like).bind(pattern.into());
+    }
+    let mut filter = Query::with_dialect(filters.dialect());
+    filter.push_str("(").append(matches).push_str(")");
+    filters.append(filter);
+}
+
+#[derive(Debug, Clone, FromRow)]
+pub struct HostSummary {
+    pub host: String,
+    pub bookmark_count: i32,
+    pub last_saved_at: Option<DateTime<Utc>>,
+}
+
+/// Hosts of the bookmarks of `user_id`, most bookmarked first.
+pub async fn find_hosts(
+    app_state: &AppState,
+    conn: &impl Connection,
+    user_id: String,
+    host: Option<String>,
+) -> Result<Vec<HostSummary>, Error> {
+    let mut filters = app_state.new_query();
+    filters.set_separator(" AND ");
+    filters.push_eq("b.user_id", user_id.into());
+    if let Some(host) = host {
+        push_host_filter(&mut filters, &host);
+    }
+
+    let mut query = app_state.new_query();
+    let dialect = query.dialect();
+    let quote = |column: &str| dialect.quote_column(column);
+    query
+        .push_str(&format!(
+            "SELECT {}, COUNT(*) AS {}, MAX({}) AS {} FROM ",
+            quote("r.host"),
+            quote("bookmark_count"),
+            quote("b.created_at"),
+            quote("last_saved_at")
+        ))
+        .push_identifier(BOOKMARK_TABLE)
+        .push_str(" b JOIN ")
+        .push_identifier(RESOURCE_TABLE)
+        .push_str(&format!(
+            " r ON {} = {} WHERE ",
+            quote("r.id"),
+            quote("b.resource_id")
+        ))
+        .append(filters)
+        .push_str(&format!(
+            " GROUP BY {0} ORDER BY {1} DESC, {0}",
+            quote("r.host"),
+            quote("bookmark_count")
+        ));
+
+    conn.fetch(query)
+        .await?
+        .iter()
+        .map(HostSummary::from_row)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{link::Canonicalizer, testutil};
+
+    #[tokio::test]
+    async fn find_hosts_by_filter() {
+        let (app_state, _worker) = testutil::app_state().await;
+        let db = app_state.database().clone();
+        for user_id in ["u", "o"] {
+            testutil::create_user(&app_state, user_id).await;
+        }
+        for (id, user_id, url) in [
+            ("1", "u", "https://example.com/a"),
+            ("2", "u", "https://EXAMPLE.com/b"),
+            ("3", "u", "https://blog.example.com/c"),
+            ("4", "u", "https://badexample.com/d"),
+            ("5", "o", "https://example.com/e"),
+            ("6", "u", "https://example.com:8080/f"),
+        ] {
+            let resource = upsert_resource(&app_state, &db, url)
+                .await
+                .unwrap()
+                .unwrap();
+            let mut query = app_state.new_query();
+            query
+                .push_str("INSERT INTO bookmark (id, user_id, url, resource_id, created_at) VALUES (?, ?, ?, ?, ?)")
+                .bind(id.into())
+                .bind(user_id.into())
+                .bind(url.into())
+                .bind(resource.id.into())
+                .bind(util::now().into());
+            Connection::execute(&db, query).await.unwrap();
+        }
+        let hosts = |host: Option<&str>| {
+            let app_state = app_state.clone();
+            let db = db.clone();
+            let host = host.map(str::to_string);
+            async move {
+                find_hosts(&app_state, &db, "u".to_string(), host)
+                    .await
+                    .unwrap()
+                    .into_iter()
+                    .map(|summary| {
+                        assert!(summary.last_saved_at.is_some());
+                        (summary.host, summary.bookmark_count)
+                    })
+                    .collect::<Vec<_>>()
+            }
+        };
+        let counts = |expected: &[(&str, i32)]| {
+            expected
+                .iter()
+                .map(|(host, count)| (host.to_string(), *count))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            hosts(None).await,
+            counts(&[
+                ("example.com", 2),
+                ("badexample.com", 1),
+                ("blog.example.com", 1),
+                ("example.com:8080", 1)
+            ])
+        );
+        assert_eq!(
+            hosts(Some("Example.com")).await,
+            counts(&[("example.com", 2), ("example.com:8080", 1)])
+        );
+        assert_eq!(
+            hosts(Some("example.com:8080")).await,
+            counts(&[("example.com:8080", 1)])
+        );
+        assert_eq!(
+            hosts(Some("*.example.com")).await,
+            counts(&[
+                ("example.com", 2),
+                ("blog.example.com", 1),
+                ("example.com:8080", 1)
+            ])
+        );
+        assert_eq!(
+            hosts(Some("badexample.com")).await,
+            counts(&[("badexample.com", 1)])
+        );
+        // LIKE wildcards in the filter match only themselves
+        assert_eq!(hosts(Some("exampl_.com")).await, counts(&[]));
+        assert_eq!(hosts(Some("*.%.com")).await, counts(&[]));
+    }
+
+    #[tokio::test]
+    async fn upsert_resource_by_configured_rules() {
+        let (app_state, _worker) = testutil::app_state().await;
+        let db = app_state.database().clone();
+        let canonicalizer = Canonicalizer::from_json(
+            r#"{"YouTube.com": {"strip_www": true, "keep_params": ["v"]}}"#,
+        )
+        .unwrap();
+        let app_state = app_state.with_canonicalizer(canonicalizer);
+
+        let upsert = |url: &'static str| {
+            let app_state = app_state.clone();
+            let db = db.clone();
+            async move {
+                upsert_resource(&app_state, &db, url)
+                    .await
+                    .unwrap()
+                    .unwrap()
+            }
+        };
+        let watch = upsert("https://www.youtube.com/watch?v=abc&list=x").await;
+        let shared = upsert("https://youtube.com/watch?feature=share&v=abc").await;
+        assert_eq!(watch.canonical_url, "https://youtube.com/watch?v=abc");
+        assert_eq!(shared.id, watch.id);
+        // The url first bookmarked is kept to request
+        assert_eq!(shared.url, "https://www.youtube.com/watch?v=abc&list=x");
+
+        let other = upsert("https://www.example.com/watch/?v=abc&list=x").await;
+        assert_eq!(
+            other.canonical_url,
+            "https://www.example.com/watch?list=x&v=abc"
+        );
+        assert_eq!(other.url, "https://www.example.com/watch/?v=abc&list=x");
+        assert!(Canonicalizer::from_json(r#"{"example.com": {"strip": true}}"#).is_err());
+    }
+}
@@ -1,11 +1,11 @@
 use crate::{
     app::{util, AppState},
-    database::Connection,
+    database::{Connection, Error, FromRow, Value},
     model::{Tag, TaggedItem, TaggedType, TAG_TABLE},
+    repo::page::{self, Cursor},
     taskqueue::Task,
 };
-use chrono::{offset::Utc, DateTime};
-use serde::{Deserialize, Serialize};
+use serde::Deserialize;
 use std::collections::HashSet;
 
 #[derive(Default, Deserialize)]
@@ -15,55 +15,49 @@ pub struct SearchTag {
     pub user_id: Option<String>,
     pub parent_id: Option<String>,
     pub depth: Option<u32>,
+    pub limit: Option<u32>,
+    pub cursor: Option<Cursor>,
 }
 
-pub async fn find_tags(app_state: &AppState, search_params: SearchTag) -> Result<Vec<Tag>, ()> {
+pub async fn find_tags(
+    app_state: &AppState,
+    conn: &impl Connection,
+    search_params: SearchTag,
+) -> Result<Vec<Tag>, Error> {
     let mut filters = app_state.new_query();
     filters.set_separator(" AND ");
     if let Some(user_id) = search_params.user_id.clone() {
-        filters.push_str("user_id = ?").bind(user_id.into());
+        filters.push_eq("user_id", user_id.into());
     }
     if let Some(parent_id) = search_params.parent_id.clone() {
-        filters.push_str("parent_id = ?").bind(parent_id.into());
+        filters.push_eq("parent_id", parent_id.into());
     }
-    if let Some(depth) = search_params.depth.clone() {
-        filters.push_str("depth = ?").bind(depth.into());
+    if let Some(depth) = search_params.depth {
+        filters.push_eq("depth", depth.into());
     }
     if let Some(tag_path_vec) = search_params.tag_path_vec.clone() {
-        filters.push_str(&format!(
-            "path IN ({})",
-            vec!["?"; tag_path_vec.len()].join(",")
-        ));
-        tag_path_vec.into_iter().for_each(|path| {
-            filters.bind(path.into());
-        });
+        filters.push_in("path", tag_path_vec.into_iter().map(Value::from).collect());
     }
     if let Some(id_vec) = search_params.id_vec.clone() {
-        filters.push_str(&format!("id IN ({})", vec!["?"; id_vec.len()].join(",")));
-        id_vec.into_iter().for_each(|tag_id| {
-            filters.bind(tag_id.into());
-        });
+        filters.push_in("id", id_vec.into_iter().map(Value::from).collect());
     }
+    page::push_cursor(&mut filters, search_params.cursor);
 
     let mut query = app_state.new_query();
-    query.push_str("SELECT * FROM ").push_str(TAG_TABLE);
+    query.push_str("SELECT * FROM ").push_identifier(TAG_TABLE);
     if !filters.is_empty() {
         query.push_str(" WHERE ").append(filters);
     }
+    page::push_limit(&mut query, search_params.limit);
 
-    app_state
-        .database()
-        .connection()
-        .fetch(query)
-        .await
-        .map_err(|_| ())?
-        .into_iter()
-        .map(|row| Tag::try_from(row))
-        .collect::<Result<Vec<_>, _>>()
-        .map_err(|_| ())
+    conn.fetch(query).await?.iter().map(Tag::from_row).collect()
 }
 
-pub async fn create_tags(app_state: &AppState, tag_inputs: Vec<Tag>) -> Result<Vec<Tag>, ()> {
+pub async fn create_tags(
+    app_state: &AppState,
+    conn: &impl Connection,
+    tag_inputs: Vec<Tag>,
+) -> Result<Vec<Tag>, Error> {
     let mut values = app_state.new_query();
     values.set_separator("), (");
     let tags = tag_inputs
@@ -77,17 +71,17 @@ pub async fn create_tags(app_state: &AppState, tag_inputs: Vec<Tag>) -> Result<V
             };
 
             values
-                .push_str(&vec!["?"; 10].join(","))
+                .push_str(&["?"; 10].join(","))
                 .bind(tag.id.clone().into())
                 .bind(tag.path.clone().into())
                 .bind(tag.prefix.clone().into())
                 .bind(tag.name.clone().into())
-                .bind(tag.depth.clone().into())
+                .bind(tag.depth.into())
                 .bind(tag.parent_id.clone().into())
                 .bind(tag.user_id.clone().into())
                 .bind(tag.value_type.clone().into())
-                .bind(tag.created_at.clone().into())
-                .bind(tag.updated_at.clone().into());
+                .bind(tag.created_at.into())
+                .bind(tag.updated_at.into());
 
             tag
         })
@@ -96,27 +90,42 @@ pub async fn create_tags(app_state: &AppState, tag_inputs: Vec<Tag>) -> Result<V
     let mut query = app_state.new_query();
     query
         .push_str("INSERT INTO ")
-        .push_str(TAG_TABLE)
-        .push_str(" (id, path, prefix, name, depth, parent_id, user_id, value_type, created_at, updated_at) VALUES (")
+        .push_identifier(TAG_TABLE)
+        .push_str(" ")
+        .push_columns(&[
+            "id",
+            "path",
+            "prefix",
+            "name",
+            "depth",
+            "parent_id",
+            "user_id",
+            "value_type",
+            "created_at",
+            "updated_at",
+        ])
+        .push_str(" VALUES (")
         .append(values)
         .push_str(")");
-    app_state
-        .database()
-        .connection()
-        .execute(query)
-        .await
-        .map_err(|_| ())?;
+    conn.execute(query).await?;
 
-    tags.clone().into_iter().for_each(|tag| {
-        let _ = app_state.dispatcher().dispatch(Task::TagUpdated(tag));
-    });
+    let dispatcher = app_state.dispatcher().clone();
+    let updated_tags = tags.clone();
+    conn.after_commit(Box::new(move || {
+        updated_tags.into_iter().for_each(|tag| {
+            let _ = dispatcher.dispatch(Task::TagUpdated(Box::new(tag)));
+        });
+    }));
 
     Ok(tags)
 }
 
-pub async fn update_tags(app_state: &AppState, tag_inputs: Vec<Tag>) -> Result<Vec<Tag>, ()> {
-    let mut values = app_state.new_query();
-    values.set_separator("), (");
+pub async fn update_tags(
+    app_state: &AppState,
+    conn: &impl Connection,
+    tag_inputs: Vec<Tag>,
+) -> Result<Vec<Tag>, Error> {
+    let mut rows = Vec::new();
     let tags = tag_inputs
         .into_iter()
         .map(|input| {
@@ -125,97 +134,97 @@ pub async fn update_tags(app_state: &AppState, tag_inputs: Vec<Tag>) -> Result<V
                 ..input
             };
 
-            values
-                .push_str(&vec!["?"; 10].join(","))
-                .bind(tag.id.clone().into())
-                .bind(tag.path.clone().into())
-                .bind(tag.prefix.clone().into())
-                .bind(tag.name.clone().into())
-                .bind(tag.depth.clone().into())
-                .bind(tag.parent_id.clone().into())
-                .bind(tag.user_id.clone().into())
-                .bind(tag.value_type.clone().into())
-                .bind(tag.created_at.clone().into())
-                .bind(tag.updated_at.clone().into());
+            rows.push(vec![
+                tag.id.clone().into(),
+                tag.path.clone().into(),
+                tag.prefix.clone().into(),
+                tag.name.clone().into(),
+                tag.depth.into(),
+                tag.parent_id.clone().into(),
+                tag.user_id.clone().into(),
+                tag.value_type.clone().into(),
+                tag.created_at.into(),
+                tag.updated_at.into(),
+            ]);
 
             tag
         })
         .collect::<Vec<_>>();
 
     let mut query = app_state.new_query();
-    query
-        .push_str("WITH _data (id, path, prefix, name, depth, parent_id, user_id, value_type, created_at, updated_at) AS ( VALUES (")
-        .append(values)
-        .push_str(")) UPDATE ")
-        .push_str(TAG_TABLE)
-        .push_str(" AS tag SET path = _data.path")
-        .push_str(", prefix = _data.prefix")
-        .push_str(", name = _data.name")
-        .push_str(", depth = _data.depth")
-        .push_str(", parent_id = _data.parent_id")
-        .push_str(", value_type = _data.value_type")
-        .push_str(", updated_at = _data.updated_at")
-        .push_str(" FROM _data")
-        .push_str(" WHERE tag.id = _data.id");
-    app_state
-        .database()
-        .connection()
-        .execute(query)
-        .await
-        .map_err(|_| ())?;
-
-    tags.clone().into_iter().for_each(|tag| {
-        let _ = app_state.dispatcher().dispatch(Task::TagUpdated(tag));
-    });
+    query.push_bulk_update(
+        TAG_TABLE,
+        &[
+            "id",
+            "path",
+            "prefix",
+            "name",
+            "depth",
+            "parent_id",
+            "user_id",
+            "value_type",
+            "created_at",
+            "updated_at",
+        ],
+        &[
+            "path",
+            "prefix",
+            "name",
+            "depth",
+            "parent_id",
+            "value_type",
+            "updated_at",
+        ],
+        rows,
+    );
+    conn.execute(query).await?;
+
+    let dispatcher = app_state.dispatcher().clone();
+    let updated_tags = tags.clone();
+    conn.after_commit(Box::new(move || {
+        updated_tags.into_iter().for_each(|tag| {
+            let _ = dispatcher.dispatch(Task::TagUpdated(Box::new(tag)));
+        });
+    }));
 
     Ok(tags)
 }
 
-pub async fn delete_tags(app_state: &AppState, tag_inputs: Vec<Tag>) -> Result<Vec<Tag>, ()> {
-    let mut values = app_state.new_query();
-    values.set_separator(",");
-    let tags = tag_inputs
-        .into_iter()
-        .map(|input| {
-            values.push_str("?").bind(input.id.clone().into());
-            input
-        })
-        .collect::<Vec<_>>();
-
+pub async fn delete_tags(
+    app_state: &AppState,
+    conn: &impl Connection,
+    tag_inputs: Vec<Tag>,
+) -> Result<Vec<Tag>, Error> {
+    let ids = tag_inputs.iter().map(|tag| tag.id.clone().into()).collect();
     let mut query = app_state.new_query();
     query
         .push_str("DELETE FROM ")
-        .push_str(TAG_TABLE)
-        .push_str(" WHERE id IN (")
-        .append(values)
-        .push_str(")");
-    app_state
-        .database()
-        .connection()
-        .execute(query)
-        .await
-        .map_err(|_| ())?;
+        .push_identifier(TAG_TABLE)
+        .push_str(" WHERE ")
+        .push_in("id", ids);
+    conn.execute(query).await?;
 
-    Ok(tags)
+    Ok(tag_inputs)
 }
 
 pub async fn sync_tags(
     app_state: &AppState,
+    conn: &impl Connection,
     user_id: String,
     tag_inputs: Vec<Tag>,
-) -> Result<Vec<Tag>, ()> {
+) -> Result<Vec<Tag>, Error> {
     let tag_paths_iter = tag_inputs.clone().into_iter().map(|t| t.path);
 
     let mut exist_tags = find_tags(
         app_state,
+        conn,
         SearchTag {
             tag_path_vec: Some(tag_paths_iter.clone().collect()),
             user_id: Some(user_id.clone()),
             ..Default::default()
         },
     )
-    .await
-    .map_err(|_| ())?;
+    .await?;
 
     let exist_set: HashSet<_> = exist_tags.clone().into_iter().map(|t| t.path).collect();
     let input_set: HashSet<_> = tag_paths_iter.clone().collect();
@@ -224,6 +233,7 @@ pub async fn sync_tags(
     let mut new_tags = if new_paths_iter.clone().count() > 0 {
         create_tags(
             app_state,
+            conn,
             new_paths_iter
                 .cloned()
                 .map(|p| Tag {
@@ -232,8 +242,7 @@ pub async fn sync_tags(
                 })
                 .collect(),
         )
-        .await
-        .map_err(|_| ())?
+        .await?
     } else {
         Vec::new()
     };
@@ -250,55 +259,40 @@ pub struct SearchTaggedItem {
 
 pub async fn find_tagged_items(
     app_state: &AppState,
+    conn: &impl Connection,
     tagged_type: TaggedType,
     search_params: SearchTaggedItem,
-) -> Result<Vec<TaggedItem>, ()> {
+) -> Result<Vec<TaggedItem>, Error> {
     let mut filters = app_state.new_query();
     filters.set_separator(" AND ");
     if let Some(tag_id_vec) = search_params.tag_id_vec.clone() {
-        filters.push_str(&format!(
-            "tag_id IN ({})",
-            vec!["?"; tag_id_vec.len()].join(",")
-        ));
-        tag_id_vec.into_iter().for_each(|tag_id| {
-            filters.bind(tag_id.into());
-        });
+        filters.push_in("tag_id", tag_id_vec.into_iter().map(Value::from).collect());
     }
     if let Some(ref_id_vec) = search_params.ref_id_vec.clone() {
-        filters.push_str(&format!(
-            "ref_id IN ({})",
-            vec!["?"; ref_id_vec.len()].join(",")
-        ));
-        ref_id_vec.into_iter().for_each(|ref_id| {
-            filters.bind(ref_id.into());
-        });
+        filters.push_in("ref_id", ref_id_vec.into_iter().map(Value::from).collect());
     }
 
     let mut query = app_state.new_query();
     query
         .push_str("SELECT * FROM ")
-        .push_str(tagged_type.table());
+        .push_identifier(tagged_type.table());
     if !filters.is_empty() {
         query.push_str(" WHERE ").append(filters);
     }
 
-    app_state
-        .database()
-        .connection()
-        .fetch(query)
-        .await
-        .map_err(|_| ())?
-        .into_iter()
-        .map(|row| TaggedItem::try_from(row))
-        .collect::<Result<Vec<_>, _>>()
-        .map_err(|_| ())
+    conn.fetch(query)
+        .await?
+        .iter()
+        .map(TaggedItem::from_row)
+        .collect()
 }
 
 pub async fn create_tagged_items(
     app_state: &AppState,
+    conn: &impl Connection,
     tagged_type: TaggedType,
     item_inputs: Vec<TaggedItem>,
-) -> Result<Vec<TaggedItem>, ()> {
+) -> Result<Vec<TaggedItem>, Error> {
     let mut values = app_state.new_query();
     values.set_separator("), (");
     let tagged_items = item_inputs
@@ -310,7 +304,7 @@ pub async fn create_tagged_items(
             };
 
             values
-                .push_str(&vec!["?"; 4].join(","))
+                .push_str(&["?"; 4].join(","))
                 .bind(item.id.clone().into())
                 .bind(item.ref_id.clone().into())
                 .bind(item.tag_id.clone().into())
@@ -323,97 +317,67 @@ pub async fn create_tagged_items(
     let mut query = app_state.new_query();
     query
         .push_str("INSERT INTO ")
-        .push_str(tagged_type.table())
-        .push_str(" (id, ref_id, tag_id, value) VALUES (")
+        .push_identifier(tagged_type.table())
+        .push_str(" ")
+        .push_columns(&["id", "ref_id", "tag_id", "value"])
+        .push_str(" VALUES (")
         .append(values)
         .push_str(")");
-    app_state
-        .database()
-        .connection()
-        .execute(query)
-        .await
-        .map_err(|_| ())?;
+    conn.execute(query).await?;
 
     Ok(tagged_items)
 }
 
 pub async fn update_tagged_items(
     app_state: &AppState,
+    conn: &impl Connection,
     tagged_type: TaggedType,
     item_inputs: Vec<TaggedItem>,
-) -> Result<Vec<TaggedItem>, ()> {
-    let mut values = app_state.new_query();
-    values.set_separator("), (");
+) -> Result<Vec<TaggedItem>, Error> {
+    let mut rows = Vec::new();
     let tagged_items = item_inputs
         .into_iter()
-        .map(|input| {
-            let item = input.clone();
-            values
-                .push_str(&vec!["?"; 4].join(","))
-                .bind(input.id.clone().into())
-                .bind(input.ref_id.clone().into())
-                .bind(input.tag_id.clone().into())
-                .bind(input.value.clone().into());
-            item
+        .inspect(|input| {
+            rows.push(vec![
+                input.id.clone().into(),
+                input.ref_id.clone().into(),
+                input.tag_id.clone().into(),
+                input.value.clone().into(),
+            ]);
         })
         .collect();
 
     let mut query = app_state.new_query();
-    query
-        .push_str("WITH _data (id, ref_id, tag_id, value) AS ( VALUES (")
-        .append(values)
-        .push_str(")) UPDATE ")
-        .push_str(tagged_type.table())
-        .push_str(" AS tagged SET value = _data.value")
-        .push_str(" FROM _data")
-        .push_str(" WHERE tagged.id = _data.id");
-    app_state
-        .database()
-        .connection()
-        .execute(query)
-        .await
-        .map_err(|_| ())?;
+    query.push_bulk_update(
+        tagged_type.table(),
+        &["id", "ref_id", "tag_id", "value"],
+        &["value"],
+        rows,
+    );
+    conn.execute(query).await?;
 
     Ok(tagged_items)
 }
 
 pub async fn delete_tagged_items(
     app_state: &AppState,
+    conn: &impl Connection,
     tagged_type: TaggedType,
     item_inputs: Vec<TaggedItem>,
-) -> Result<Vec<TaggedItem>, ()> {
-    let mut values = app_state.new_query();
-    values.set_separator("), (");
-    let tagged_items = item_inputs
-        .into_iter()
-        .map(|input| {
-            let item = input.clone();
-            values
-                .push_str(&vec!["?"; 3].join(","))
-                .bind(input.id.clone().into())
-                .bind(input.ref_id.clone().into())
-                .bind(input.tag_id.clone().into());
-            item
-        })
+) -> Result<Vec<TaggedItem>, Error> {
+    let ids = item_inputs
+        .iter()
+        .map(|item| item.id.clone().into())
         .collect();
-
     let mut query = app_state.new_query();
     query
-        .push_str("WITH _data (id, ref_id, tag_id) AS ( VALUES (")
-        .append(values)
-        .push_str(")) DELETE FROM ")
-        .push_str(tagged_type.table())
-        .push_str(" AS tagged")
-        .push_str(" WHERE EXISTS")
-        .push_str(" (SELECT 1 FROM _data WHERE tagged.id = _data.id)");
-    app_state
-        .database()
-        .connection()
-        .execute(query)
-        .await
-        .map_err(|_| ())?;
+        .push_str("DELETE FROM ")
+        .push_identifier(tagged_type.table())
+        .push_str(" WHERE ")
+        .push_in("id", ids);
+    conn.execute(query).await?;
 
-    Ok(tagged_items)
+    Ok(item_inputs)
 }
 
 #[derive(Clone)]
@@ -467,26 +431,28 @@ impl From<TaggedItem> for TaggedItemCmp {
     }
 }
 
-impl Into<TaggedItem> for TaggedItemCmp {
-    fn into(self) -> TaggedItem {
-        TaggedItem {
-            id: self.id,
-            ref_id: self.ref_id,
-            tag_id: self.tag_id,
-            value: self.value,
+impl From<TaggedItemCmp> for TaggedItem {
+    fn from(cmp: TaggedItemCmp) -> Self {
+        Self {
+            id: cmp.id,
+            ref_id: cmp.ref_id,
+            tag_id: cmp.tag_id,
+            value: cmp.value,
         }
     }
 }
 
 pub async fn sync_tagged_items(
     app_state: &AppState,
+    conn: &impl Connection,
     tagged_type: TaggedType,
     item_inputs: Vec<TaggedItem>,
-) -> Result<Vec<TaggedItem>, ()> {
+) -> Result<Vec<TaggedItem>, Error> {
     use itertools::Itertools;
 
     let exist_items = find_tagged_items(
         app_state,
+        conn,
         tagged_type.clone(),
         SearchTaggedItem {
             ref_id_vec: Some(
@@ -499,8 +465,7 @@ pub async fn sync_tagged_items(
             ..Default::default()
         },
     )
-    .await
-    .map_err(|_| ())?;
+    .await?;
 
     let input_set = item_inputs
         .clone()
@@ -511,7 +476,7 @@ pub async fn sync_tagged_items(
     let exist_set = exist_items
         .clone()
         .into_iter()
-        .map(|item| TaggedItemCmp::from(item))
+        .map(TaggedItemCmp::from)
         .collect::<HashSet<_>>();
     let create_items_iter = input_set.difference(&exist_set);
     let update_items_iter = input_set.intersection(&exist_set);
@@ -531,6 +496,7 @@ pub async fn sync_tagged_items(
     if create_items_iter.clone().count() > 0 {
         let mut created_items = create_tagged_items(
             app_state,
+            conn,
             tagged_type.clone(),
             create_items_iter
                 .cloned()
@@ -538,34 +504,33 @@ pub async fn sync_tagged_items(
                 .map(|cmp| cmp.into())
                 .collect::<Vec<_>>(),
         )
-        .await
-        .map_err(|_| ())?;
+        .await?;
         result.append(&mut created_items);
     }
     if update_items_iter.clone().count() > 0 {
-        let mut updated_items = &mut update_tagged_items(
+        let mut updated_items = update_tagged_items(
             app_state,
+            conn,
             tagged_type.clone(),
             update_items_iter
                 .cloned()
                 .map(|cmp| cmp.into())
                 .collect::<Vec<_>>(),
         )
-        .await
-        .map_err(|_| ())?;
+        .await?;
         result.append(&mut updated_items);
     }
     if delete_items_iter.clone().count() > 0 {
         delete_tagged_items(
             app_state,
+            conn,
             tagged_type.clone(),
             delete_items_iter
                 .cloned()
                 .map(|cmp| cmp.into())
                 .collect::<Vec<_>>(),
         )
-        .await
-        .map_err(|_| ())?;
+        .await?;
     }
     Ok(result)
 }
@@ -600,29 +565,30 @@ pub struct TaggedData(pub Tag, pub TaggedItem);
 
 pub async fn find_tagged_data_from_refs(
     app_state: &AppState,
+    conn: &impl Connection,
     tagged_type: TaggedType,
     ref_id_vec: Vec<String>,
-) -> Result<TaggedResult, ()> {
+) -> Result<TaggedResult, Error> {
     let tagged_items = find_tagged_items(
         app_state,
-        TaggedType::Bookmark,
+        conn,
+        tagged_type.clone(),
         SearchTaggedItem {
             ref_id_vec: Some(ref_id_vec),
             ..Default::default()
         },
     )
-    .await
-    .map_err(|_| ())?;
+    .await?;
 
     let tags = find_tags(
         app_state,
+        conn,
         SearchTag {
             id_vec: Some(tagged_items.clone().into_iter().map(|t| t.tag_id).collect()),
             ..Default::default()
         },
     )
-    .await
-    .map_err(|_| ())?;
+    .await?;
 
     Ok(TaggedResult {
         tags,
@@ -633,21 +599,24 @@ pub async fn find_tagged_data_from_refs(
 
 pub async fn sync_tagged_data_from_ref(
     app_state: &AppState,
+    conn: &impl Connection,
     user_id: String,
     tagged_type: TaggedType,
     ref_id: String,
     inputs: Vec<TaggedData>,
-) -> Result<TaggedResult, ()> {
+) -> Result<TaggedResult, Error> {
+    let tx = conn.begin().await?;
     let tags = sync_tags(
         app_state,
+        &tx,
         user_id,
         inputs.clone().into_iter().map(|data| data.0).collect(),
     )
-    .await
-    .map_err(|_| ())?;
+    .await?;
 
     let tagged_items = sync_tagged_items(
         app_state,
+        &tx,
         tagged_type.clone(),
         inputs
             .clone()
@@ -655,14 +624,15 @@ pub async fn sync_tagged_data_from_ref(
             .map(|data| {
                 let tag = tags.iter().find(|t| t.path == data.0.path).unwrap();
                 TaggedItem {
+                    ref_id: ref_id.clone(),
                     tag_id: tag.id.clone(),
                     ..data.1
                 }
             })
             .collect(),
     )
-    .await
-    .map_err(|_| ())?;
+    .await?;
+    tx.commit().await?;
 
     Ok(TaggedResult {
         tags,
@@ -1,4 +1,4 @@
-use super::{task::Task, worker::Worker};
+use super::task::Task;
 
 #[derive(Clone)]
 pub struct Dispatcher {
@@ -10,7 +10,7 @@ impl Dispatcher {
         Self { sx }
     }
 
-    pub fn dispatch(&self, task: Task) -> Result<(), ()> {
-        self.sx.send(task).map_err(|_| ())
+    pub fn dispatch(&self, task: Task) -> Result<(), crossbeam::channel::SendError<Task>> {
+        self.sx.send(task)
     }
 }
@@ -1,8 +1,10 @@
 mod dispatcher;
+mod scheduler;
 mod task;
 mod worker;
 
 pub use dispatcher::*;
+pub use scheduler::*;
 pub use task::*;
 pub use worker::*;
 
@@ -0,0 +1,152 @@
+use super::task::Task;
+use crate::{
+    app::{util, AppState},
+    database::Error,
+    repo,
+};
+use std::time::Duration;
+
+/// Periodically dispatches checks of the links not checked recently.
+#[derive(Debug, Clone)]
+pub struct Scheduler {
+    pub interval: Duration,
+    pub recheck_after: chrono::Duration,
+    /// Links dispatched per interval at most
+    pub batch_size: u32,
+}
+
+impl Default for Scheduler {
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_secs(10 * 60),
+            recheck_after: chrono::Duration::days(1),
+            batch_size: 100,
+        }
+    }
+}
+
+impl Scheduler {
+    pub async fn run(self, app_state: AppState) {
+        let mut ticker = tokio::time::interval(self.interval);
+        loop {
+            ticker.tick().await;
+            if let Err(e) = self.schedule_link_checks(&app_state).await {
+                log::error!("scheduling link checks failed - {}", e);
+            }
+        }
+    }
+
+    /// Dispatches checks of the stalest links and marks them queued, so the
+    /// next tick does not dispatch them again while they wait.
+    pub async fn schedule_link_checks(&self, app_state: &AppState) -> Result<usize, Error> {
+        let conn = app_state.database().connection();
+        let now = util::now();
+        let resources = repo::link::find_resources_to_check(
+            app_state,
+            &conn,
+            now - self.recheck_after,
+            self.batch_size,
+        )
+        .await?;
+        repo::link::mark_link_checks_queued(
+            app_state,
+            &conn,
+            resources.iter().map(|r| r.id.clone()).collect(),
+            now,
+        )
+        .await?;
+
+        let count = resources.len();
+        for resource in resources {
+            let _ = app_state
+                .dispatcher()
+                .dispatch(Task::CheckLink(Box::new(resource)));
+        }
+        Ok(count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        database::{Connection, FromRow},
+        link::{serve_stub, FetchPolicy},
+        model::Resource,
+        testutil,
+    };
+
+    #[tokio::test]
+    async fn check_links() {
+        let base = serve_stub().await;
+        let (app_state, _worker) = testutil::app_state().await;
+        let db = app_state.database().clone();
+        let app_state = app_state.with_fetch_policy(FetchPolicy {
+            allow_private: true,
+            ..Default::default()
+        });
+        testutil::create_user(&app_state, "u").await;
+        for path in ["ok", "moved", "gone", "orphan"] {
+            let url = format!("{}/{}", base, path);
+            let resource = repo::resource::upsert_resource(&app_state, &db, &url)
+                .await
+                .unwrap()
+                .unwrap();
+            if path == "orphan" {
+                continue;
+            }
+            let mut query = app_state.new_query();
+            query
+                .push_str(
+                    "INSERT INTO bookmark (id, user_id, url, resource_id) VALUES (?, ?, ?, ?)",
+                )
+                .bind(path.into())
+                .bind("u".into())
+                .bind(url.into())
+                .bind(resource.id.into());
+            Connection::execute(&db, query).await.unwrap();
+        }
+
+        let scheduler = Scheduler::default();
+        let find_resources = || async {
+            Connection::fetch(&db, "SELECT * FROM resource ORDER BY url".into())
+                .await
+                .unwrap()
+                .iter()
+                .map(Resource::from_row)
+                .collect::<Result<Vec<_>, _>>()
+                .unwrap()
+        };
+        assert_eq!(scheduler.schedule_link_checks(&app_state).await.unwrap(), 3);
+        // Queued checks are not dispatched again before they ran
+        assert_eq!(scheduler.schedule_link_checks(&app_state).await.unwrap(), 0);
+        for resource in find_resources().await {
+            // Resources no bookmark refers to are not checked
+            if resource.url.ends_with("/orphan") {
+                assert!(resource.check_queued_at.is_none());
+                continue;
+            }
+            assert!(resource.check_queued_at.is_some());
+            Task::CheckLink(Box::new(resource))
+                .run(&app_state)
+                .await
+                .unwrap();
+        }
+        assert_eq!(scheduler.schedule_link_checks(&app_state).await.unwrap(), 0);
+
+        let resources = find_resources().await;
+        let statuses = resources
+            .iter()
+            .map(|r| (r.status_code, r.final_url.clone(), r.last_ok_at.is_some()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            statuses,
+            vec![
+                (Some(404), None, false),
+                (Some(200), Some(format!("{}/ok", base)), true),
+                (Some(200), None, true),
+                (None, None, false),
+            ]
+        );
+    }
+}
@@ -1,12 +1,27 @@
-use crate::{api, app::AppState, model, repo};
+use crate::{
+    app::{util, AppState},
+    archive::{self, Storage},
+    database::{Connection, Error},
+    extract, link, model, repo,
+};
 
 pub enum Task {
     Empty,
-    TagUpdated(model::Tag),
+    TagUpdated(Box<model::Tag>),
+    CheckLink(Box<model::Resource>),
+    /// Fills in the title, description and preview image of a bookmark left
+    /// empty by the user, and those of its resource, from its page.
+    EnrichBookmark(Box<model::Bookmark>),
+    /// Archives the page of a resource, unless it already is and `refresh` is
+    /// not set.
+    ArchivePage {
+        resource: Box<model::Resource>,
+        refresh: bool,
+    },
 }
 
 impl Task {
-    pub async fn run(self, app_state: &AppState) -> Result<(), ()> {
+    pub async fn run(self, app_state: &AppState) -> Result<(), Error> {
         match self {
             Task::Empty => {
                 log::debug!("discarded");
@@ -19,7 +34,7 @@ impl Task {
                     return Ok(());
                 }
 
-                let mut splits = tag.path.split("/").collect::<Vec<_>>();
+                let mut splits = tag.path.split('/').collect::<Vec<_>>();
                 let _ = splits.pop();
                 let parent_path = splits.join("/");
                 log::debug!(
@@ -29,28 +44,141 @@ impl Task {
                     parent_path
                 );
 
+                let tx = app_state.database().begin().await?;
                 let parent = repo::tag::sync_tags(
                     app_state,
+                    &tx,
                     tag.user_id.clone(),
                     vec![model::Tag::from_path(parent_path)],
                 )
-                .await
-                .map_err(|_| ())?
+                .await?
                 .remove(0);
 
                 log::debug!("Task::TagUpdated parent of {:?} is {:?}", tag, parent);
                 let _ = repo::tag::update_tags(
                     app_state,
+                    &tx,
                     vec![model::Tag {
                         parent_id: Some(parent.id),
-                        ..tag
+                        ..*tag
                     }],
                 )
-                .await
-                .map_err(|_| ())?;
+                .await?;
+                tx.commit().await?;
 
                 Ok(())
             }
+
+            Task::CheckLink(resource) => {
+                log::info!("Task::CheckLink start - {}", resource.url);
+                let status = link::check(app_state.fetcher(), &resource.url).await;
+                log::debug!("Task::CheckLink {} - {:?}", resource.url, status);
+                repo::link::update_link_status(
+                    app_state,
+                    &app_state.database().connection(),
+                    resource.id,
+                    &status,
+                )
+                .await
+            }
+
+            Task::EnrichBookmark(bookmark) => {
+                let Some(url) = bookmark.url else {
+                    return Ok(());
+                };
+                log::info!("Task::EnrichBookmark start - {}", url);
+                let metatag = match extract::fetch_metatag(app_state, &url, false).await {
+                    Ok(metatag) => metatag,
+                    Err(extract::MetatagError::Database(e)) => return Err(e),
+                    Err(e) => {
+                        log::warn!("Task::EnrichBookmark {} failed - {}", url, e);
+                        return Ok(());
+                    }
+                };
+                log::debug!("Task::EnrichBookmark {} - {:?}", url, metatag);
+                match metatag {
+                    Some(metatag) => {
+                        if let Some(resource_id) = bookmark.resource_id {
+                            repo::resource::fill_resource_metadata(
+                                app_state,
+                                &app_state.database().connection(),
+                                resource_id,
+                                &metatag,
+                            )
+                            .await?;
+                        }
+                        repo::bookmark::fill_bookmark_metadata(
+                            app_state,
+                            &app_state.database().connection(),
+                            bookmark.id,
+                            url,
+                            metatag,
+                        )
+                        .await
+                    }
+                    None => Ok(()),
+                }
+            }
+
+            Task::ArchivePage { resource, refresh } => {
+                log::info!("Task::ArchivePage start - {}", resource.url);
+                let conn = app_state.database().connection();
+                if !refresh {
+                    match repo::archive::find_archive(app_state, &conn, resource.id.clone()).await {
+                        Ok(_) => return Ok(()),
+                        Err(Error::NotFound) => {}
+                        Err(e) => return Err(e),
+                    }
+                }
+
+                let page = match archive::fetch_page(app_state.fetcher(), &resource.url).await {
+                    Ok(page) => page,
+                    Err(e) => {
+                        log::warn!("Task::ArchivePage {} failed - {}", resource.url, e);
+                        return Ok(());
+                    }
+                };
+                let readable = {
+                    let document = scraper::Html::parse_document(&page.html);
+                    extract::readable(&document, &page.base_url(&document))
+                };
+                let html = archive::inline_page(app_state.fetcher(), &page).await;
+                let size = i32::try_from(html.len()).map_err(|_| {
+                    Error::Other(format!("archive of {} is too large", resource.url))
+                })?;
+                let archive = model::Archive {
+                    id: util::new_uid(),
+                    resource_id: resource.id.clone(),
+                    storage_key: format!("{}.html", resource.id),
+                    content_type: "text/html".to_string(),
+                    size,
+                    created_at: Some(util::now()),
+                };
+                app_state
+                    .archive_storage()
+                    .put(&archive.storage_key, html.into_bytes())
+                    .await
+                    .map_err(|e| Error::Other(e.to_string()))?;
+                repo::archive::upsert_archive(app_state, &conn, archive).await?;
+
+                match readable {
+                    Some(readable) => {
+                        let article = model::Article {
+                            id: util::new_uid(),
+                            resource_id: resource.id,
+                            text: readable.text,
+                            html: readable.html,
+                            word_count: readable.word_count as i32,
+                            created_at: Some(util::now()),
+                        };
+                        repo::article::upsert_article(app_state, &conn, article).await
+                    }
+                    None => {
+                        log::debug!("Task::ArchivePage no article in {}", resource.url);
+                        Ok(())
+                    }
+                }
+            }
         }
     }
 }
@@ -13,10 +13,20 @@ impl Worker {
 
     pub async fn work(&self, app_state: AppState) -> Result<(), ()> {
         loop {
-            let _ = match self.rx.recv() {
-                Ok(task) => task.run(&app_state).await,
+            match self.rx.recv() {
+                Ok(task) => {
+                    if let Err(e) = task.run(&app_state).await {
+                        log::error!("task failed - {}", e);
+                    }
+                }
                 Err(_) => return Err(()),
             };
         }
     }
+
+    /// Takes the tasks dispatched so far without running them.
+    #[cfg(test)]
+    pub(crate) fn drain(&self) -> Vec<Task> {
+        self.rx.try_iter().collect()
+    }
 }
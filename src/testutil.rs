@@ -0,0 +1,75 @@
+//! Fixtures shared by the tests.
+
+use crate::{
+    app::{util, AppState},
+    database::{self, Connection, Database, Dialect},
+    model::USER_TABLE,
+    taskqueue::{self, Worker},
+};
+use axum::Router;
+use url::Url;
+
+/// Connects to a new, empty database on the server named by
+/// `TEST_DATABASE_URL`, in a schema of its own on Postgres and a database of
+/// its own on MySQL. Without it, the database is an in-memory SQLite one.
+pub async fn connect() -> Database {
+    let Ok(dsn) = std::env::var("TEST_DATABASE_URL") else {
+        return database::connect("sqlite::memory:".to_string())
+            .await
+            .unwrap();
+    };
+    let server = database::connect(dsn.clone()).await.unwrap();
+    let name = format!("test_{}", util::new_uid().to_lowercase());
+    let mut url = Url::parse(&dsn).unwrap();
+    match server.dialect() {
+        Dialect::Sqlite => return server,
+        Dialect::Postgres => {
+            url.query_pairs_mut()
+                .append_pair("options[search_path]", &name);
+            server.execute(format!("CREATE SCHEMA {}", name).into())
+        }
+        Dialect::MySql => {
+            url.set_path(&name);
+            server.execute(format!("CREATE DATABASE {}", name).into())
+        }
+    }
+    .await
+    .unwrap();
+    database::connect(url.to_string()).await.unwrap()
+}
+
+/// App state over a new database with the migrations applied. Dispatched
+/// tasks wait in the worker until it is run or dropped.
+pub async fn app_state() -> (AppState, Worker) {
+    let db = connect().await;
+    database::migrate(&db).await.unwrap();
+    let (dispatcher, worker) = taskqueue::channel();
+    (AppState::new(db, dispatcher), worker)
+}
+
+/// Adds a user whose id is also its username.
+pub async fn create_user(app_state: &AppState, id: &str) {
+    let mut values = app_state.new_query();
+    values.set_separator(", ");
+    for value in [id, id, "p", "user"] {
+        values.push_str("?").bind(value.into());
+    }
+    let mut query = app_state.new_query();
+    query
+        .push_str("INSERT INTO ")
+        .push_identifier(USER_TABLE)
+        .push_str(" ")
+        .push_columns(&["id", "username", "password", "role"])
+        .push_str(" VALUES (")
+        .append(values)
+        .push_str(")");
+    app_state.database().execute(query).await.unwrap();
+}
+
+/// Serves `router` on a local port, returning its base url.
+pub async fn serve(router: Router) -> String {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+    format!("http://{}", address)
+}